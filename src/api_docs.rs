@@ -4,12 +4,27 @@ use utoipa::openapi::security::HttpAuthScheme;
 use utoipa::openapi::security::HttpBuilder;
 use utoipa::openapi::security::SecurityScheme;
 
-use crate::enums::UserFcmTokenStatus;
+use crate::enums::{
+    AnnouncementStatus, CampaignStatus, DevicePlatform, PriceAlertCondition, TokenProvider,
+    UserFcmTokenStatus, WebhookStatus,
+};
+use crate::models::user_notifications::NotificationMetadata;
+use crate::routes::admin::dto::{
+    AnnouncementDto, CampaignDto, CreateAnnouncementRequestDto, CreateCampaignRequestDto,
+    CreateNotificationTemplateRequestDto, NotificationTemplateDto,
+    PreviewNotificationTemplateRequestDto, PreviewNotificationTemplateResponseDto,
+    UpdateAnnouncementRequestDto, UpdateCampaignRequestDto, UpdateNotificationTemplateRequestDto,
+};
 use crate::routes::notification::dto::{
-    EditNotifPreferenceRequestDto, MarkNotificationAsReadResponseDto, NotifPreferenceResponseDto,
-    NotificationDto,
+    CreatePriceAlertRequestDto, CreateWebhookRequestDto, DeviceDto, EditNotifPreferenceRequestDto,
+    MarkNotificationAsReadResponseDto, NotifPreferenceResponseDto, NotificationDto, PriceAlertDto,
+    RegisterDeviceRequestDto, TelegramChatDto, TelegramLinkCodeDto, TelegramLinkDto,
+    TelegramMessageDto, TelegramUpdateDto, TelegramUserDto, UpdatePriceAlertRequestDto,
+    UpdateWebhookRequestDto, VapidPublicKeyDto, WebPushKeysDto, WebPushSubscriptionDto,
+    WebPushSubscriptionRequestDto, WebhookDeliveryDto, WebhookDto,
 };
 use crate::utils::pagination::PaginationResponseDto;
+use crate::utils::segment::AudienceSegment;
 use crate::utils::structs::NotificationPreferences;
 
 #[derive(OpenApi)]
@@ -22,16 +37,58 @@ use crate::utils::structs::NotificationPreferences;
     components(
         schemas(
             UserFcmTokenStatus,
+            DevicePlatform,
+            TokenProvider,
             NotificationDto,
+            NotificationMetadata,
             MarkNotificationAsReadResponseDto,
             NotifPreferenceResponseDto,
             EditNotifPreferenceRequestDto,
             NotificationPreferences,
             PaginationResponseDto<NotificationDto>,
+            RegisterDeviceRequestDto,
+            DeviceDto,
+            WebhookStatus,
+            CreateWebhookRequestDto,
+            UpdateWebhookRequestDto,
+            WebhookDto,
+            WebhookDeliveryDto,
+            PaginationResponseDto<WebhookDeliveryDto>,
+            PriceAlertCondition,
+            CreatePriceAlertRequestDto,
+            UpdatePriceAlertRequestDto,
+            PriceAlertDto,
+            TelegramLinkCodeDto,
+            TelegramLinkDto,
+            TelegramUpdateDto,
+            TelegramMessageDto,
+            TelegramChatDto,
+            TelegramUserDto,
+            WebPushSubscriptionRequestDto,
+            WebPushKeysDto,
+            WebPushSubscriptionDto,
+            VapidPublicKeyDto,
+            CreateNotificationTemplateRequestDto,
+            UpdateNotificationTemplateRequestDto,
+            NotificationTemplateDto,
+            PreviewNotificationTemplateRequestDto,
+            PreviewNotificationTemplateResponseDto,
+            AnnouncementStatus,
+            AudienceSegment,
+            CreateAnnouncementRequestDto,
+            UpdateAnnouncementRequestDto,
+            AnnouncementDto,
+            PaginationResponseDto<AnnouncementDto>,
+            CampaignStatus,
+            CreateCampaignRequestDto,
+            UpdateCampaignRequestDto,
+            CampaignDto,
+            PaginationResponseDto<CampaignDto>,
         )
     ),
     tags(
         (name = "Notification APIs", description = "Notification management endpoints"),
+        (name = "Admin APIs", description = "Operator endpoints, authenticated with HTTP Basic"),
         (name = "Health", description = "Health check endpoints"),
     )
 )]
@@ -22,7 +22,10 @@ pub async fn create_app() -> eyre::Result<Router> {
         .nest("/health", routes::health::route::create_route())
         .nest(
             "/api/v1",
-            OpenApiRouter::new().nest("/notification", routes::notification::route::create_route()),
+            OpenApiRouter::new()
+                .nest("/notification", routes::notification::route::create_route())
+                .nest("/realtime", routes::realtime::route::create_route())
+                .nest("/admin", routes::admin::route::create_route()),
         )
         .with_state(app_state)
         .split_for_parts();
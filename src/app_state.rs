@@ -1,6 +1,9 @@
 use axum::extract::FromRef;
 use mongodb::Database;
 
+use crate::config::APP_CONFIG;
+use crate::core::cache::redis_emitter::setup_redis_emitter;
+use crate::core::realtime::notification_hub::start_notification_hub;
 use crate::database;
 
 #[derive(Clone)]
@@ -12,6 +15,11 @@ impl AppState {
     pub async fn init() -> eyre::Result<Self> {
         let database = database::connection().await.clone();
 
+        setup_redis_emitter(&APP_CONFIG.redis_url)
+            .await
+            .map_err(|e| eyre::eyre!("Failed to setup redis emitter: {e}"))?;
+        start_notification_hub();
+
         Ok(Self { database })
     }
 }
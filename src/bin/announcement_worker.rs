@@ -0,0 +1,225 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
+use push_notify_service::config::{APP_CONFIG, KafkaConfig};
+use push_notify_service::core::cache::redis_emitter::setup_redis_emitter;
+use push_notify_service::core::kafka_service::producer::{
+    publish_kafka_rmp_messages, setup_kafka_producer,
+};
+use push_notify_service::core::realtime::notification_hub::emit_new_notification;
+use push_notify_service::enums::KafkaTopic;
+use push_notify_service::errors::Error;
+use push_notify_service::loading_preferences::get_user_notification_preferences_batch;
+use push_notify_service::models::accounts::Account;
+use push_notify_service::models::announcements::Announcement;
+use push_notify_service::models::user_notifications::{NotificationMetadata, UserNotification};
+use push_notify_service::utils::models::ModelExt;
+use push_notify_service::utils::structs::{
+    AnnouncementNotifData, NotifMessage, NotifMetadata, NotifType,
+};
+use push_notify_service::utils::tracing::init_standard_tracing;
+use wither::bson::DateTime;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    init_standard_tracing(env!("CARGO_CRATE_NAME"));
+
+    let kafka_config = KafkaConfig {
+        kafka_group_id: APP_CONFIG.kafka_group_id.clone(),
+        kafka_brokers: APP_CONFIG.kafka_brokers.clone(),
+        kafka_ssl_enabled: APP_CONFIG.kafka_ssl_enabled,
+        kafka_sasl_username: APP_CONFIG.kafka_sasl_username.clone(),
+        kafka_sasl_password: APP_CONFIG.kafka_sasl_password.clone(),
+        enable_idempotence: APP_CONFIG.enable_idempotence,
+    };
+
+    setup_kafka_producer(&kafka_config).await?;
+    setup_redis_emitter(&APP_CONFIG.redis_url).await?;
+
+    // A single worker runs, so announcements still sending were left by a
+    // previous run and continue from their cursor.
+    for announcement in Announcement::find_sending().await? {
+        run_fan_out(announcement).await;
+    }
+
+    let interval = Duration::from_secs(APP_CONFIG.announcement_poll_interval_secs);
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        loop {
+            match Announcement::claim_due().await {
+                Ok(Some(announcement)) => run_fan_out(announcement).await,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::error!("Failed to claim due announcement: {e}");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn run_fan_out(announcement: Announcement) {
+    let Some(id) = announcement.id else {
+        return;
+    };
+
+    tracing::info!("Fanning out announcement {id}: {}", announcement.title);
+
+    let error = match fan_out(&announcement).await {
+        Ok(()) => None,
+        Err(e) => {
+            tracing::error!("Fan-out of announcement {id} failed: {e}");
+            Some(e.to_string())
+        }
+    };
+
+    if let Err(e) = Announcement::finish(id, error).await {
+        tracing::error!("Failed to finish announcement {id}: {e}");
+    }
+}
+
+/// Writes the announcement to the inbox of every account in its segment that
+/// did not opt out, a page of accounts at a time, and hands the pushes to the
+/// publisher at `announcement_push_rate_per_sec`. Progress is saved after
+/// every page, so a restarted worker resumes after the last account done.
+///
+/// A restarted worker redoes the page it was on: inbox entries written before
+/// are skipped, while the pushes are handed over again for the whole page and
+/// the publisher drops those it already sent.
+async fn fan_out(announcement: &Announcement) -> Result<(), Error> {
+    let id = announcement
+        .id
+        .ok_or_else(|| Error::internal_err("Announcement has no id"))?;
+    let filter = announcement.segment.account_filter();
+    let source_event_id = announcement.source_event_id();
+    let metadata = NotifMetadata::Announcement(AnnouncementNotifData {
+        announcement_id: id.to_hex(),
+        title: announcement.title.clone(),
+        body: announcement.body.clone(),
+        image_url: announcement.image_url.clone(),
+        link: announcement.link.clone(),
+    });
+    let push_rate = APP_CONFIG.announcement_push_rate_per_sec.max(1);
+    let mut push_ticker = tokio::time::interval(Duration::from_secs(1));
+
+    let mut cursor = announcement.cursor;
+    if cursor.is_none() {
+        let total_recipients = Account::count(filter.clone()).await?;
+        Announcement::set_total_recipients(id, total_recipients as i64).await?;
+    }
+
+    loop {
+        if !Announcement::is_sending(id).await? {
+            tracing::info!("Announcement {id} was cancelled, stopping the fan-out");
+            return Ok(());
+        }
+
+        let accounts =
+            Account::find_page(filter.clone(), cursor, APP_CONFIG.announcement_page_size).await?;
+        let Some(last_id) = accounts.last().and_then(|account| account.id) else {
+            break;
+        };
+        cursor = Some(last_id);
+
+        let user_ids = accounts
+            .into_iter()
+            .map(|account| account.user_id)
+            .collect::<Vec<_>>();
+        let preferences = get_user_notification_preferences_batch(user_ids.clone())
+            .await
+            .map_err(|e| Error::internal_err(&format!("Failed to load preferences: {e}")))?;
+        let recipients = user_ids
+            .iter()
+            .filter(|user_id| {
+                preferences
+                    .get(*user_id)
+                    .is_none_or(|preference| preference.announcement)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let skipped = user_ids.len() - recipients.len();
+
+        // Users reached before a restart already have the inbox entry.
+        let reached =
+            UserNotification::find_user_ids_by_source_event_id(&source_event_id, &recipients)
+                .await?
+                .into_iter()
+                .collect::<HashSet<_>>();
+
+        let now = DateTime::now();
+        let notifications = recipients
+            .iter()
+            .filter(|user_id| !reached.contains(*user_id))
+            .map(|user_id| UserNotification {
+                id: None,
+                r#type: NotifType::Announcement.to_string(),
+                user_id: user_id.clone(),
+                title: announcement.title.clone(),
+                message: announcement.body.clone(),
+                created_at: now,
+                updated_at: now,
+                is_read: false,
+                metadata: Some(NotificationMetadata::from(&metadata)),
+                source_event_id: Some(source_event_id.clone()),
+            })
+            .collect::<Vec<_>>();
+        let stored = UserNotification::insert_many(notifications).await?;
+        let delivered = stored.len();
+
+        let mut emit_failures = 0;
+        for notification in &stored {
+            if !emit_new_notification(notification).await {
+                emit_failures += 1;
+            }
+        }
+        if emit_failures > 0 {
+            tracing::warn!(
+                "Announcement {id}: failed to emit {emit_failures} inbox entries in realtime"
+            );
+        }
+
+        let timestamp = now.timestamp_millis();
+        let messages = recipients
+            .into_iter()
+            .map(|user_id| NotifMessage {
+                user_id,
+                notif_type: NotifType::Announcement,
+                timestamp,
+                metadata: metadata.clone(),
+                event_id: Some(source_event_id.clone()),
+            })
+            .collect::<Vec<_>>();
+        for chunk in messages.chunks(push_rate) {
+            push_ticker.tick().await;
+            publish_kafka_rmp_messages(
+                &KafkaTopic::UserNotificationPublisher.to_string(),
+                None,
+                vec![chunk.to_vec()],
+                None,
+            )
+            .await
+            .map_err(|e| Error::internal_err(&format!("Failed to publish pushes: {e}")))?;
+        }
+
+        Announcement::record_progress(
+            id,
+            last_id,
+            user_ids.len() as i64,
+            delivered as i64,
+            skipped as i64,
+        )
+        .await?;
+
+        tracing::info!(
+            "Announcement {id}: {} accounts processed, {delivered} delivered, {skipped} opted out",
+            user_ids.len()
+        );
+    }
+
+    tracing::info!("Announcement {id} fanned out to its whole segment");
+
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use push_notify_service::config::{APP_CONFIG, KafkaConfig};
+use push_notify_service::core::kafka_service::producer::{
+    publish_kafka_rmp_messages, setup_kafka_producer,
+};
+use push_notify_service::core::realtime::presence::node_id;
+use push_notify_service::enums::{CampaignStatus, KafkaTopic};
+use push_notify_service::errors::Error;
+use push_notify_service::models::accounts::Account;
+use push_notify_service::models::campaigns::Campaign;
+use push_notify_service::utils::models::ModelExt;
+use push_notify_service::utils::structs::{
+    CampaignNotifData, NotifMessage, NotifMetadata, NotifType,
+};
+use push_notify_service::utils::tracing::init_standard_tracing;
+use wither::bson::DateTime;
+
+/// Holder of the campaign leases this process takes. Unique per process, as
+/// replicas deployed from one environment share `NODE_ID`.
+static LEASE_OWNER: LazyLock<String> =
+    LazyLock::new(|| format!("{}:{}", node_id(), uuid::Uuid::new_v4()));
+
+fn lease_owner() -> &'static str {
+    &LEASE_OWNER
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    init_standard_tracing(env!("CARGO_CRATE_NAME"));
+
+    let kafka_config = KafkaConfig {
+        kafka_group_id: APP_CONFIG.kafka_group_id.clone(),
+        kafka_brokers: APP_CONFIG.kafka_brokers.clone(),
+        kafka_ssl_enabled: APP_CONFIG.kafka_ssl_enabled,
+        kafka_sasl_username: APP_CONFIG.kafka_sasl_username.clone(),
+        kafka_sasl_password: APP_CONFIG.kafka_sasl_password.clone(),
+        enable_idempotence: APP_CONFIG.enable_idempotence,
+    };
+
+    setup_kafka_producer(&kafka_config).await?;
+
+    tracing::info!(
+        "Campaign scheduler {} started with a {}s lease",
+        lease_owner(),
+        APP_CONFIG.campaign_lease_secs
+    );
+
+    let interval = Duration::from_secs(APP_CONFIG.campaign_poll_interval_secs);
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        loop {
+            match Campaign::claim_due(lease_owner(), APP_CONFIG.campaign_lease_secs).await {
+                Ok(Some(campaign)) => run_campaign(campaign).await,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::error!("Failed to claim due campaign: {e}");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn run_campaign(campaign: Campaign) {
+    let Some(id) = campaign.id else {
+        return;
+    };
+
+    tracing::info!("Running campaign {} ({id})", campaign.name);
+
+    let (status, error) = match fan_out(&campaign).await {
+        Ok(Some(status)) => (status, None),
+        Ok(None) => {
+            tracing::info!("Campaign {id} was cancelled or taken over, stopping");
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Campaign {id} failed: {e}");
+            (CampaignStatus::Failed, Some(e.to_string()))
+        }
+    };
+
+    if let Err(e) = Campaign::finish(id, lease_owner(), status, error).await {
+        tracing::error!("Failed to finish campaign {id}: {e}");
+    }
+}
+
+/// Publishes the campaign to every account of its segment, a page at a time,
+/// to the persister and publisher topics, which apply the campaign opt-out.
+/// The lease is renewed right before each page is published, so a slow page
+/// cannot let another replica take the campaign over while it is published,
+/// and each page is followed by a progress write.
+///
+/// Returns the final status, or `None` once the lease is lost. Should a page
+/// still be published twice, e.g. when a replica stalls mid-page, the
+/// persister stores it once per user and the publisher pushes it once.
+async fn fan_out(campaign: &Campaign) -> Result<Option<CampaignStatus>, Error> {
+    let id = campaign
+        .id
+        .ok_or_else(|| Error::internal_err("Campaign has no id"))?;
+    let owner = lease_owner();
+    let lease_secs = APP_CONFIG.campaign_lease_secs;
+    let filter = campaign.segment.account_filter();
+    let source_event_id = campaign.source_event_id();
+    let metadata = NotifMetadata::Campaign(CampaignNotifData {
+        campaign_id: id.to_hex(),
+        title: campaign.title.clone(),
+        body: campaign.body.clone(),
+        image_url: campaign.image_url.clone(),
+        link: campaign.link.clone(),
+    });
+
+    let mut cursor = campaign.cursor;
+    if cursor.is_none() {
+        let total_recipients = Account::count(filter.clone()).await?;
+        if !Campaign::set_total_recipients(id, owner, lease_secs, total_recipients as i64).await? {
+            return Ok(None);
+        }
+    }
+
+    loop {
+        if campaign.has_ended() {
+            return Ok(Some(CampaignStatus::Expired));
+        }
+
+        let accounts =
+            Account::find_page(filter.clone(), cursor, APP_CONFIG.campaign_page_size).await?;
+        let Some(last_id) = accounts.last().and_then(|account| account.id) else {
+            return Ok(Some(CampaignStatus::Completed));
+        };
+        cursor = Some(last_id);
+
+        if !Campaign::renew_lease(id, owner, lease_secs).await? {
+            return Ok(None);
+        }
+
+        let timestamp = DateTime::now().timestamp_millis();
+        let messages = accounts
+            .into_iter()
+            .map(|account| NotifMessage {
+                user_id: account.user_id,
+                notif_type: NotifType::Campaign,
+                timestamp,
+                metadata: metadata.clone(),
+                event_id: Some(source_event_id.clone()),
+            })
+            .collect::<Vec<_>>();
+        let published = messages.len() as i64;
+
+        for topic in [
+            KafkaTopic::UserNotificationPersister,
+            KafkaTopic::UserNotificationPublisher,
+        ] {
+            publish_kafka_rmp_messages(&topic.to_string(), None, vec![messages.clone()], None)
+                .await
+                .map_err(|e| Error::internal_err(&format!("Failed to publish campaign: {e}")))?;
+        }
+
+        if !Campaign::record_progress(id, owner, lease_secs, last_id, published).await? {
+            return Ok(None);
+        }
+
+        tracing::info!("Campaign {id}: published {published} notifications");
+    }
+}
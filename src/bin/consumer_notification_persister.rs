@@ -8,10 +8,12 @@ use push_notify_service::core::kafka_service::consumers::streams::{
     KafkaStreamConsumer, KafkaStreamConsumerExt,
 };
 use push_notify_service::core::kafka_service::producer::setup_kafka_producer;
+use push_notify_service::core::realtime::notification_hub::emit_new_notification;
 use push_notify_service::enums::KafkaTopic;
 use push_notify_service::errors::Error;
 use push_notify_service::loading_preferences::load_user_notification_preferences;
-use push_notify_service::models::user_notifications::UserNotification;
+use push_notify_service::loading_templates::{load_notification_templates, spawn_template_sync};
+use push_notify_service::models::user_notifications::{NotificationMetadata, UserNotification};
 use push_notify_service::utils::models::ModelExt;
 use push_notify_service::utils::notification::{
     NotifKey, NotificationWithTimestamp, group_by_user_id,
@@ -37,6 +39,11 @@ async fn main() -> anyhow::Result<()> {
     setup_kafka_producer(&kafka_config).await?;
     setup_redis_emitter(&APP_CONFIG.redis_url).await?;
 
+    // Fails while older duplicates exist; upserts still skip new ones then.
+    if let Err(e) = UserNotification::create_indexes().await {
+        tracing::warn!("Failed to create the user notification indexes: {e}");
+    }
+
     if let Err(e) = load_user_notification_preferences().await {
         tracing::warn!(
             "Failed to load user notification preferences: {e}. Return empty preferences."
@@ -45,6 +52,16 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("User notification preferences loaded successfully.");
     };
 
+    // Subscribe before loading so template changes made meanwhile are not lost.
+    let mut template_sync = spawn_template_sync();
+    if !template_sync.wait_ready().await {
+        tracing::warn!("Template updates are not subscribed yet, loading templates anyway.");
+    }
+
+    if let Err(e) = load_notification_templates().await {
+        tracing::warn!("Failed to load notification templates: {e}. Using built-in texts.");
+    }
+
     NotificationPersistConsumer::run_single_vec_message(&kafka_config, DeserializerType::RmpSerde)
         .await?;
 
@@ -95,9 +112,10 @@ async fn process(
         return Ok(());
     }
 
+    let mut emit_failures = 0usize;
+
     for (key, notifications) in grouped_notifications {
         let r#type = key.r#type.to_string();
-        let title = key.r#type.construct_title();
 
         match key.r#type {
             NotifType::Order => {
@@ -112,20 +130,30 @@ async fn process(
                         id: None,
                         r#type: r#type.clone(),
                         user_id: key.user_id.clone(),
-                        title,
+                        title: last_notif.title.clone(),
                         message: last_notif.message.clone(),
                         created_at,
                         updated_at: created_at,
                         is_read: false,
+                        metadata: Some(NotificationMetadata::from(&last_notif.metadata)),
+                        source_event_id: Some(last_notif.source_event_id.clone()),
                     };
 
-                    match UserNotification::create(notification).await {
-                        Ok(_) => {
+                    match UserNotification::create_once(notification).await {
+                        Ok(Some(saved)) => {
                             tracing::info!(
                                 "Successfully persisted Order notification for user_id={}",
                                 key.user_id
                             );
+                            if !emit_new_notification(&saved).await {
+                                emit_failures += 1;
+                            }
                         }
+                        Ok(None) => tracing::info!(
+                            "Order notification {} of user_id={} was persisted before",
+                            last_notif.source_event_id,
+                            key.user_id
+                        ),
                         Err(e) => {
                             tracing::error!(
                                 "Failed to persist Order notification for user_id={}: {e}",
@@ -136,7 +164,11 @@ async fn process(
                     }
                 }
             }
-            NotifType::Transaction | NotifType::Account => {
+            NotifType::Transaction
+            | NotifType::Account
+            | NotifType::PriceAlert
+            | NotifType::Announcement
+            | NotifType::Campaign => {
                 for notif_with_ts in notifications {
                     let chrono_dt =
                         chrono::DateTime::from_timestamp_millis(notif_with_ts.timestamp)
@@ -152,19 +184,30 @@ async fn process(
                         id: None,
                         r#type: r#type.clone(),
                         user_id: key.user_id.clone(),
-                        title: title.clone(),
+                        title: notif_with_ts.title,
                         message: notif_with_ts.message,
                         created_at,
                         updated_at: created_at,
                         is_read: false,
+                        metadata: Some(NotificationMetadata::from(&notif_with_ts.metadata)),
+                        source_event_id: Some(notif_with_ts.source_event_id),
                     };
 
-                    if let Err(e) = UserNotification::create(notification).await {
-                        tracing::error!(
-                            "Failed to persist notification for user_id={}: {e}",
-                            key.user_id
-                        );
-                        continue;
+                    match UserNotification::create_once(notification).await {
+                        Ok(Some(saved)) => {
+                            if !emit_new_notification(&saved).await {
+                                emit_failures += 1;
+                            }
+                        }
+                        // Consumed again, e.g. a campaign page published twice.
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to persist notification for user_id={}: {e}",
+                                key.user_id
+                            );
+                            continue;
+                        }
                     }
                 }
 
@@ -173,12 +216,15 @@ async fn process(
                     key.user_id
                 );
             }
-            _ => {
-                tracing::warn!("Unsupported notification type: {}", key.r#type);
-                continue;
-            }
         };
     }
 
+    if emit_failures > 0 {
+        tracing::warn!(
+            "Failed to emit {} persisted notifications in realtime",
+            emit_failures
+        );
+    }
+
     Ok(())
 }
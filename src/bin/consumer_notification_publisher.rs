@@ -1,49 +1,33 @@
 use std::collections::HashMap;
-use std::sync::{Arc, LazyLock};
-use std::time::{Duration};
 
 use async_trait::async_trait;
-use futures::stream::{self, StreamExt};
+use once_cell::sync::OnceCell;
 use push_notify_service::common::{DeserializerType, MessageWithOffset};
 use push_notify_service::config::{APP_CONFIG, KafkaConfig};
-use push_notify_service::core::cache::redis_service::RedisService;
+use push_notify_service::core::cache::redis_emitter::setup_redis_emitter;
+use push_notify_service::core::delivery::dedupe::claim_push;
+use push_notify_service::core::delivery::{
+    DeliveryRequest, SharedChannel, build_channels, dispatch,
+};
 use push_notify_service::core::kafka_service::consumers::streams::{
     KafkaStreamConsumer, KafkaStreamConsumerExt,
 };
 use push_notify_service::core::kafka_service::producer::setup_kafka_producer;
 use push_notify_service::enums::KafkaTopic;
 use push_notify_service::errors::Error;
-use push_notify_service::loading_fcm_token::{get_user_fcm_tokens, preload_user_fcm_tokens};
+use push_notify_service::loading_fcm_token::{preload_user_fcm_tokens, spawn_fcm_token_sync};
 use push_notify_service::loading_preferences::load_user_notification_preferences;
+use push_notify_service::loading_templates::{load_notification_templates, spawn_template_sync};
 use push_notify_service::utils::notification::{
     NotifKey, NotificationWithTimestamp, group_by_user_id,
 };
-use push_notify_service::utils::structs::{NotifMessage, OrderNotifBuilder};
+use push_notify_service::utils::structs::NotifMessage;
 use push_notify_service::utils::tracing::init_standard_tracing;
-use tokio_retry::{Retry, strategy::ExponentialBackoff};
-
-const NOTIFICATION_KEY_PREFIX: &str = "raidenx:notification";
-const RATE_LIMIT_DURATION: usize = 2; // 2 second
-const UNSENT_COUNT_DURATION: usize = 60 * 60 * 24; // 24 hours
-
-static FCM_CLIENT: LazyLock<fcm_notification::FcmNotification> = LazyLock::new(|| {
-    fcm_notification::FcmNotification::new(APP_CONFIG.firebase_credentials_path.as_str())
-        .unwrap_or_else(|e| {
-            tracing::error!("Failed to create FCM client: {e}");
-            std::process::exit(1);
-        })
-});
-
-const FCM_RETRY_ATTEMPTS: usize = 3;
-const FCM_RETRY_INITIAL_DELAY_MS: u64 = 100;
-const FCM_SEND_CONCURRENCY: usize = 8;
-
-struct SendJob {
-    token: String,
-    title: Arc<String>,
-    body: Arc<String>,
-    user_id: String,
-}
+
+/// Channels pushing the grouped notifications users opted into.
+static PUSH_CHANNELS: OnceCell<Vec<SharedChannel>> = OnceCell::new();
+/// Channels getting every event, e.g. webhooks.
+static EVENT_CHANNELS: OnceCell<Vec<SharedChannel>> = OnceCell::new();
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -60,6 +44,22 @@ async fn main() -> anyhow::Result<()> {
     };
 
     setup_kafka_producer(&kafka_config).await?;
+    setup_redis_emitter(&APP_CONFIG.redis_url).await?;
+
+    let channels = build_channels(&APP_CONFIG.delivery_channels)?;
+    tracing::info!(
+        "Delivering through channels: {}",
+        channels
+            .iter()
+            .map(|channel| channel.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let (event_channels, push_channels): (Vec<_>, Vec<_>) = channels
+        .into_iter()
+        .partition(|channel| channel.capabilities().every_event);
+    let _ = EVENT_CHANNELS.set(event_channels);
+    let _ = PUSH_CHANNELS.set(push_channels);
 
     if let Err(e) = load_user_notification_preferences().await {
         tracing::warn!(
@@ -69,6 +69,23 @@ async fn main() -> anyhow::Result<()> {
         tracing::info!("User notification preferences loaded successfully.");
     };
 
+    // Subscribe before loading so template changes made meanwhile are not lost.
+    let mut template_sync = spawn_template_sync();
+    if !template_sync.wait_ready().await {
+        tracing::warn!("Template updates are not subscribed yet, loading templates anyway.");
+    }
+
+    if let Err(e) = load_notification_templates().await {
+        tracing::warn!("Failed to load notification templates: {e}. Using built-in texts.");
+    }
+
+    // Updates published once the subscription is up reach the cache even
+    // while the preload is still reading the database.
+    let mut fcm_token_sync = spawn_fcm_token_sync();
+    if !fcm_token_sync.wait_ready().await {
+        tracing::warn!("FCM token updates are not subscribed yet, preloading anyway.");
+    }
+
     if let Err(e) = preload_user_fcm_tokens().await {
         tracing::warn!("Failed to preload user FCM tokens: {e}");
     } else {
@@ -100,6 +117,8 @@ impl KafkaStreamConsumer<NotifMessage> for NotificationPublishConsumer {
         }
 
         tracing::info!("Received {} notifications from Kafka topic", messages.len());
+        dispatch_events(&messages).await?;
+
         let user_notifications = group_by_user_id(messages).await?;
 
         let total_grouped = user_notifications.values().map(|v| v.len()).sum::<usize>();
@@ -116,245 +135,92 @@ impl KafkaStreamConsumer<NotifMessage> for NotificationPublishConsumer {
     }
 }
 
-async fn process(
-    grouped_notifications: HashMap<NotifKey, Vec<NotificationWithTimestamp>>,
-) -> Result<(), Error> {
-    if grouped_notifications.is_empty() {
-        tracing::info!(
-            "No notifications to send (all were skipped due to user preferences), but offset will still be committed"
-        );
+/// Hands every event to the event channels before pushes are filtered by
+/// preference and collapsed per second.
+async fn dispatch_events(messages: &[NotifMessage]) -> Result<(), Error> {
+    let channels = EVENT_CHANNELS
+        .get()
+        .ok_or_else(|| Error::internal_err("Delivery channels are not initialized"))?;
+    if channels.is_empty() {
         return Ok(());
     }
 
-    for (key, notifications) in grouped_notifications {
-        let title = key.r#type.construct_title();
+    for message in messages {
+        // Event channels send the event data, not the rendered text.
+        let request = DeliveryRequest::new(
+            message.user_id.clone(),
+            message.notif_type,
+            String::new(),
+            String::new(),
+        )
+        .with_metadata(message.metadata.clone())
+        .with_source_event_id(message.source_event_id())
+        .with_occurred_at(message.timestamp);
 
-        let notif_data = notifications
-            .iter()
-            .map(|notif| OrderNotifBuilder {
-                user_id: key.user_id.clone(),
-                message: notif.message.clone(),
-            })
-            .collect::<Vec<OrderNotifBuilder>>();
-
-        if let Some(last_notif) = notif_data.last() {
-            if let Err(e) = push_notification_to_firebase(title, last_notif).await {
-                tracing::error!("Failed to push notification for user {}: {e}", key.user_id);
-            }
-        }
+        dispatch(channels, &request).await;
     }
 
     Ok(())
 }
 
-async fn push_notification_to_firebase(
-    title: String,
-    notif: &OrderNotifBuilder,
+async fn process(
+    grouped_notifications: HashMap<NotifKey, Vec<NotificationWithTimestamp>>,
 ) -> Result<(), Error> {
-    let tokens = get_user_fcm_tokens(notif.user_id.clone()).await?;
-    if tokens.is_empty() {
-        tracing::warn!(
-            "No FCM tokens found for user ID {}. Skipping notification.",
-            notif.user_id
+    if grouped_notifications.is_empty() {
+        tracing::info!(
+            "No notifications to send (all were skipped due to user preferences), but offset will still be committed"
         );
         return Ok(());
     }
 
-    let mut send_jobs = Vec::new();
-
-    for token in tokens {
-        let unsent_count = get_unsent_notification_count(token.clone())
-            .await
-            .unwrap_or(0);
-
-        if can_send_notification(token.clone()).await {
-            let (title_str, message_str) = if unsent_count > 1 {
-                let title = "You have many notifications".to_string();
-                let message = format!(
-                    "You have {unsent_count} unread notifications. Please check your app.",
-                );
-                (title, message)
-            } else {
-                (title.clone(), notif.message.clone())
-            };
-
-            send_jobs.push(SendJob {
-                token: token.clone(),
-                title: Arc::new(title_str),
-                body: Arc::new(message_str),
-                user_id: notif.user_id.clone(),
-            });
-        } else {
-            if let Err(e) = increment_unsent_count(token.clone()).await {
-                tracing::warn!(
-                    "Failed to update unsent count notification for user ID {}: {e}",
-                    notif.user_id
-                );
-            };
-            tracing::warn!(
-                "Skipping notification for user ID {} due to rate limiting.",
-                notif.user_id
-            );
-        }
-    }
-
-    if send_jobs.is_empty() {
-        return Ok(());
-    }
-
-
-    let results = stream::iter(send_jobs.into_iter().map(send_job))
-        .buffer_unordered(FCM_SEND_CONCURRENCY)
-        .collect::<Vec<_>>()
-        .await;
+    let channels = PUSH_CHANNELS
+        .get()
+        .ok_or_else(|| Error::internal_err("Delivery channels are not initialized"))?;
 
-    for (user_id, result) in results {
-        if let Err(e) = result {
-            tracing::error!(
-                "Failed to send notification to user ID {} after retries: {}",
-                user_id,
-                e
-            );
+    for (key, notifications) in grouped_notifications {
+        let Some((last_notif, earlier)) = notifications.split_last() else {
+            continue;
+        };
+
+        // Only the latest notification of the second is pushed, except
+        // mandatory ones, which must not be collapsed into a later event.
+        for notif in earlier.iter().filter(|notif| notif.metadata.is_mandatory()) {
+            push_once(channels, &key, notif).await;
         }
+        push_once(channels, &key, last_notif).await;
     }
 
     Ok(())
 }
 
-async fn send_job(job: SendJob) -> (String, Result<(), Error>) {
-    let SendJob {
-        token,
-        title,
-        body,
-        user_id,
-    } = job;
-
-    let retry_strategy = ExponentialBackoff::from_millis(FCM_RETRY_INITIAL_DELAY_MS)
-        .max_delay(Duration::from_secs(5))
-        .take(FCM_RETRY_ATTEMPTS);
-
-    let token_arc = Arc::new(token.clone());
-    let title_arc = Arc::clone(&title);
-    let body_arc = Arc::clone(&body);
-
-    let send_result = Retry::spawn(retry_strategy, {
-        let token_arc = Arc::clone(&token_arc);
-        let title_arc = Arc::clone(&title_arc);
-        let body_arc = Arc::clone(&body_arc);
-        move || {
-            let token = Arc::clone(&token_arc);
-            let title = Arc::clone(&title_arc);
-            let body = Arc::clone(&body_arc);
-            async move {
-                let notification = fcm_notification::NotificationPayload {
-                    token: token.as_str(),
-                    title: title.as_ref(),
-                    body: body.as_ref(),
-                    data: None,
-                };
-                FCM_CLIENT.send_notification(&notification).await
-            }
-        }
-    })
-    .await;
-
-    match send_result {
-        Ok(_) => {
-            if let Err(e) = update_last_sent(token.clone()).await {
-                tracing::warn!("Failed to update rate limit for user ID {}: {e}", user_id);
-            }
-            if let Err(e) = reset_unsent_count(token.clone()).await {
-                tracing::warn!(
-                    "Failed to reset unsent count notification for user ID {}: {e}",
-                    user_id
-                );
-            }
-
-            tracing::info!("Notification sent successfully for user ID {}", user_id);
-            (user_id, Ok(()))
-        }
-        Err(e) => {
-            (
-                user_id,
-                Err(Error::internal_err(&format!(
-                    "FCM send failed after {} retries: {}",
-                    FCM_RETRY_ATTEMPTS, e
-                ))),
-            )
-        }
-    }
-}
-
-async fn can_send_notification(device_token: String) -> bool {
-    let redis_service = RedisService::new().await;
-    let last_sent = redis_service
-        .get_cache::<Option<i64>>(
-            format!("{}:{}:last_sent", NOTIFICATION_KEY_PREFIX, device_token).as_str(),
-        )
-        .await
-        .unwrap_or_default();
-
-    match last_sent {
-        Some(timestamp) => {
-            let current_time = chrono::Utc::now().timestamp_millis();
-            let elapsed_time = current_time - timestamp;
-            elapsed_time >= (RATE_LIMIT_DURATION as i64 * 1000)
-        }
-        None => true,
+/// Pushes `notif` unless the user already got the same event.
+async fn push_once(channels: &[SharedChannel], key: &NotifKey, notif: &NotificationWithTimestamp) {
+    if !claim_push(&key.user_id, &notif.source_event_id).await {
+        tracing::info!(
+            "Event {} was already pushed to user ID {}, skipping",
+            notif.source_event_id,
+            key.user_id
+        );
+        return;
     }
-}
 
-async fn update_last_sent(device_token: String) -> Result<(), Error> {
-    let redis_service = RedisService::new().await;
-
-    let key = format!("{}:{}:last_sent", NOTIFICATION_KEY_PREFIX, device_token);
-    let current_time = chrono::Utc::now().timestamp_millis();
-
-    redis_service
-        .set_ex_cache(key.as_str(), &current_time, RATE_LIMIT_DURATION)
-        .await
-        .map_err(|e| {
-            Error::internal_err(&format!("Failed to update unsent notification count: {e}"))
-        })?;
-
-    Ok(())
-}
-
-async fn increment_unsent_count(device_token: String) -> Result<(), Error> {
-    let current_count = get_unsent_notification_count(device_token.clone())
-        .await
-        .unwrap_or(0);
-    let new_count = current_count + 1;
-
-    let redis_service = RedisService::new().await;
-    let key = format!("{}:{}:unsent_count", NOTIFICATION_KEY_PREFIX, device_token);
-
-    redis_service
-        .set_ex_cache(&key, &new_count, UNSENT_COUNT_DURATION)
-        .await
-        .map_err(|e| Error::internal_err(&format!("Failed to increment unsent count: {e}")))?;
-
-    Ok(())
-}
-
-async fn reset_unsent_count(device_token: String) -> Result<(), Error> {
-    let redis_service = RedisService::new().await;
-    let key = format!("{}:{}:unsent_count", NOTIFICATION_KEY_PREFIX, device_token);
-
-    redis_service
-        .set_ex_cache(&key, &0i64, UNSENT_COUNT_DURATION)
-        .await
-        .map_err(|e| Error::internal_err(&format!("Failed to reset unsent count: {e}")))?;
-
-    Ok(())
+    dispatch(channels, &build_request(key, notif)).await;
 }
 
-async fn get_unsent_notification_count(device_token: String) -> Option<i64> {
-    let redis_service = RedisService::new().await;
-    let key = format!("{}:{}:unsent_count", NOTIFICATION_KEY_PREFIX, device_token);
+fn build_request(key: &NotifKey, notif: &NotificationWithTimestamp) -> DeliveryRequest {
+    let mut request = DeliveryRequest::new(
+        key.user_id.clone(),
+        key.r#type,
+        notif.title.clone(),
+        notif.message.clone(),
+    )
+    .with_metadata(notif.metadata.clone())
+    .with_source_event_id(notif.source_event_id.clone())
+    .with_occurred_at(notif.timestamp)
+    .with_language(notif.lang);
+    if let Some(image_url) = notif.metadata.image_url() {
+        request = request.with_image_url(image_url);
+    }
 
-    redis_service
-        .get_cache::<Option<i64>>(key.as_str())
-        .await
-        .unwrap_or_default()
+    request
 }
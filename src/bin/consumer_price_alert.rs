@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use push_notify_service::common::{DeserializerType, MessageWithOffset};
+use push_notify_service::config::{APP_CONFIG, KafkaConfig};
+use push_notify_service::core::cache::redis_emitter::setup_redis_emitter;
+use push_notify_service::core::kafka_service::consumers::streams::{
+    KafkaStreamConsumer, KafkaStreamConsumerExt,
+};
+use push_notify_service::core::kafka_service::producer::{
+    publish_kafka_rmp_messages, setup_kafka_producer,
+};
+use push_notify_service::core::price_alerts::price_alert_index::{
+    CrossedAlert, get_last_price, load_price_alerts, price_alert_index, refresh_price_alert,
+    spawn_price_alert_sync, store_last_prices,
+};
+use push_notify_service::enums::{KafkaTopic, PriceAlertCondition};
+use push_notify_service::models::price_alerts::PriceAlert;
+use push_notify_service::utils::structs::{
+    NotifMessage, NotifMetadata, NotifType, PriceAlertNotifData, PriceTick,
+};
+use push_notify_service::utils::tracing::init_standard_tracing;
+use rust_decimal::Decimal;
+use tokio::sync::Mutex;
+
+/// Price each symbol was last evaluated at.
+static LAST_PRICES: LazyLock<Mutex<HashMap<String, Decimal>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    init_standard_tracing(env!("CARGO_CRATE_NAME"));
+
+    let kafka_config = KafkaConfig {
+        kafka_group_id: APP_CONFIG.kafka_group_id.clone(),
+        kafka_brokers: APP_CONFIG.kafka_brokers.clone(),
+        kafka_ssl_enabled: APP_CONFIG.kafka_ssl_enabled,
+        kafka_sasl_username: APP_CONFIG.kafka_sasl_username.clone(),
+        kafka_sasl_password: APP_CONFIG.kafka_sasl_password.clone(),
+        enable_idempotence: APP_CONFIG.enable_idempotence,
+    };
+
+    setup_kafka_producer(&kafka_config).await?;
+    setup_redis_emitter(&APP_CONFIG.redis_url).await?;
+
+    // Subscribe before loading so alerts changed meanwhile are not lost.
+    let mut price_alert_sync = spawn_price_alert_sync();
+    if !price_alert_sync.wait_ready().await {
+        tracing::warn!("Price alert updates are not subscribed yet, loading alerts anyway.");
+    }
+
+    load_price_alerts().await?;
+
+    PriceAlertConsumer::run_single_vec_message(&kafka_config, DeserializerType::RmpSerde).await?;
+
+    Ok(())
+}
+
+pub struct PriceAlertConsumer;
+
+#[async_trait]
+impl KafkaStreamConsumer<PriceTick> for PriceAlertConsumer {
+    fn topic() -> String {
+        KafkaTopic::MarketPrice.to_string()
+    }
+
+    /// Publishes the notifications of a batch before recording the triggers
+    /// and advancing the last prices, so a failed publish leaves the crossing
+    /// to be found again. Event ids follow the trigger count, so a crossing
+    /// published twice is still stored once.
+    async fn handle_single_vector_message(
+        payload: MessageWithOffset<Vec<PriceTick>>,
+    ) -> anyhow::Result<()> {
+        let ticks = payload.message;
+
+        if ticks.is_empty() {
+            return Ok(());
+        }
+
+        let mut messages = Vec::new();
+        let mut fired = Vec::new();
+        let mut fired_ids = HashSet::new();
+        let mut latest_prices = HashMap::new();
+
+        for tick in ticks {
+            if tick.price <= Decimal::ZERO {
+                tracing::warn!("Skipping {} tick with price {}", tick.symbol, tick.price);
+                continue;
+            }
+
+            // After a restart the price from before it still tells whether a
+            // threshold was crossed meanwhile.
+            let previous = match latest_prices.get(&tick.symbol) {
+                Some(previous) => Some(*previous),
+                None => match LAST_PRICES.lock().await.get(&tick.symbol).copied() {
+                    Some(previous) => Some(previous),
+                    None => get_last_price(&tick.symbol).await,
+                },
+            };
+            latest_prices.insert(tick.symbol.clone(), tick.price);
+
+            let Some(previous) = previous else {
+                continue;
+            };
+
+            let crossed =
+                price_alert_index()
+                    .read()
+                    .await
+                    .crossed(&tick.symbol, previous, tick.price);
+
+            for crossed_alert in crossed {
+                // Triggers are recorded once the batch is published, so the
+                // index still holds the alerts that fired earlier in it.
+                let Some(id) = crossed_alert.alert.id else {
+                    continue;
+                };
+                if !fired_ids.insert(id) {
+                    continue;
+                }
+
+                if let Some(message) = build_message(&crossed_alert, &tick).await {
+                    messages.push(message);
+                    fired.push((crossed_alert.alert, tick.price));
+                }
+            }
+        }
+
+        if !messages.is_empty() {
+            tracing::info!("Triggered {} price alerts", messages.len());
+
+            for topic in [
+                KafkaTopic::UserNotificationPersister,
+                KafkaTopic::UserNotificationPublisher,
+            ] {
+                publish_kafka_rmp_messages(&topic.to_string(), None, vec![messages.clone()], None)
+                    .await?;
+            }
+        }
+
+        for (alert, price) in fired {
+            record_trigger(alert, price).await;
+        }
+
+        LAST_PRICES.lock().await.extend(latest_prices.clone());
+        if let Err(e) = store_last_prices(&latest_prices).await {
+            tracing::warn!("Failed to store last prices: {e}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the notification of a crossed alert. Returns `None` when the alert
+/// expired since it was indexed.
+async fn build_message(crossed_alert: &CrossedAlert, tick: &PriceTick) -> Option<NotifMessage> {
+    let CrossedAlert {
+        alert, threshold, ..
+    } = crossed_alert;
+    let id = alert.id?;
+
+    if alert.is_expired() {
+        if let Err(e) = PriceAlert::deactivate(id).await {
+            tracing::error!("Failed to deactivate expired price alert {id}: {e}");
+        }
+        price_alert_index().write().await.remove(&id);
+        return None;
+    }
+
+    let Some(condition) = alert.condition() else {
+        tracing::warn!(
+            "Price alert {id} has an invalid condition: {}",
+            alert.condition
+        );
+        return None;
+    };
+
+    Some(NotifMessage {
+        user_id: alert.user_id.clone(),
+        notif_type: NotifType::PriceAlert,
+        timestamp: tick.timestamp,
+        metadata: NotifMetadata::PriceAlert(PriceAlertNotifData {
+            alert_id: id.to_hex(),
+            symbol: alert.symbol.clone(),
+            condition,
+            threshold: *threshold,
+            percent: alert.percent,
+            reference_price: match condition {
+                PriceAlertCondition::PercentChange => alert.reference_price,
+                PriceAlertCondition::Above | PriceAlertCondition::Below => None,
+            },
+            price: tick.price,
+        }),
+        event_id: Some(format!("price_alert:{}:{}", id, alert.trigger_count + 1)),
+    })
+}
+
+/// Records a published trigger and moves the alert in the index accordingly.
+async fn record_trigger(alert: PriceAlert, price: Decimal) {
+    let Some(id) = alert.id else {
+        return;
+    };
+
+    match alert.record_trigger(price).await {
+        Ok(Some(updated)) if updated.is_active => price_alert_index().write().await.insert(updated),
+        Ok(Some(_)) => {
+            price_alert_index().write().await.remove(&id);
+        }
+        // Changed since it was indexed, or the trigger was recorded before.
+        Ok(None) => {
+            if let Err(e) = refresh_price_alert(id).await {
+                tracing::error!("Failed to refresh price alert {id}: {e}");
+            }
+        }
+        Err(e) => tracing::error!("Failed to record trigger of price alert {id}: {e}"),
+    }
+}
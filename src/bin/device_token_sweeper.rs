@@ -0,0 +1,69 @@
+use std::time::Duration;
+
+use push_notify_service::config::APP_CONFIG;
+use push_notify_service::core::cache::redis_emitter::setup_redis_emitter;
+use push_notify_service::errors::Error;
+use push_notify_service::loading_fcm_token::{UpdateFcmTokenAction, publish_fcm_token_update};
+use push_notify_service::models::user_fcm_token::UserFcmToken;
+use push_notify_service::utils::tracing::init_standard_tracing;
+use wither::bson::DateTime;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    init_standard_tracing(env!("CARGO_CRATE_NAME"));
+
+    setup_redis_emitter(&APP_CONFIG.redis_url).await?;
+
+    let interval = Duration::from_secs(APP_CONFIG.device_token_sweep_interval_secs);
+    tracing::info!(
+        "Sweeping device tokens idle for more than {} days every {:?}",
+        APP_CONFIG.device_token_max_idle_days,
+        interval
+    );
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        if let Err(e) = sweep_idle_tokens().await {
+            tracing::error!("Device token sweep failed: {e}");
+        }
+    }
+}
+
+async fn sweep_idle_tokens() -> Result<(), Error> {
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(APP_CONFIG.device_token_max_idle_days);
+    let pruned = UserFcmToken::deactivate_idle_since(DateTime::from_chrono(cutoff)).await?;
+
+    for token in &pruned {
+        tracing::info!(
+            "Pruned idle FCM token of user {} on device {} (last seen {})",
+            token.user_id,
+            token.device_id,
+            token.last_seen()
+        );
+
+        if let Err(e) = publish_fcm_token_update(
+            token.user_id.clone(),
+            token.token.clone(),
+            None,
+            token.provider(),
+            UpdateFcmTokenAction::Remove,
+        )
+        .await
+        {
+            tracing::warn!(
+                "Failed to broadcast removal of idle FCM token for user {}: {e}",
+                token.user_id
+            );
+        }
+    }
+
+    tracing::info!(
+        "Device token sweep finished: {} idle token(s) pruned",
+        pruned.len()
+    );
+
+    Ok(())
+}
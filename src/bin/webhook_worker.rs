@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use push_notify_service::config::APP_CONFIG;
+use push_notify_service::core::delivery::webhook::WebhookChannel;
+use push_notify_service::models::webhook_jobs::WebhookJob;
+use push_notify_service::utils::tracing::init_standard_tracing;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenvy::dotenv().ok();
+    init_standard_tracing(env!("CARGO_CRATE_NAME"));
+
+    WebhookJob::create_indexes().await?;
+    let channel = WebhookChannel::new()?;
+
+    let interval = Duration::from_secs(APP_CONFIG.webhook_poll_interval_secs);
+    tracing::info!(
+        "Webhook worker started, delivering up to {} events at once",
+        APP_CONFIG.webhook_worker_concurrency
+    );
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+
+        // Keeps claiming batches until the due jobs run out.
+        while deliver_batch(&channel).await == APP_CONFIG.webhook_worker_concurrency {}
+    }
+}
+
+/// Claims up to `webhook_worker_concurrency` due jobs and delivers them
+/// concurrently. Returns how many jobs were claimed.
+async fn deliver_batch(channel: &WebhookChannel) -> usize {
+    let mut jobs = Vec::new();
+    while jobs.len() < APP_CONFIG.webhook_worker_concurrency {
+        match WebhookJob::claim_due(APP_CONFIG.webhook_lease_secs).await {
+            Ok(Some(job)) => jobs.push(job),
+            Ok(None) => break,
+            Err(e) => {
+                tracing::error!("Failed to claim due webhook job: {e}");
+                break;
+            }
+        }
+    }
+
+    let claimed = jobs.len();
+    futures::future::join_all(jobs.iter().map(|job| async move {
+        if let Err(e) = channel.deliver(job).await {
+            tracing::error!(
+                "Failed to deliver event {} to webhook {}: {e}",
+                job.event_id,
+                job.subscription_id
+            );
+        }
+    }))
+    .await;
+
+    claimed
+}
@@ -56,8 +56,228 @@ pub struct Config {
     #[clap(long, env)]
     pub enable_idempotence: bool,
 
+    /// Firebase service account key, used to authorize FCM HTTP v1 requests.
     #[clap(long, env)]
     pub firebase_credentials_path: String,
+
+    /// An `http://` URL points the FCM client at a local mock server.
+    #[clap(long, env, default_value = "https://fcm.googleapis.com")]
+    pub fcm_base_url: String,
+
+    /// Prefix of the deep links sent with pushes, e.g. `raidenx://app/orders/42`.
+    #[clap(long, env, default_value = "raidenx://app")]
+    pub deep_link_base_url: String,
+
+    /// Channels the publisher delivers through, e.g. `fcm`.
+    #[clap(long, env, value_delimiter = ',', default_value = "fcm")]
+    pub delivery_channels: Vec<String>,
+
+    /// Account events delivered even when the user turned off account
+    /// notifications and never rate limited, as `Category:Action`.
+    #[clap(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "Password:Reset,Password:Change,Mfa:Disabled,Account:Disabled"
+    )]
+    pub mandatory_account_events: Vec<String>,
+
+    /// Token-based (.p8) auth key, required when `apns` is a delivery channel.
+    #[clap(long, env)]
+    pub apns_key_path: Option<String>,
+
+    #[clap(long, env)]
+    pub apns_key_id: Option<String>,
+
+    #[clap(long, env)]
+    pub apns_team_id: Option<String>,
+
+    /// Bundle id of the iOS app, sent as `apns-topic`.
+    #[clap(long, env)]
+    pub apns_topic: Option<String>,
+
+    /// Use `https://api.sandbox.push.apple.com` for development builds; an
+    /// `http://` URL talks cleartext HTTP/2 to a local mock server.
+    #[clap(long, env, default_value = "https://api.push.apple.com")]
+    pub apns_base_url: String,
+
+    /// SMTP relay used by the `email` delivery channel.
+    #[clap(long, env)]
+    pub smtp_host: Option<String>,
+
+    #[clap(long, env, default_value_t = 587)]
+    pub smtp_port: u16,
+
+    #[clap(long, env)]
+    pub smtp_username: Option<String>,
+
+    #[clap(long, env)]
+    pub smtp_password: Option<String>,
+
+    /// `starttls`, `tls`, or `none` for a local SMTP sink.
+    #[clap(long, env, default_value = "starttls")]
+    pub smtp_tls: String,
+
+    #[clap(long, env)]
+    pub email_from: Option<String>,
+
+    /// Webhooks failing this many deliveries in a row are disabled.
+    #[clap(long, env, default_value_t = 10)]
+    pub webhook_max_consecutive_failures: i32,
+
+    /// Accepts `http://` webhook URLs, for testing against local receivers.
+    #[clap(long, env, default_value_t = false)]
+    pub webhook_allow_http: bool,
+
+    /// Lets webhooks reach loopback, private and other non-public addresses,
+    /// for testing against receivers on the local network.
+    #[clap(long, env, default_value_t = false)]
+    pub webhook_allow_private_hosts: bool,
+
+    /// Attempts made to deliver an event before the webhook worker gives up.
+    #[clap(long, env, default_value_t = 8)]
+    pub webhook_max_attempts: i32,
+
+    /// How often the webhook worker looks for deliveries that are due.
+    #[clap(long, env, default_value_t = 5)]
+    pub webhook_poll_interval_secs: u64,
+
+    /// How long a worker owns a claimed delivery; it is retried after that.
+    #[clap(long, env, default_value_t = 60)]
+    pub webhook_lease_secs: i64,
+
+    /// Deliveries the webhook worker has in flight at once.
+    #[clap(long, env, default_value_t = 8)]
+    pub webhook_worker_concurrency: usize,
+
+    #[clap(long, env)]
+    pub telegram_bot_token: Option<String>,
+
+    /// Bot username without `@`, used to build `t.me` deep links.
+    #[clap(long, env)]
+    pub telegram_bot_username: Option<String>,
+
+    /// Point at a local stand-in of the Bot API for testing.
+    #[clap(long, env, default_value = "https://api.telegram.org")]
+    pub telegram_api_base_url: String,
+
+    /// Expected `X-Telegram-Bot-Api-Secret-Token` on bot webhook calls.
+    #[clap(long, env)]
+    pub telegram_webhook_secret: Option<String>,
+
+    #[clap(long, env, default_value_t = 600)]
+    pub telegram_link_code_ttl_secs: usize,
+
+    /// VAPID private key as the base64url encoded P-256 scalar.
+    #[clap(long, env)]
+    pub vapid_private_key: Option<String>,
+
+    /// Contact for push services, e.g. `mailto:ops@example.com`.
+    #[clap(long, env)]
+    pub vapid_subject: Option<String>,
+
+    /// Push services subscriptions may point at; subdomains of these hosts
+    /// are accepted too.
+    #[clap(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "fcm.googleapis.com,updates.push.services.mozilla.com,web.push.apple.com,notify.windows.com"
+    )]
+    pub web_push_allowed_hosts: Vec<String>,
+
+    /// How long push services keep an undelivered message.
+    #[clap(long, env, default_value_t = 86400)]
+    pub web_push_ttl_secs: u32,
+
+    /// Generic HTTP SMS provider; messages are posted to `{url}/messages`.
+    #[clap(long, env)]
+    pub sms_api_url: Option<String>,
+
+    #[clap(long, env)]
+    pub sms_api_key: Option<String>,
+
+    /// Sender ID or number the provider sends from.
+    #[clap(long, env)]
+    pub sms_sender: Option<String>,
+
+    /// Account events sent by SMS, as `Category:Action`, e.g. `Password:Reset`.
+    #[clap(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "Password:Reset,Mfa:Disabled,Account:Disabled"
+    )]
+    pub sms_event_types: Vec<String>,
+
+    /// Maximum number of SMS sent to one user per UTC day.
+    #[clap(long, env, default_value_t = 5)]
+    pub sms_daily_limit: i64,
+
+    /// Open notification streams, SSE or WebSocket, allowed per user on one API instance.
+    #[clap(long, env, default_value_t = 5)]
+    pub sse_max_connections_per_user: usize,
+
+    /// Interval of keep-alive comments on idle notification streams.
+    #[clap(long, env, default_value_t = 15)]
+    pub sse_keep_alive_secs: u64,
+
+    /// Notifications read per page when a stream resumes from `Last-Event-ID`.
+    #[clap(long, env, default_value_t = 100)]
+    pub sse_replay_limit: i64,
+
+    /// Identifies this instance in WebSocket presence and, suffixed with a
+    /// random id per process, in campaign leases; random when unset.
+    #[clap(long, env)]
+    pub node_id: Option<String>,
+
+    /// HTTP Basic credentials of the admin API; it is disabled while unset.
+    #[clap(long, env)]
+    pub admin_username: Option<String>,
+
+    #[clap(long, env)]
+    pub admin_password: Option<String>,
+
+    /// Emits a `NewNotification` socket.io event for every persisted notification.
+    #[clap(long, env, default_value_t = true, action = clap::ArgAction::Set)]
+    pub realtime_emit_enabled: bool,
+
+    /// Active devices kept per user; registering beyond this signs out the
+    /// least recently seen device.
+    #[clap(long, env, default_value_t = 10)]
+    pub max_active_devices_per_user: usize,
+
+    /// Tokens not seen for this many days are deactivated by the sweeper.
+    #[clap(long, env, default_value_t = 60)]
+    pub device_token_max_idle_days: i64,
+
+    #[clap(long, env, default_value_t = 3600)]
+    pub device_token_sweep_interval_secs: u64,
+
+    /// How often the announcement worker looks for announcements that are due.
+    #[clap(long, env, default_value_t = 30)]
+    pub announcement_poll_interval_secs: u64,
+
+    /// Accounts read per page of an announcement fan-out.
+    #[clap(long, env, default_value_t = 500)]
+    pub announcement_page_size: i64,
+
+    /// Pushes an announcement fan-out hands to the publisher per second.
+    #[clap(long, env, default_value_t = 200)]
+    pub announcement_push_rate_per_sec: usize,
+
+    /// How often the campaign scheduler looks for campaigns that are due.
+    #[clap(long, env, default_value_t = 30)]
+    pub campaign_poll_interval_secs: u64,
+
+    /// How long a scheduler owns a campaign without renewing its lease;
+    /// another replica takes the campaign over after that.
+    #[clap(long, env, default_value_t = 120)]
+    pub campaign_lease_secs: i64,
+
+    /// Accounts read, and notifications published, per page of a campaign.
+    #[clap(long, env, default_value_t = 500)]
+    pub campaign_page_size: i64,
 }
 
 use serde::Deserialize;
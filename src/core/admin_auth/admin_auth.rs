@@ -0,0 +1,49 @@
+use axum::{extract::FromRequestParts, http::request::Parts};
+use axum_extra::{
+    TypedHeader,
+    headers::{Authorization, authorization::Basic},
+};
+
+use crate::{config::APP_CONFIG, errors::Error as AppError};
+
+/// Guards operator endpoints with the HTTP Basic credentials from configuration.
+/// Admin endpoints are unavailable while no credentials are configured.
+#[derive(Debug)]
+pub struct AdminAuth {
+    pub username: String,
+}
+
+impl<S> FromRequestParts<S> for AdminAuth
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let (Some(username), Some(password)) =
+            (&APP_CONFIG.admin_username, &APP_CONFIG.admin_password)
+        else {
+            return Err(AppError::forbidden("Admin API is disabled"));
+        };
+
+        let TypedHeader(Authorization(basic)) =
+            TypedHeader::<Authorization<Basic>>::from_request_parts(parts, _state)
+                .await
+                .map_err(|_| AppError::unauthorized("Authorization header missing"))?;
+
+        // Compare both so the response time does not reveal which one was wrong.
+        let username_matches = constant_time_eq(basic.username().as_bytes(), username.as_bytes());
+        let password_matches = constant_time_eq(basic.password().as_bytes(), password.as_bytes());
+        if !(username_matches & password_matches) {
+            return Err(AppError::unauthorized("Invalid admin credentials"));
+        }
+
+        Ok(AdminAuth {
+            username: basic.username().to_string(),
+        })
+    }
+}
+
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -3,11 +3,22 @@ use once_cell::sync::OnceCell;
 use redis::{Client, Commands};
 use serde::{Serialize, de::DeserializeOwned};
 use socketio_rust_emitter::Emitter;
+use std::future::Future;
+use std::pin::Pin;
 use std::time::Duration;
-use tokio::task;
+use tokio::sync::oneshot;
+use tokio::task::{self, JoinHandle};
 
 pub static REDIS_EMITTER: OnceCell<RedisEmitter> = OnceCell::new();
 
+const SUBSCRIPTION_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const SUBSCRIPTION_MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// How long startup waits for the first subscription before loading state
+/// anyway; the state is then reloaded once the subscription comes up.
+const SUBSCRIPTION_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type SubscriptionFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
 pub async fn setup_redis_emitter(redis_url: &str) -> Result<(), Error> {
     let redis_emitter = RedisEmitter::new(redis_url)?;
     REDIS_EMITTER
@@ -80,14 +91,21 @@ impl RedisEmitter {
     pub async fn subscribe<T>(
         &self,
         channel: &str,
-        callback: impl Fn(
-            T,
-        ) -> std::pin::Pin<
-            Box<dyn std::future::Future<Output = anyhow::Result<()>> + Send>,
-        >
-        + Send
-        + 'static
-        + Clone,
+        callback: impl Fn(T) -> SubscriptionFuture + Send + 'static + Clone,
+    ) -> anyhow::Result<()>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.subscribe_with_ready::<T>(channel, callback, None)
+            .await
+    }
+
+    /// Same as `subscribe`, but signals `ready` once the channel is subscribed.
+    pub async fn subscribe_with_ready<T>(
+        &self,
+        channel: &str,
+        callback: impl Fn(T) -> SubscriptionFuture + Send + 'static + Clone,
+        mut ready: Option<oneshot::Sender<()>>,
     ) -> anyhow::Result<()>
     where
         T: DeserializeOwned + Send + 'static,
@@ -101,6 +119,10 @@ impl RedisEmitter {
             pubsub.set_read_timeout(Some(Duration::from_secs(1)))?;
             pubsub.subscribe(&channel)?;
 
+            if let Some(ready) = ready.take() {
+                let _ = ready.send(());
+            }
+
             let rt = tokio::runtime::Handle::current();
 
             loop {
@@ -108,8 +130,17 @@ impl RedisEmitter {
                     Ok(msg) => {
                         let payload: String = msg.get_payload()?;
 
-                        let data: T = serde_json::from_str(&payload)
-                            .map_err(|e| anyhow::anyhow!("Failed to deserialize message: {}", e))?;
+                        let data: T = match serde_json::from_str(&payload) {
+                            Ok(data) => data,
+                            Err(e) => {
+                                tracing::warn!(
+                                    "Skipping malformed message on channel {}: {}",
+                                    channel,
+                                    e
+                                );
+                                continue;
+                            }
+                        };
 
                         let cb = callback.clone();
                         rt.spawn(cb(data));
@@ -128,3 +159,102 @@ impl RedisEmitter {
         Ok(())
     }
 }
+
+/// A subscription kept alive by `spawn_supervised_subscription`.
+pub struct SupervisedSubscription {
+    pub handle: JoinHandle<()>,
+    ready: Option<oneshot::Receiver<()>>,
+}
+
+impl SupervisedSubscription {
+    /// Waits until the channel is subscribed for the first time, so state
+    /// loaded afterwards cannot miss an update. Returns `false` when it took
+    /// longer than `SUBSCRIPTION_READY_TIMEOUT`; `on_resubscribed` then runs
+    /// once the subscription comes up instead.
+    pub async fn wait_ready(&mut self) -> bool {
+        let Some(ready) = self.ready.take() else {
+            return true;
+        };
+
+        matches!(
+            tokio::time::timeout(SUBSCRIPTION_READY_TIMEOUT, ready).await,
+            Ok(Ok(()))
+        )
+    }
+}
+
+/// Keeps a subscription to `channel` alive for the lifetime of the process.
+///
+/// The subscription is re-established with exponential backoff whenever the
+/// connection drops. Messages published while disconnected are lost, so
+/// `on_resubscribed` runs after every successful reconnect to let the caller
+/// reload whatever state the channel keeps in sync. The first subscription
+/// only signals `SupervisedSubscription::wait_ready`, after which the caller
+/// loads the state itself.
+pub fn spawn_supervised_subscription<T, C, R, Fut>(
+    channel: &str,
+    callback: C,
+    on_resubscribed: R,
+) -> SupervisedSubscription
+where
+    T: DeserializeOwned + Send + 'static,
+    C: Fn(T) -> SubscriptionFuture + Send + 'static + Clone,
+    R: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let channel = channel.to_string();
+    let (initial_ready_tx, initial_ready_rx) = oneshot::channel();
+
+    let handle = tokio::spawn(async move {
+        let mut backoff = SUBSCRIPTION_INITIAL_BACKOFF;
+        let mut initial_ready = Some(initial_ready_tx);
+
+        loop {
+            let (ready_tx, ready_rx) = oneshot::channel();
+            let subscription = tokio::spawn({
+                let channel = channel.clone();
+                let callback = callback.clone();
+                async move {
+                    get_redis_emitter()
+                        .subscribe_with_ready::<T>(&channel, callback, Some(ready_tx))
+                        .await
+                }
+            });
+
+            // The sender is dropped without a value when subscribing fails.
+            if ready_rx.await.is_ok() {
+                tracing::info!("Subscribed to Redis channel {}", channel);
+                backoff = SUBSCRIPTION_INITIAL_BACKOFF;
+
+                // Fails when the caller stopped waiting and loaded its state
+                // before this subscription existed.
+                let signalled = initial_ready
+                    .take()
+                    .is_some_and(|ready| ready.send(()).is_ok());
+                if !signalled {
+                    if let Err(e) = on_resubscribed().await {
+                        tracing::error!(
+                            "Failed to resync state after resubscribing to {}: {e}",
+                            channel
+                        );
+                    }
+                }
+            }
+
+            match subscription.await {
+                Ok(Ok(())) => tracing::warn!("Subscription to {} ended", channel),
+                Ok(Err(e)) => tracing::error!("Subscription to {} failed: {e}", channel),
+                Err(e) => tracing::error!("Subscription task for {} panicked: {e}", channel),
+            }
+
+            tracing::info!("Reconnecting to Redis channel {} in {:?}", channel, backoff);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(SUBSCRIPTION_MAX_BACKOFF);
+        }
+    });
+
+    SupervisedSubscription {
+        handle,
+        ready: Some(initial_ready_rx),
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use bb8::Pool;
 use bb8_redis::RedisConnectionManager;
 use bb8_redis::redis::{self, AsyncCommands};
@@ -151,4 +153,85 @@ impl RedisService {
             None => Ok(None),
         }
     }
+
+    /// Increments a counter, starting its expiry when the key is first created.
+    pub async fn incr_ex(&self, key: &str, seconds: i64) -> Result<i64, Error> {
+        let mut conn = self.pool.get().await?;
+        let count: i64 = conn.incr(key, 1).await?;
+        if count == 1 {
+            let _: () = conn.expire(key, seconds).await?;
+        }
+
+        Ok(count)
+    }
+
+    /// Sets `key` with an expiry unless it exists. Returns whether it was set.
+    pub async fn set_nx_ex(&self, key: &str, seconds: u64) -> Result<bool, Error> {
+        let mut conn = self.pool.get().await?;
+        let set: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(seconds)
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(set.is_some())
+    }
+
+    /// Sets a hash field and (re)starts the expiry of the whole hash.
+    pub async fn hset_ex(
+        &self,
+        key: &str,
+        field: &str,
+        value: &str,
+        seconds: i64,
+    ) -> Result<(), Error> {
+        let mut conn = self.pool.get().await?;
+        let _: () = redis::pipe()
+            .hset(key, field, value)
+            .ignore()
+            .expire(key, seconds)
+            .ignore()
+            .query_async(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn hgetall(&self, key: &str) -> Result<HashMap<String, String>, Error> {
+        let mut conn = self.pool.get().await?;
+        let values: HashMap<String, String> = conn.hgetall(key).await?;
+
+        Ok(values)
+    }
+
+    pub async fn hdel(&self, key: &str, fields: &[String]) -> Result<(), Error> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.pool.get().await?;
+        let _: () = conn.hdel(key, fields).await?;
+
+        Ok(())
+    }
+
+    /// Reads and deletes a key atomically, so a value can only be consumed once.
+    pub async fn take_cache<T: serde::de::DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Result<Option<T>, Error> {
+        let mut conn = self.pool.get().await?;
+        let value: Option<String> = conn.get_del(key).await?;
+
+        match value {
+            Some(val) => {
+                let cache_value = serde_json::from_str(&val)?;
+                Ok(Some(cache_value))
+            }
+            None => Ok(None),
+        }
+    }
 }
@@ -0,0 +1,423 @@
+//! Direct APNs provider speaking HTTP/2 with token-based (.p8) authentication.
+//!
+//! Only delivers to device tokens registered with the `apns` provider; tokens
+//! issued by FCM stay with the FCM channel whatever the device platform.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use tokio_retry::{RetryIf, strategy::ExponentialBackoff};
+
+use crate::config::APP_CONFIG;
+use crate::core::delivery::throttle::{record_delivery, throttle_target};
+use crate::core::delivery::{
+    ChannelCapabilities, DeliveryChannel, DeliveryError, DeliveryErrorKind, DeliveryPriority,
+    DeliveryRequest, TargetOutcome,
+};
+use crate::enums::TokenProvider;
+use crate::errors::Error;
+use crate::loading_fcm_token::{get_user_fcm_tokens, prune_fcm_token};
+
+const APNS_RETRY_ATTEMPTS: usize = 3;
+const APNS_RETRY_INITIAL_DELAY_MS: u64 = 100;
+const APNS_SEND_CONCURRENCY: usize = 8;
+const APNS_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Apple rejects provider tokens older than an hour and throttles refreshing
+/// them more often than every 20 minutes.
+const APNS_PROVIDER_TOKEN_TTL_SECS: i64 = 50 * 60;
+
+/// APNs reasons meaning the device token will never be deliverable again.
+const APNS_PERMANENT_ERROR_REASONS: [&str; 2] = ["BadDeviceToken", "Unregistered"];
+
+pub struct ApnsChannel {
+    client: reqwest::Client,
+    base_url: String,
+    topic: String,
+    key_id: String,
+    team_id: String,
+    signing_key: EncodingKey,
+    provider_token: Mutex<Option<ProviderToken>>,
+}
+
+struct ProviderToken {
+    jwt: String,
+    issued_at: i64,
+}
+
+#[derive(Serialize)]
+struct ProviderClaims<'a> {
+    iss: &'a str,
+    iat: i64,
+}
+
+#[derive(Serialize)]
+struct ApnsPayload<'a> {
+    aps: Aps<'a>,
+    /// Custom keys next to `aps`, read by the app when the push is opened.
+    #[serde(flatten)]
+    data: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Aps<'a> {
+    alert: ApsAlert<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    badge: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sound: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ApsAlert<'a> {
+    title: &'a str,
+    body: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ApnsErrorBody {
+    reason: String,
+}
+
+impl ApnsChannel {
+    pub fn new() -> Result<Self, Error> {
+        let key_path = required_setting(&APP_CONFIG.apns_key_path, "APNS_KEY_PATH")?;
+        let key_id = required_setting(&APP_CONFIG.apns_key_id, "APNS_KEY_ID")?;
+        let team_id = required_setting(&APP_CONFIG.apns_team_id, "APNS_TEAM_ID")?;
+        let topic = required_setting(&APP_CONFIG.apns_topic, "APNS_TOPIC")?;
+
+        let key = std::fs::read(&key_path).map_err(|e| {
+            Error::internal_err(&format!("Failed to read APNs key {key_path}: {e}"))
+        })?;
+        let signing_key = EncodingKey::from_ec_pem(&key)
+            .map_err(|e| Error::internal_err(&format!("Invalid APNs key {key_path}: {e}")))?;
+
+        let base_url = APP_CONFIG.apns_base_url.trim_end_matches('/').to_string();
+
+        let mut builder =
+            reqwest::Client::builder().timeout(Duration::from_secs(APNS_REQUEST_TIMEOUT_SECS));
+        // APNs only speaks HTTP/2. Over TLS it is negotiated through ALPN, but a
+        // cleartext mock server has to be spoken to in HTTP/2 from the start.
+        if base_url.starts_with("http://") {
+            builder = builder.http2_prior_knowledge();
+        }
+        let client = builder
+            .build()
+            .map_err(|e| Error::internal_err(&format!("Failed to create APNs client: {e}")))?;
+
+        Ok(Self {
+            client,
+            base_url,
+            topic,
+            key_id,
+            team_id,
+            signing_key,
+            provider_token: Mutex::new(None),
+        })
+    }
+
+    fn provider_token(&self) -> Result<String, Error> {
+        let now = chrono::Utc::now().timestamp();
+        let mut cached = self
+            .provider_token
+            .lock()
+            .map_err(|_| Error::internal_err("APNs provider token lock poisoned"))?;
+
+        if let Some(token) = cached.as_ref() {
+            if now - token.issued_at < APNS_PROVIDER_TOKEN_TTL_SECS {
+                return Ok(token.jwt.clone());
+            }
+        }
+
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.key_id.clone());
+        let claims = ProviderClaims {
+            iss: &self.team_id,
+            iat: now,
+        };
+        let jwt = jsonwebtoken::encode(&header, &claims, &self.signing_key).map_err(|e| {
+            Error::internal_err(&format!("Failed to sign APNs provider token: {e}"))
+        })?;
+
+        *cached = Some(ProviderToken {
+            jwt: jwt.clone(),
+            issued_at: now,
+        });
+
+        Ok(jwt)
+    }
+
+    fn invalidate_provider_token(&self) {
+        if let Ok(mut cached) = self.provider_token.lock() {
+            *cached = None;
+        }
+    }
+
+    async fn post_notification(
+        &self,
+        token: &str,
+        request: &DeliveryRequest,
+        title: &str,
+        body: &str,
+    ) -> Result<(), String> {
+        let jwt = self.provider_token().map_err(|e| e.to_string())?;
+
+        let payload = ApnsPayload {
+            aps: Aps {
+                alert: ApsAlert { title, body },
+                badge: request.badge,
+                sound: request.sound.as_deref(),
+                thread_id: request.thread_id.as_deref(),
+                category: request.category.as_deref(),
+            },
+            data: request.data_payload(),
+        };
+
+        let mut http_request = self
+            .client
+            .post(format!("{}/3/device/{}", self.base_url, token))
+            .bearer_auth(jwt)
+            .header("apns-topic", &self.topic)
+            .header("apns-push-type", "alert")
+            .header("apns-priority", apns_priority(request.priority))
+            .json(&payload);
+        if let Some(collapse_id) = &request.collapse_key {
+            http_request = http_request.header("apns-collapse-id", collapse_id);
+        }
+        if let Some(ttl) = request.ttl {
+            let expiration = chrono::Utc::now().timestamp() + ttl.as_secs() as i64;
+            http_request = http_request.header("apns-expiration", expiration.to_string());
+        }
+
+        let response = http_request
+            .send()
+            .await
+            .map_err(|e| format!("APNs request failed: {e}"))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let reason = response
+            .json::<ApnsErrorBody>()
+            .await
+            .map(|body| body.reason)
+            .unwrap_or_else(|_| "Unknown".to_string());
+
+        if reason == "ExpiredProviderToken" {
+            self.invalidate_provider_token();
+        }
+
+        Err(format!("APNs responded {status}: {reason}"))
+    }
+
+    async fn send_to_token(
+        &self,
+        request: &DeliveryRequest,
+        token: String,
+        title: Arc<String>,
+        body: Arc<String>,
+    ) -> TargetOutcome {
+        let retry_strategy = ExponentialBackoff::from_millis(APNS_RETRY_INITIAL_DELAY_MS)
+            .max_delay(Duration::from_secs(5))
+            .take(APNS_RETRY_ATTEMPTS);
+
+        let token_ref = token.as_str();
+
+        let send_result = RetryIf::spawn(
+            retry_strategy,
+            || {
+                let title = Arc::clone(&title);
+                let body = Arc::clone(&body);
+                async move {
+                    self.post_notification(token_ref, request, &title, &body)
+                        .await
+                }
+            },
+            is_transient_apns_error,
+        )
+        .await;
+
+        let user_id = &request.user_id;
+        let result = match send_result {
+            Ok(()) => {
+                if let Err(e) = record_delivery(&token, request).await {
+                    tracing::warn!("Failed to update rate limit for user ID {}: {e}", user_id);
+                }
+
+                tracing::info!(
+                    "APNs notification sent successfully for user ID {}",
+                    user_id
+                );
+                Ok(())
+            }
+            Err(error) => {
+                let kind = self.classify_error(&error);
+
+                if kind == DeliveryErrorKind::Permanent {
+                    tracing::warn!(
+                        "APNs rejected token of user ID {} permanently, pruning it: {}",
+                        user_id,
+                        error
+                    );
+                    if let Err(e) =
+                        prune_fcm_token(user_id.clone(), token.clone(), TokenProvider::Apns).await
+                    {
+                        tracing::error!(
+                            "Failed to prune dead APNs token for user ID {}: {e}",
+                            user_id
+                        );
+                    }
+                }
+
+                Err(DeliveryError {
+                    kind,
+                    message: match kind {
+                        DeliveryErrorKind::Permanent => {
+                            format!("APNs token rejected permanently: {error}")
+                        }
+                        DeliveryErrorKind::Transient => {
+                            format!("APNs send failed after {APNS_RETRY_ATTEMPTS} retries: {error}")
+                        }
+                    },
+                })
+            }
+        };
+
+        TargetOutcome {
+            target: token,
+            result,
+        }
+    }
+}
+
+#[async_trait]
+impl DeliveryChannel for ApnsChannel {
+    fn name(&self) -> &'static str {
+        "apns"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            title: true,
+            data_payload: true,
+            throttled: true,
+            every_event: false,
+        }
+    }
+
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind {
+        classify_apns_error(error)
+    }
+
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error> {
+        let tokens = get_user_fcm_tokens(request.user_id.clone())
+            .await?
+            .into_iter()
+            .filter(|token| token.provider == TokenProvider::Apns)
+            .map(|token| token.token)
+            .collect::<Vec<_>>();
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut send_jobs = Vec::new();
+
+        for token in tokens {
+            match throttle_target(&token, request).await {
+                Some((title, body)) => send_jobs.push((token, Arc::new(title), Arc::new(body))),
+                None => tracing::warn!(
+                    "Skipping APNs notification for user ID {} due to rate limiting.",
+                    request.user_id
+                ),
+            }
+        }
+
+        let outcomes = stream::iter(
+            send_jobs
+                .into_iter()
+                .map(|(token, title, body)| self.send_to_token(request, token, title, body)),
+        )
+        .buffer_unordered(APNS_SEND_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+        Ok(outcomes)
+    }
+}
+
+fn required_setting(value: &Option<String>, name: &str) -> Result<String, Error> {
+    value
+        .clone()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            Error::internal_err(&format!("{name} is required for the apns delivery channel"))
+        })
+}
+
+fn apns_priority(priority: DeliveryPriority) -> &'static str {
+    match priority {
+        DeliveryPriority::High => "10",
+        DeliveryPriority::Normal => "5",
+    }
+}
+
+fn classify_apns_error(error: &str) -> DeliveryErrorKind {
+    if APNS_PERMANENT_ERROR_REASONS
+        .iter()
+        .any(|reason| error.contains(reason))
+    {
+        return DeliveryErrorKind::Permanent;
+    }
+
+    DeliveryErrorKind::Transient
+}
+
+fn is_transient_apns_error<E: std::fmt::Display>(error: &E) -> bool {
+    classify_apns_error(&error.to_string()) == DeliveryErrorKind::Transient
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_dead_device_tokens_as_permanent() {
+        for error in [
+            "APNs responded 400 Bad Request: BadDeviceToken",
+            "APNs responded 410 Gone: Unregistered",
+        ] {
+            assert_eq!(
+                classify_apns_error(error),
+                DeliveryErrorKind::Permanent,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_other_apns_errors_as_transient() {
+        for error in [
+            "APNs responded 400 Bad Request: PayloadTooLarge",
+            "APNs responded 429 Too Many Requests: TooManyRequests",
+            "APNs responded 503 Service Unavailable: ServiceUnavailable",
+        ] {
+            assert_eq!(
+                classify_apns_error(error),
+                DeliveryErrorKind::Transient,
+                "{error}"
+            );
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Remembers which events were pushed to which user, so an event published
+//! twice, e.g. a campaign page republished by the replica taking it over, or a
+//! replayed Kafka batch, reaches the user's devices once.
+
+use crate::core::cache::redis_service::RedisService;
+
+const PUSHED_EVENT_KEY_PREFIX: &str = "raidenx:notification:pushed";
+const PUSHED_EVENT_TTL_SECS: u64 = 60 * 60 * 24; // 24 hours
+
+/// Claims the push of `source_event_id` to `user_id`. Returns `false` when it
+/// was pushed before; when Redis is unavailable the push goes ahead.
+pub async fn claim_push(user_id: &str, source_event_id: &str) -> bool {
+    let key = format!("{PUSHED_EVENT_KEY_PREFIX}:{user_id}:{source_event_id}");
+
+    match RedisService::new()
+        .await
+        .set_nx_ex(&key, PUSHED_EVENT_TTL_SECS)
+        .await
+    {
+        Ok(claimed) => claimed,
+        Err(e) => {
+            tracing::warn!("Failed to check whether {key} was pushed: {e}");
+            true
+        }
+    }
+}
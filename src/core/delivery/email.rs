@@ -0,0 +1,282 @@
+//! Email channel for account security events, sent over SMTP.
+//!
+//! Only account notifications flagged by `AccountNotifType::is_security_event`
+//! are mailed; everything else is left to the push channels. Preferences are
+//! applied before dispatch, so a user who turned account notifications off gets
+//! no email either. Addresses the relay rejects permanently are suppressed.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use minijinja::{Environment, context};
+use tokio_retry::{RetryIf, strategy::ExponentialBackoff};
+
+use crate::config::APP_CONFIG;
+use crate::core::delivery::{
+    ChannelCapabilities, DeliveryChannel, DeliveryError, DeliveryErrorKind, DeliveryRequest,
+    TargetOutcome,
+};
+use crate::errors::Error;
+use crate::models::accounts::Account;
+use crate::models::email_suppressions::EmailSuppression;
+use crate::utils::structs::NotifMetadata;
+
+const EMAIL_RETRY_ATTEMPTS: usize = 3;
+const EMAIL_RETRY_INITIAL_DELAY_MS: u64 = 500;
+
+const ACCOUNT_SECURITY_HTML: &str = "account_security.html";
+const ACCOUNT_SECURITY_TEXT: &str = "account_security.txt";
+
+pub struct EmailChannel {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    templates: Environment<'static>,
+}
+
+impl EmailChannel {
+    pub fn new() -> Result<Self, Error> {
+        let host = APP_CONFIG
+            .smtp_host
+            .clone()
+            .ok_or_else(|| Error::internal_err("SMTP_HOST is required for the email channel"))?;
+        let from = APP_CONFIG
+            .email_from
+            .as_deref()
+            .ok_or_else(|| Error::internal_err("EMAIL_FROM is required for the email channel"))?
+            .parse::<Mailbox>()
+            .map_err(|e| Error::internal_err(&format!("Invalid EMAIL_FROM: {e}")))?;
+
+        let builder = match APP_CONFIG.smtp_tls.as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host),
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host),
+            "none" => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &host,
+            )),
+            other => {
+                return Err(Error::internal_err(&format!(
+                    "Invalid SMTP_TLS {other}, expected tls, starttls or none"
+                )));
+            }
+        }
+        .map_err(|e| Error::internal_err(&format!("Failed to create SMTP transport: {e}")))?;
+
+        let mut builder = builder.port(APP_CONFIG.smtp_port);
+        if let (Some(username), Some(password)) =
+            (&APP_CONFIG.smtp_username, &APP_CONFIG.smtp_password)
+        {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        let mut templates = Environment::new();
+        for (name, source) in [
+            (
+                ACCOUNT_SECURITY_HTML,
+                include_str!("../../../templates/email/account_security.html"),
+            ),
+            (
+                ACCOUNT_SECURITY_TEXT,
+                include_str!("../../../templates/email/account_security.txt"),
+            ),
+        ] {
+            templates
+                .add_template(name, source)
+                .map_err(|e| Error::internal_err(&format!("Invalid email template {name}: {e}")))?;
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+            templates,
+        })
+    }
+
+    fn render(
+        &self,
+        name: &str,
+        display_name: &str,
+        request: &DeliveryRequest,
+    ) -> Result<String, Error> {
+        self.templates
+            .get_template(name)
+            .and_then(|template| {
+                template.render(context! {
+                    title => request.title,
+                    display_name => display_name,
+                    message => request.body,
+                })
+            })
+            .map_err(|e| Error::internal_err(&format!("Failed to render {name}: {e}")))
+    }
+
+    async fn send_email(
+        &self,
+        to: &str,
+        html: String,
+        text: String,
+        subject: &str,
+    ) -> Result<(), DeliveryError> {
+        let to = to.parse::<Mailbox>().map_err(|e| DeliveryError {
+            kind: DeliveryErrorKind::Permanent,
+            message: format!("Invalid email address: {e}"),
+        })?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(subject)
+            .multipart(MultiPart::alternative_plain_html(text, html))
+            .map_err(|e| DeliveryError {
+                kind: DeliveryErrorKind::Transient,
+                message: format!("Failed to build email: {e}"),
+            })?;
+
+        let retry_strategy = ExponentialBackoff::from_millis(EMAIL_RETRY_INITIAL_DELAY_MS)
+            .max_delay(Duration::from_secs(10))
+            .take(EMAIL_RETRY_ATTEMPTS);
+
+        let transport = &self.transport;
+        RetryIf::spawn(
+            retry_strategy,
+            move || transport.send(message.clone()),
+            is_transient_smtp_error,
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            let message = e.to_string();
+            DeliveryError {
+                kind: self.classify_error(&message),
+                message,
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl DeliveryChannel for EmailChannel {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            title: true,
+            data_payload: false,
+            throttled: false,
+            every_event: false,
+        }
+    }
+
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind {
+        classify_email_error(error)
+    }
+
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error> {
+        let Some(NotifMetadata::Account(account_data)) = &request.metadata else {
+            return Ok(Vec::new());
+        };
+        if !account_data.activity_type.is_security_event() {
+            return Ok(Vec::new());
+        }
+
+        let Some(account) = Account::get_account_by_user_id(&request.user_id).await? else {
+            tracing::warn!(
+                "Account not found for user ID {}. Skipping email.",
+                request.user_id
+            );
+            return Ok(Vec::new());
+        };
+        let Some(email) = account.email.filter(|email| !email.is_empty()) else {
+            tracing::debug!("No email address for user ID {}", request.user_id);
+            return Ok(Vec::new());
+        };
+
+        if EmailSuppression::is_suppressed(&email).await? {
+            tracing::info!(
+                "Email address of user ID {} is suppressed. Skipping email.",
+                request.user_id
+            );
+            return Ok(Vec::new());
+        }
+
+        let html = self.render(ACCOUNT_SECURITY_HTML, &account.display_name, request)?;
+        let text = self.render(ACCOUNT_SECURITY_TEXT, &account.display_name, request)?;
+
+        let result = self.send_email(&email, html, text, &request.title).await;
+
+        match &result {
+            Ok(()) => tracing::info!("Email sent successfully for user ID {}", request.user_id),
+            Err(e) if e.kind == DeliveryErrorKind::Permanent => {
+                tracing::warn!(
+                    "Email of user ID {} bounced permanently, suppressing it: {}",
+                    request.user_id,
+                    e.message
+                );
+                if let Err(e) =
+                    EmailSuppression::suppress(&email, &request.user_id, &e.message).await
+                {
+                    tracing::error!(
+                        "Failed to suppress email address of user ID {}: {e}",
+                        request.user_id
+                    );
+                }
+            }
+            Err(_) => {}
+        }
+
+        Ok(vec![TargetOutcome {
+            target: email,
+            result,
+        }])
+    }
+}
+
+fn is_transient_smtp_error(error: &lettre::transport::smtp::Error) -> bool {
+    !error.is_permanent()
+}
+
+/// lettre reports 5xx replies as "permanent error (5xx)" and 4xx replies as
+/// "transient error (4xx)"; connection problems carry neither prefix.
+fn classify_email_error(error: &str) -> DeliveryErrorKind {
+    if error.starts_with("permanent error") || error.starts_with("Invalid email address") {
+        DeliveryErrorKind::Permanent
+    } else {
+        DeliveryErrorKind::Transient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_rejected_recipients_as_permanent() {
+        for error in [
+            "permanent error (550): 5.1.1 mailbox unavailable",
+            "Invalid email address: not-an-email",
+        ] {
+            assert_eq!(
+                classify_email_error(error),
+                DeliveryErrorKind::Permanent,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_deferrals_and_connection_errors_as_transient() {
+        for error in [
+            "transient error (451): 4.7.1 try again later",
+            "Connection error: Connection refused (os error 111)",
+        ] {
+            assert_eq!(
+                classify_email_error(error),
+                DeliveryErrorKind::Transient,
+                "{error}"
+            );
+        }
+    }
+}
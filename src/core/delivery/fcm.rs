@@ -0,0 +1,557 @@
+//! FCM HTTP v1 client, authorized with an OAuth2 access token obtained by
+//! signing a JWT with the Firebase service account key.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio_retry::{RetryIf, strategy::ExponentialBackoff};
+
+use crate::config::APP_CONFIG;
+use crate::core::delivery::throttle::{record_delivery, throttle_target};
+use crate::core::delivery::{
+    ChannelCapabilities, DeliveryChannel, DeliveryError, DeliveryErrorKind, DeliveryPriority,
+    DeliveryRequest, TargetOutcome,
+};
+use crate::enums::TokenProvider;
+use crate::errors::Error;
+use crate::loading_fcm_token::{get_user_fcm_tokens, prune_fcm_token};
+
+const FCM_RETRY_ATTEMPTS: usize = 3;
+const FCM_RETRY_INITIAL_DELAY_MS: u64 = 100;
+const FCM_SEND_CONCURRENCY: usize = 8;
+const FCM_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+const FCM_OAUTH_SCOPE: &str = "https://www.googleapis.com/auth/firebase.messaging";
+const GOOGLE_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+
+/// Google issues access tokens for an hour; refresh them a little earlier so
+/// a request never goes out with one about to expire.
+const ACCESS_TOKEN_EXPIRY_MARGIN_SECS: i64 = 5 * 60;
+
+/// FCM error codes meaning the token will never be deliverable again.
+const FCM_PERMANENT_ERROR_CODES: [&str; 2] = ["UNREGISTERED", "SENDER_ID_MISMATCH"];
+
+pub struct FcmChannel {
+    client: reqwest::Client,
+    send_url: String,
+    client_email: String,
+    token_uri: String,
+    signing_key: EncodingKey,
+    access_token: Mutex<Option<AccessToken>>,
+}
+
+struct AccessToken {
+    token: String,
+    expires_at: i64,
+}
+
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    project_id: String,
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+#[derive(Serialize)]
+struct AssertionClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    access_token: String,
+    expires_in: i64,
+}
+
+#[derive(Serialize)]
+struct FcmSendRequest<'a> {
+    message: FcmMessage<'a>,
+}
+
+#[derive(Serialize)]
+struct FcmMessage<'a> {
+    token: &'a str,
+    notification: FcmNotification<'a>,
+    data: &'a HashMap<String, String>,
+    android: AndroidConfig<'a>,
+    apns: ApnsConfig<'a>,
+}
+
+#[derive(Serialize)]
+struct FcmNotification<'a> {
+    title: &'a str,
+    body: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct AndroidConfig<'a> {
+    priority: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collapse_key: Option<&'a str>,
+    notification: AndroidNotification<'a>,
+}
+
+#[derive(Serialize)]
+struct AndroidNotification<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    channel_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sound: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ApnsConfig<'a> {
+    headers: HashMap<&'static str, String>,
+    payload: ApnsPayload<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fcm_options: Option<ApnsFcmOptions<'a>>,
+}
+
+#[derive(Serialize)]
+struct ApnsPayload<'a> {
+    aps: Aps<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Aps<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sound: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    badge: Option<u32>,
+    /// Lets the notification service extension download the image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mutable_content: Option<u8>,
+}
+
+#[derive(Serialize)]
+struct ApnsFcmOptions<'a> {
+    image: &'a str,
+}
+
+#[derive(Deserialize)]
+struct FcmErrorResponse {
+    error: FcmErrorBody,
+}
+
+#[derive(Deserialize)]
+struct FcmErrorBody {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    details: Vec<FcmErrorDetail>,
+}
+
+#[derive(Deserialize)]
+struct FcmErrorDetail {
+    #[serde(rename = "errorCode")]
+    error_code: Option<String>,
+}
+
+impl FcmChannel {
+    pub fn new() -> Result<Self, Error> {
+        let path = APP_CONFIG.firebase_credentials_path.as_str();
+        let key = std::fs::read(path).map_err(|e| {
+            Error::internal_err(&format!("Failed to read Firebase credentials {path}: {e}"))
+        })?;
+        let key: ServiceAccountKey = serde_json::from_slice(&key).map_err(|e| {
+            Error::internal_err(&format!("Invalid Firebase credentials {path}: {e}"))
+        })?;
+        let signing_key = EncodingKey::from_rsa_pem(key.private_key.as_bytes()).map_err(|e| {
+            Error::internal_err(&format!("Invalid Firebase private key in {path}: {e}"))
+        })?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(FCM_REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| Error::internal_err(&format!("Failed to create FCM client: {e}")))?;
+
+        Ok(Self {
+            client,
+            send_url: format!(
+                "{}/v1/projects/{}/messages:send",
+                APP_CONFIG.fcm_base_url.trim_end_matches('/'),
+                key.project_id
+            ),
+            client_email: key.client_email,
+            token_uri: key
+                .token_uri
+                .unwrap_or_else(|| GOOGLE_TOKEN_URI.to_string()),
+            signing_key,
+            access_token: Mutex::new(None),
+        })
+    }
+
+    /// Returns the cached access token, exchanging a freshly signed assertion
+    /// for a new one when it is about to expire.
+    async fn access_token(&self) -> Result<String, String> {
+        let now = chrono::Utc::now().timestamp();
+        let mut cached = self.access_token.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if now < token.expires_at - ACCESS_TOKEN_EXPIRY_MARGIN_SECS {
+                return Ok(token.token.clone());
+            }
+        }
+
+        let claims = AssertionClaims {
+            iss: &self.client_email,
+            scope: FCM_OAUTH_SCOPE,
+            aud: &self.token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let assertion =
+            jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &self.signing_key)
+                .map_err(|e| format!("Failed to sign FCM token assertion: {e}"))?;
+
+        let response = self
+            .client
+            .post(&self.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("FCM access token request failed: {e}"))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!(
+                "FCM access token request responded {status}: {body}"
+            ));
+        }
+
+        let token = response
+            .json::<AccessTokenResponse>()
+            .await
+            .map_err(|e| format!("Invalid FCM access token response: {e}"))?;
+
+        *cached = Some(AccessToken {
+            token: token.access_token.clone(),
+            expires_at: now + token.expires_in,
+        });
+
+        Ok(token.access_token)
+    }
+
+    async fn invalidate_access_token(&self) {
+        *self.access_token.lock().await = None;
+    }
+
+    async fn post_message(
+        &self,
+        token: &str,
+        request: &DeliveryRequest,
+        data: &HashMap<String, String>,
+        title: &str,
+        body: &str,
+    ) -> Result<(), String> {
+        let access_token = self.access_token().await?;
+
+        let image = request.image_url.as_deref();
+        let mut apns_headers = HashMap::from([(
+            "apns-priority",
+            match request.priority {
+                DeliveryPriority::High => "10",
+                DeliveryPriority::Normal => "5",
+            }
+            .to_string(),
+        )]);
+        if let Some(ttl) = request.ttl {
+            let expiration = chrono::Utc::now().timestamp() + ttl.as_secs() as i64;
+            apns_headers.insert("apns-expiration", expiration.to_string());
+        }
+        if let Some(collapse_id) = &request.collapse_key {
+            apns_headers.insert("apns-collapse-id", collapse_id.clone());
+        }
+
+        let message = FcmSendRequest {
+            message: FcmMessage {
+                token,
+                notification: FcmNotification { title, body, image },
+                data,
+                android: AndroidConfig {
+                    priority: match request.priority {
+                        DeliveryPriority::High => "HIGH",
+                        DeliveryPriority::Normal => "NORMAL",
+                    },
+                    ttl: request.ttl.map(|ttl| format!("{}s", ttl.as_secs())),
+                    collapse_key: request.collapse_key.as_deref(),
+                    notification: AndroidNotification {
+                        channel_id: request.android_channel_id.as_deref(),
+                        // A shared tag would replace earlier notifications in
+                        // the tray; one per event only replaces its own resends.
+                        tag: request.source_event_id.as_deref(),
+                        sound: request.sound.as_deref(),
+                    },
+                },
+                apns: ApnsConfig {
+                    headers: apns_headers,
+                    payload: ApnsPayload {
+                        aps: Aps {
+                            category: request.category.as_deref(),
+                            thread_id: request.thread_id.as_deref(),
+                            sound: request.sound.as_deref(),
+                            badge: request.badge,
+                            mutable_content: image.map(|_| 1),
+                        },
+                    },
+                    fcm_options: image.map(|image| ApnsFcmOptions { image }),
+                },
+            },
+        };
+
+        let response = self
+            .client
+            .post(&self.send_url)
+            .bearer_auth(access_token)
+            .json(&message)
+            .send()
+            .await
+            .map_err(|e| format!("FCM request failed: {e}"))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            self.invalidate_access_token().await;
+        }
+
+        let error = match response.json::<FcmErrorResponse>().await {
+            Ok(FcmErrorResponse { error }) => {
+                let code = error
+                    .details
+                    .into_iter()
+                    .find_map(|detail| detail.error_code)
+                    .unwrap_or(error.status);
+                format!("{code}: {}", error.message)
+            }
+            Err(_) => "Unknown".to_string(),
+        };
+
+        Err(format!("FCM responded {status}: {error}"))
+    }
+
+    async fn send_to_token(
+        &self,
+        request: &DeliveryRequest,
+        data: &HashMap<String, String>,
+        token: String,
+        title: Arc<String>,
+        body: Arc<String>,
+    ) -> TargetOutcome {
+        let retry_strategy = ExponentialBackoff::from_millis(FCM_RETRY_INITIAL_DELAY_MS)
+            .max_delay(Duration::from_secs(5))
+            .take(FCM_RETRY_ATTEMPTS);
+
+        let token_ref = token.as_str();
+
+        let send_result = RetryIf::spawn(
+            retry_strategy,
+            || {
+                let title = Arc::clone(&title);
+                let body = Arc::clone(&body);
+                async move {
+                    self.post_message(token_ref, request, data, &title, &body)
+                        .await
+                }
+            },
+            is_transient_fcm_error,
+        )
+        .await;
+
+        let user_id = &request.user_id;
+        let result = match send_result {
+            Ok(()) => {
+                if let Err(e) = record_delivery(&token, request).await {
+                    tracing::warn!("Failed to update rate limit for user ID {}: {e}", user_id);
+                }
+
+                tracing::info!("Notification sent successfully for user ID {}", user_id);
+                Ok(())
+            }
+            Err(error) => {
+                let kind = self.classify_error(&error);
+
+                if kind == DeliveryErrorKind::Permanent {
+                    tracing::warn!(
+                        "FCM rejected token of user ID {} permanently, pruning it: {}",
+                        user_id,
+                        error
+                    );
+                    if let Err(e) =
+                        prune_fcm_token(user_id.clone(), token.clone(), TokenProvider::Fcm).await
+                    {
+                        tracing::error!(
+                            "Failed to prune dead FCM token for user ID {}: {e}",
+                            user_id
+                        );
+                    }
+                }
+
+                Err(DeliveryError {
+                    kind,
+                    message: match kind {
+                        DeliveryErrorKind::Permanent => {
+                            format!("FCM token rejected permanently: {error}")
+                        }
+                        DeliveryErrorKind::Transient => {
+                            format!("FCM send failed after {FCM_RETRY_ATTEMPTS} retries: {error}")
+                        }
+                    },
+                })
+            }
+        };
+
+        TargetOutcome {
+            target: token,
+            result,
+        }
+    }
+}
+
+#[async_trait]
+impl DeliveryChannel for FcmChannel {
+    fn name(&self) -> &'static str {
+        "fcm"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            title: true,
+            data_payload: true,
+            throttled: true,
+            every_event: false,
+        }
+    }
+
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind {
+        classify_fcm_error(error)
+    }
+
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error> {
+        // Tokens issued by APNs are delivered by the APNs channel.
+        let tokens = get_user_fcm_tokens(request.user_id.clone())
+            .await?
+            .into_iter()
+            .filter(|token| token.provider == TokenProvider::Fcm)
+            .map(|token| token.token)
+            .collect::<Vec<_>>();
+        if tokens.is_empty() {
+            tracing::warn!(
+                "No FCM tokens found for user ID {}. Skipping notification.",
+                request.user_id
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut send_jobs = Vec::new();
+
+        for token in tokens {
+            match throttle_target(&token, request).await {
+                Some((title, body)) => send_jobs.push((token, Arc::new(title), Arc::new(body))),
+                None => tracing::warn!(
+                    "Skipping notification for user ID {} due to rate limiting.",
+                    request.user_id
+                ),
+            }
+        }
+
+        let data = request.data_payload();
+        let outcomes =
+            stream::iter(send_jobs.into_iter().map(|(token, title, body)| {
+                self.send_to_token(request, &data, token, title, body)
+            }))
+            .buffer_unordered(FCM_SEND_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+
+        Ok(outcomes)
+    }
+}
+
+fn classify_fcm_error(error: &str) -> DeliveryErrorKind {
+    if FCM_PERMANENT_ERROR_CODES
+        .iter()
+        .any(|code| error.contains(code))
+    {
+        return DeliveryErrorKind::Permanent;
+    }
+
+    // INVALID_ARGUMENT is also returned for malformed payloads, which says
+    // nothing about the token, so only treat it as permanent when FCM blames
+    // the registration token.
+    if error.contains("INVALID_ARGUMENT") && error.to_lowercase().contains("registration token") {
+        return DeliveryErrorKind::Permanent;
+    }
+
+    DeliveryErrorKind::Transient
+}
+
+fn is_transient_fcm_error<E: std::fmt::Display>(error: &E) -> bool {
+    classify_fcm_error(&error.to_string()) == DeliveryErrorKind::Transient
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_unregistered_tokens_as_permanent() {
+        for error in [
+            "FCM responded 404 Not Found: UNREGISTERED: Requested entity was not found.",
+            "FCM responded 403 Forbidden: SENDER_ID_MISMATCH: SenderId mismatch",
+            "FCM responded 400 Bad Request: INVALID_ARGUMENT: The registration token is not a valid FCM registration token",
+        ] {
+            assert_eq!(
+                classify_fcm_error(error),
+                DeliveryErrorKind::Permanent,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_payload_and_server_errors_as_transient() {
+        for error in [
+            "FCM responded 400 Bad Request: INVALID_ARGUMENT: Invalid JSON payload received.",
+            "FCM responded 429 Too Many Requests: QUOTA_EXCEEDED: Quota exceeded",
+            "FCM responded 503 Service Unavailable: UNAVAILABLE: The service is unavailable",
+            "FCM request failed: connection reset",
+        ] {
+            assert_eq!(
+                classify_fcm_error(error),
+                DeliveryErrorKind::Transient,
+                "{error}"
+            );
+        }
+    }
+}
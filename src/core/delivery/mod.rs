@@ -0,0 +1,337 @@
+pub mod apns;
+pub mod dedupe;
+pub mod email;
+pub mod fcm;
+pub mod public_host;
+pub mod sms;
+pub mod telegram;
+pub mod throttle;
+pub mod web_push;
+pub mod webhook;
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::config::APP_CONFIG;
+use crate::core::delivery::apns::ApnsChannel;
+use crate::core::delivery::email::EmailChannel;
+use crate::core::delivery::fcm::FcmChannel;
+use crate::core::delivery::sms::SmsChannel;
+use crate::core::delivery::telegram::TelegramChannel;
+use crate::core::delivery::web_push::WebPushChannel;
+use crate::core::delivery::webhook::WebhookChannel;
+use crate::errors::Error;
+use crate::models::accounts::ELanguage;
+use crate::utils::structs::{NotifMetadata, NotifType};
+
+pub type SharedChannel = Arc<dyn DeliveryChannel>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryPriority {
+    /// Delivered immediately, waking the device if needed.
+    High,
+    /// May be delayed or batched by the platform to save power.
+    Normal,
+}
+
+/// How pushes of one notification type are presented and delivered.
+#[derive(Debug, Clone, Copy)]
+pub struct PushProfile {
+    /// Android notification channel the app registers for the type.
+    pub android_channel_id: &'static str,
+    /// iOS notification category, which selects the actions shown.
+    pub category: &'static str,
+    pub priority: DeliveryPriority,
+    /// How long the platform keeps trying to reach an offline device.
+    pub ttl: Duration,
+}
+
+impl PushProfile {
+    pub fn for_type(notif_type: NotifType) -> Self {
+        const HOUR: u64 = 60 * 60;
+
+        match notif_type {
+            NotifType::Order => Self {
+                android_channel_id: "orders",
+                category: "ORDER",
+                priority: DeliveryPriority::High,
+                ttl: Duration::from_secs(HOUR),
+            },
+            NotifType::Transaction => Self {
+                android_channel_id: "transactions",
+                category: "TRANSACTION",
+                priority: DeliveryPriority::High,
+                ttl: Duration::from_secs(24 * HOUR),
+            },
+            NotifType::Account => Self {
+                android_channel_id: "security",
+                category: "ACCOUNT",
+                priority: DeliveryPriority::High,
+                ttl: Duration::from_secs(24 * HOUR),
+            },
+            NotifType::Announcement => Self {
+                android_channel_id: "announcements",
+                category: "ANNOUNCEMENT",
+                priority: DeliveryPriority::Normal,
+                ttl: Duration::from_secs(3 * 24 * HOUR),
+            },
+            NotifType::Campaign => Self {
+                android_channel_id: "promotions",
+                category: "CAMPAIGN",
+                priority: DeliveryPriority::Normal,
+                ttl: Duration::from_secs(24 * HOUR),
+            },
+            // A price alert delivered long after the move is misleading.
+            NotifType::PriceAlert => Self {
+                android_channel_id: "price_alerts",
+                category: "PRICE_ALERT",
+                priority: DeliveryPriority::High,
+                ttl: Duration::from_secs(15 * 60),
+            },
+        }
+    }
+}
+
+/// A rendered notification addressed to a single user.
+#[derive(Debug, Clone)]
+pub struct DeliveryRequest {
+    pub user_id: String,
+    pub notif_type: NotifType,
+    pub title: String,
+    pub body: String,
+    /// Id of the event the notification was created from; the app matches
+    /// the push to the inbox notification with the same `sourceEventId`.
+    pub source_event_id: Option<String>,
+    pub priority: DeliveryPriority,
+    pub ttl: Option<Duration>,
+    pub android_channel_id: Option<String>,
+    pub category: Option<String>,
+    /// Picture shown in the expanded notification.
+    pub image_url: Option<String>,
+    /// Replaces a not yet displayed notification carrying the same key.
+    pub collapse_key: Option<String>,
+    /// Groups related notifications together on the device.
+    pub thread_id: Option<String>,
+    pub badge: Option<u32>,
+    pub sound: Option<String>,
+    /// The event the notification was rendered from.
+    pub metadata: Option<NotifMetadata>,
+    /// When the event happened, in milliseconds since the Unix epoch.
+    pub occurred_at: Option<i64>,
+    /// Language the notification was rendered in.
+    pub language: ELanguage,
+}
+
+impl DeliveryRequest {
+    pub fn new(user_id: String, notif_type: NotifType, title: String, body: String) -> Self {
+        let profile = PushProfile::for_type(notif_type);
+
+        Self {
+            user_id,
+            notif_type,
+            title,
+            body,
+            source_event_id: None,
+            priority: profile.priority,
+            ttl: Some(profile.ttl),
+            android_channel_id: Some(profile.android_channel_id.to_string()),
+            category: Some(profile.category.to_string()),
+            image_url: None,
+            collapse_key: None,
+            thread_id: Some(notif_type.to_string()),
+            badge: None,
+            sound: Some("default".to_string()),
+            metadata: None,
+            occurred_at: None,
+            language: ELanguage::default(),
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata: NotifMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    pub fn with_source_event_id(mut self, source_event_id: String) -> Self {
+        self.source_event_id = Some(source_event_id);
+        self
+    }
+
+    pub fn with_occurred_at(mut self, occurred_at: i64) -> Self {
+        self.occurred_at = Some(occurred_at);
+        self
+    }
+
+    pub fn with_language(mut self, language: ELanguage) -> Self {
+        self.language = language;
+        self
+    }
+
+    pub fn with_image_url(mut self, image_url: String) -> Self {
+        self.image_url = Some(image_url);
+        self
+    }
+
+    /// Mandatory security notifications skip the per-target rate limit.
+    pub fn is_mandatory(&self) -> bool {
+        self.metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.is_mandatory())
+    }
+
+    /// Where tapping the notification takes the user in the app.
+    pub fn deep_link(&self) -> String {
+        let path = match &self.metadata {
+            Some(metadata) => metadata.deep_link_path(),
+            None => "notifications".to_string(),
+        };
+
+        format!(
+            "{}/{}",
+            APP_CONFIG.deep_link_base_url.trim_end_matches('/'),
+            path
+        )
+    }
+
+    /// String key-value pairs handed to the app along with the push.
+    pub fn data_payload(&self) -> HashMap<String, String> {
+        let mut data = HashMap::from([
+            ("type".to_string(), self.notif_type.to_string()),
+            ("deepLink".to_string(), self.deep_link()),
+        ]);
+        if let Some(source_event_id) = &self.source_event_id {
+            data.insert("sourceEventId".to_string(), source_event_id.clone());
+        }
+        match &self.metadata {
+            Some(NotifMetadata::Order(order_data)) => {
+                data.insert("orderId".to_string(), order_data.order_id.to_string());
+            }
+            Some(NotifMetadata::Transaction(transaction_data))
+                if !transaction_data.tx_hash.is_empty() =>
+            {
+                data.insert("txHash".to_string(), transaction_data.tx_hash.clone());
+            }
+            Some(NotifMetadata::PriceAlert(price_alert_data)) => {
+                data.insert("symbol".to_string(), price_alert_data.symbol.clone());
+            }
+            _ => {}
+        }
+
+        data
+    }
+}
+
+/// What a channel supports, so callers can adapt requests to it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChannelCapabilities {
+    /// Shows a title separately from the body.
+    pub title: bool,
+    /// Carries a structured data payload next to the rendered text.
+    pub data_payload: bool,
+    /// Goes through the per-target rate limit and unsent-count summary, unless
+    /// the request is mandatory.
+    pub throttled: bool,
+    /// Gets every incoming event on its own, regardless of push preferences
+    /// and of the per-second collapsing applied to pushes.
+    pub every_event: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryErrorKind {
+    /// The target will never accept deliveries again and should be dropped.
+    Permanent,
+    /// Quota, availability or network problems worth retrying.
+    Transient,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeliveryError {
+    pub kind: DeliveryErrorKind,
+    pub message: String,
+}
+
+impl Display for DeliveryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+/// Result of delivering to one target of a channel, e.g. one device token.
+#[derive(Debug)]
+pub struct TargetOutcome {
+    pub target: String,
+    pub result: Result<(), DeliveryError>,
+}
+
+#[async_trait]
+pub trait DeliveryChannel: Send + Sync {
+    /// Short identifier used in configuration and logs.
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> ChannelCapabilities;
+
+    /// Sorts a provider error for a single target into permanent or transient.
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind;
+
+    /// Delivers `request` to every target the user has on this channel.
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error>;
+}
+
+/// Builds the channels listed in `delivery_channels`.
+pub fn build_channels(names: &[String]) -> Result<Vec<SharedChannel>, Error> {
+    names
+        .iter()
+        .map(|name| match name.trim() {
+            "fcm" => Ok(Arc::new(FcmChannel::new()?) as SharedChannel),
+            "apns" => Ok(Arc::new(ApnsChannel::new()?) as SharedChannel),
+            "email" => Ok(Arc::new(EmailChannel::new()?) as SharedChannel),
+            "webhook" => Ok(Arc::new(WebhookChannel::new()?) as SharedChannel),
+            "telegram" => Ok(Arc::new(TelegramChannel::new()?) as SharedChannel),
+            "webpush" => Ok(Arc::new(WebPushChannel::new()?) as SharedChannel),
+            "sms" => Ok(Arc::new(SmsChannel::new()?) as SharedChannel),
+            other => Err(Error::internal_err(&format!(
+                "Unknown delivery channel: {other}"
+            ))),
+        })
+        .collect()
+}
+
+/// Sends `request` through every channel concurrently and logs failed targets.
+pub async fn dispatch(channels: &[SharedChannel], request: &DeliveryRequest) {
+    let sends = channels
+        .iter()
+        .map(|channel| async move { (channel.name(), channel.send(request).await) });
+
+    for (channel, result) in futures::future::join_all(sends).await {
+        match result {
+            Ok(outcomes) => {
+                for outcome in outcomes {
+                    match outcome.result {
+                        Ok(()) => {}
+                        Err(e) if e.kind == DeliveryErrorKind::Permanent => tracing::warn!(
+                            "{} target of user ID {} rejected permanently: {}",
+                            channel,
+                            request.user_id,
+                            e.message
+                        ),
+                        Err(e) => tracing::error!(
+                            "Failed to deliver {} notification to user ID {}: {}",
+                            channel,
+                            request.user_id,
+                            e.message
+                        ),
+                    }
+                }
+            }
+            Err(e) => tracing::error!(
+                "{} delivery failed for user ID {}: {e}",
+                channel,
+                request.user_id
+            ),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Guards for outbound requests to user-supplied URLs, so webhooks and push
+//! endpoints cannot be used to reach services inside our network.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+/// Resolves hosts to public addresses only, so a name cannot be pointed at an
+/// internal service after the URL was accepted.
+pub struct PublicDnsResolver;
+
+impl Resolve for PublicDnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public_host(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `ip` is reachable over the internet, i.e. not loopback, private,
+/// link-local, unique local, shared, multicast or otherwise reserved.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_ipv4(mapped);
+            }
+
+            let segments = ip.segments();
+            // NAT64 addresses reach the IPv4 address in their last 32 bits.
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_public_ipv4(Ipv4Addr::new(a, b, c, d));
+            }
+
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // fc00::/7, unique local
+                || (segments[0] & 0xfe00) == 0xfc00
+                // fe80::/10, link-local
+                || (segments[0] & 0xffc0) == 0xfe80
+                // 2001:db8::/32, documentation
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8, "this network"
+        || a == 0
+        // 100.64.0.0/10, carrier-grade NAT
+        || (a == 100 && (b & 0xc0) == 64)
+        // 198.18.0.0/15, benchmarking
+        || (a == 198 && (b & 0xfe) == 18)
+        // 240.0.0.0/4, reserved
+        || a >= 240)
+}
+
+/// Resolves `host` and fails unless every address it resolves to is public.
+pub async fn resolve_public_host(host: &str) -> Result<Vec<SocketAddr>, String> {
+    let addrs = tokio::net::lookup_host((host, 0))
+        .await
+        .map_err(|e| format!("Failed to resolve {host}: {e}"))?
+        .collect::<Vec<_>>();
+
+    if addrs.is_empty() {
+        return Err(format!("{host} has no address"));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!(
+            "{host} resolves to non-public address {}",
+            addr.ip()
+        ));
+    }
+
+    Ok(addrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_internal_addresses() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip} is not public");
+        }
+    }
+
+    #[test]
+    fn accepts_public_addresses() {
+        for ip in [
+            "8.8.8.8",
+            "1.1.1.1",
+            "2606:4700:4700::1111",
+            "::ffff:8.8.8.8",
+        ] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip} is public");
+        }
+    }
+}
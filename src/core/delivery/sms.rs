@@ -0,0 +1,374 @@
+//! SMS channel for the most urgent account events.
+//!
+//! Only account events listed in `sms_event_types` are texted, to the E.164
+//! number on the user's account, and at most `sms_daily_limit` times per user
+//! per UTC day. Every attempt, including ones held back by the cap, is stored
+//! as an `SmsDelivery`.
+
+use std::fmt::{Display, Formatter};
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio_retry::{RetryIf, strategy::ExponentialBackoff};
+
+use crate::config::APP_CONFIG;
+use crate::core::cache::redis_service::RedisService;
+use crate::core::delivery::{
+    ChannelCapabilities, DeliveryChannel, DeliveryError, DeliveryErrorKind, DeliveryRequest,
+    TargetOutcome,
+};
+use crate::enums::SmsDeliveryStatus;
+use crate::errors::Error;
+use crate::models::accounts::Account;
+use crate::models::sms_deliveries::SmsDelivery;
+use crate::utils::structs::NotifMetadata;
+
+const SMS_RETRY_ATTEMPTS: usize = 3;
+const SMS_RETRY_INITIAL_DELAY_MS: u64 = 200;
+const SMS_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Counters outlive their UTC day a little so a late increment never resets one.
+const SMS_DAILY_COUNTER_TTL_SECS: i64 = 2 * 24 * 60 * 60;
+
+static E164_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\+[1-9][0-9]{7,14}$").expect("valid E.164 pattern"));
+
+pub fn is_valid_e164(phone_number: &str) -> bool {
+    E164_REGEX.is_match(phone_number)
+}
+
+#[derive(Debug)]
+pub struct SmsError {
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+impl Display for SmsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "SMS provider responded {status}: {}", self.message),
+            None => write!(f, "SMS request failed: {}", self.message),
+        }
+    }
+}
+
+/// Sends a text message, returning the provider's message ID when it has one.
+#[async_trait]
+pub trait SmsProvider: Send + Sync {
+    async fn send_sms(&self, to: &str, text: &str) -> Result<Option<String>, SmsError>;
+}
+
+/// Provider speaking a plain JSON API: `POST {base_url}/messages` with a
+/// bearer key, answering with the ID of the queued message.
+pub struct HttpSmsProvider {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    sender: String,
+}
+
+#[derive(Serialize)]
+struct SendSmsRequest<'a> {
+    from: &'a str,
+    to: &'a str,
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+struct SendSmsResponse {
+    #[serde(default, alias = "messageId")]
+    id: Option<String>,
+}
+
+impl HttpSmsProvider {
+    pub fn from_config() -> Result<Self, Error> {
+        let base_url = APP_CONFIG
+            .sms_api_url
+            .as_deref()
+            .ok_or_else(|| Error::internal_err("SMS_API_URL is required for the sms channel"))?
+            .trim_end_matches('/')
+            .to_string();
+        let api_key = APP_CONFIG
+            .sms_api_key
+            .clone()
+            .ok_or_else(|| Error::internal_err("SMS_API_KEY is required for the sms channel"))?;
+        let sender = APP_CONFIG
+            .sms_sender
+            .clone()
+            .ok_or_else(|| Error::internal_err("SMS_SENDER is required for the sms channel"))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(SMS_REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| Error::internal_err(&format!("Failed to create SMS client: {e}")))?;
+
+        Ok(Self {
+            client,
+            base_url,
+            api_key,
+            sender,
+        })
+    }
+}
+
+#[async_trait]
+impl SmsProvider for HttpSmsProvider {
+    async fn send_sms(&self, to: &str, text: &str) -> Result<Option<String>, SmsError> {
+        let response = self
+            .client
+            .post(format!("{}/messages", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&SendSmsRequest {
+                from: &self.sender,
+                to,
+                text,
+            })
+            .send()
+            .await
+            .map_err(|e| SmsError {
+                status: None,
+                message: e.to_string(),
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(SmsError {
+                status: Some(status.as_u16()),
+                message: message.chars().take(500).collect(),
+            });
+        }
+
+        // The message is queued either way; a body we cannot read only loses its ID.
+        Ok(response
+            .json::<SendSmsResponse>()
+            .await
+            .ok()
+            .and_then(|body| body.id))
+    }
+}
+
+pub struct SmsChannel {
+    provider: Box<dyn SmsProvider>,
+}
+
+impl SmsChannel {
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self::with_provider(Box::new(
+            HttpSmsProvider::from_config()?
+        )))
+    }
+
+    pub fn with_provider(provider: Box<dyn SmsProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// Counts this message against the user's daily limit, returning whether it
+    /// may still be sent.
+    async fn reserve_daily_slot(&self, user_id: &str) -> Result<bool, Error> {
+        let key = format!(
+            "raidenx:sms:daily:{user_id}:{}",
+            chrono::Utc::now().format("%Y%m%d")
+        );
+        let count = RedisService::new()
+            .await
+            .incr_ex(&key, SMS_DAILY_COUNTER_TTL_SECS)
+            .await?;
+
+        Ok(count <= APP_CONFIG.sms_daily_limit)
+    }
+}
+
+#[async_trait]
+impl DeliveryChannel for SmsChannel {
+    fn name(&self) -> &'static str {
+        "sms"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            title: false,
+            data_payload: false,
+            throttled: false,
+            every_event: false,
+        }
+    }
+
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind {
+        classify_sms_error(error)
+    }
+
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error> {
+        let Some(NotifMetadata::Account(account_data)) = &request.metadata else {
+            return Ok(Vec::new());
+        };
+        let event_type = account_data.activity_type.config_key();
+        if !APP_CONFIG
+            .sms_event_types
+            .iter()
+            .any(|allowed| allowed.trim() == event_type)
+        {
+            return Ok(Vec::new());
+        }
+
+        let Some(account) = Account::get_account_by_user_id(&request.user_id).await? else {
+            tracing::warn!(
+                "Account not found for user ID {}. Skipping SMS.",
+                request.user_id
+            );
+            return Ok(Vec::new());
+        };
+        let Some(phone_number) = account.phone_number.filter(|phone| !phone.is_empty()) else {
+            tracing::debug!("No phone number for user ID {}", request.user_id);
+            return Ok(Vec::new());
+        };
+
+        if !is_valid_e164(&phone_number) {
+            let message = "Invalid phone number, expected E.164".to_string();
+            if let Err(e) = SmsDelivery::record(
+                &request.user_id,
+                &phone_number,
+                &event_type,
+                SmsDeliveryStatus::Failed,
+                None,
+                Some(message.clone()),
+            )
+            .await
+            {
+                tracing::warn!("Failed to log SMS of user ID {}: {e}", request.user_id);
+            }
+
+            return Ok(vec![TargetOutcome {
+                target: phone_number,
+                result: Err(DeliveryError {
+                    kind: DeliveryErrorKind::Permanent,
+                    message,
+                }),
+            }]);
+        }
+
+        if !self.reserve_daily_slot(&request.user_id).await? {
+            tracing::warn!(
+                "User ID {} reached the daily SMS limit. Skipping SMS.",
+                request.user_id
+            );
+            if let Err(e) = SmsDelivery::record(
+                &request.user_id,
+                &phone_number,
+                &event_type,
+                SmsDeliveryStatus::Capped,
+                None,
+                None,
+            )
+            .await
+            {
+                tracing::warn!("Failed to log SMS of user ID {}: {e}", request.user_id);
+            }
+            return Ok(Vec::new());
+        }
+
+        let text = format!("{}: {}", request.title, request.body);
+        let retry_strategy = ExponentialBackoff::from_millis(SMS_RETRY_INITIAL_DELAY_MS)
+            .max_delay(Duration::from_secs(5))
+            .take(SMS_RETRY_ATTEMPTS);
+
+        let send_result = RetryIf::spawn(
+            retry_strategy,
+            || self.provider.send_sms(&phone_number, &text),
+            is_transient_sms_error,
+        )
+        .await;
+
+        let (status, provider_message_id, error) = match &send_result {
+            Ok(message_id) => (SmsDeliveryStatus::Sent, message_id.clone(), None),
+            Err(e) => (SmsDeliveryStatus::Failed, None, Some(e.to_string())),
+        };
+        if let Err(e) = SmsDelivery::record(
+            &request.user_id,
+            &phone_number,
+            &event_type,
+            status,
+            provider_message_id,
+            error,
+        )
+        .await
+        {
+            tracing::warn!("Failed to log SMS of user ID {}: {e}", request.user_id);
+        }
+
+        let result = match send_result {
+            Ok(_) => {
+                tracing::info!("SMS sent successfully for user ID {}", request.user_id);
+                Ok(())
+            }
+            Err(e) => {
+                let message = e.to_string();
+                Err(DeliveryError {
+                    kind: self.classify_error(&message),
+                    message,
+                })
+            }
+        };
+
+        Ok(vec![TargetOutcome {
+            target: phone_number,
+            result,
+        }])
+    }
+}
+
+fn is_transient_sms_error(error: &SmsError) -> bool {
+    match error.status {
+        None => true,
+        Some(status) => status == 429 || status >= 500,
+    }
+}
+
+/// Providers answer 400 or 422 for numbers they can never deliver to.
+fn classify_sms_error(error: &str) -> DeliveryErrorKind {
+    if error.contains("responded 400")
+        || error.contains("responded 422")
+        || error.starts_with("Invalid phone number")
+    {
+        DeliveryErrorKind::Permanent
+    } else {
+        DeliveryErrorKind::Transient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_undeliverable_numbers_as_permanent() {
+        for error in [
+            "SMS provider responded 400: invalid destination",
+            "SMS provider responded 422: number is not mobile",
+            "Invalid phone number, expected E.164",
+        ] {
+            assert_eq!(
+                classify_sms_error(error),
+                DeliveryErrorKind::Permanent,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_other_sms_errors_as_transient() {
+        for error in [
+            "SMS provider responded 429: rate limited",
+            "SMS provider responded 500: internal error",
+        ] {
+            assert_eq!(
+                classify_sms_error(error),
+                DeliveryErrorKind::Transient,
+                "{error}"
+            );
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Telegram channel delivering through a bot to the chat a user linked.
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tokio_retry::{RetryIf, strategy::ExponentialBackoff};
+
+use crate::config::APP_CONFIG;
+use crate::core::delivery::throttle::{record_delivery, throttle_target};
+use crate::core::delivery::{
+    ChannelCapabilities, DeliveryChannel, DeliveryError, DeliveryErrorKind, DeliveryRequest,
+    TargetOutcome,
+};
+use crate::errors::Error;
+use crate::models::telegram_links::TelegramLink;
+
+const TELEGRAM_RETRY_ATTEMPTS: usize = 3;
+const TELEGRAM_RETRY_INITIAL_DELAY_MS: u64 = 100;
+const TELEGRAM_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+static TELEGRAM_BOT: OnceCell<TelegramBot> = OnceCell::new();
+
+/// Minimal Bot API client shared by the delivery channel and the bot webhook.
+pub struct TelegramBot {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+#[derive(Serialize)]
+struct SendMessage<'a> {
+    chat_id: i64,
+    text: &'a str,
+}
+
+#[derive(Deserialize)]
+struct BotApiResponse {
+    ok: bool,
+    #[serde(default)]
+    error_code: Option<u16>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct TelegramError {
+    pub status: Option<u16>,
+    pub description: String,
+}
+
+impl Display for TelegramError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "Telegram responded {status}: {}", self.description),
+            None => write!(f, "Telegram request failed: {}", self.description),
+        }
+    }
+}
+
+pub fn telegram_bot() -> Result<&'static TelegramBot, Error> {
+    TELEGRAM_BOT.get_or_try_init(TelegramBot::from_config)
+}
+
+impl TelegramBot {
+    fn from_config() -> Result<Self, Error> {
+        let token = APP_CONFIG
+            .telegram_bot_token
+            .clone()
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| Error::internal_err("TELEGRAM_BOT_TOKEN is not configured"))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(TELEGRAM_REQUEST_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| Error::internal_err(&format!("Failed to create Telegram client: {e}")))?;
+
+        Ok(Self {
+            client,
+            base_url: APP_CONFIG
+                .telegram_api_base_url
+                .trim_end_matches('/')
+                .to_string(),
+            token,
+        })
+    }
+
+    pub async fn send_message(&self, chat_id: i64, text: &str) -> Result<(), TelegramError> {
+        let response = self
+            .client
+            .post(format!("{}/bot{}/sendMessage", self.base_url, self.token))
+            .json(&SendMessage { chat_id, text })
+            .send()
+            .await
+            .map_err(|e| TelegramError {
+                status: None,
+                // reqwest errors include the URL, which carries the bot token.
+                description: e.without_url().to_string(),
+            })?;
+
+        let status = response.status().as_u16();
+        let body = response
+            .json::<BotApiResponse>()
+            .await
+            .map_err(|e| TelegramError {
+                status: Some(status),
+                description: e.without_url().to_string(),
+            })?;
+
+        if body.ok {
+            return Ok(());
+        }
+
+        Err(TelegramError {
+            status: Some(body.error_code.unwrap_or(status)),
+            description: body.description.unwrap_or_default(),
+        })
+    }
+}
+
+pub struct TelegramChannel {
+    bot: &'static TelegramBot,
+}
+
+impl TelegramChannel {
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            bot: telegram_bot()?,
+        })
+    }
+}
+
+#[async_trait]
+impl DeliveryChannel for TelegramChannel {
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            title: false,
+            data_payload: false,
+            throttled: true,
+            every_event: false,
+        }
+    }
+
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind {
+        classify_telegram_error(error)
+    }
+
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error> {
+        let Some(link) = TelegramLink::find_by_user_id(&request.user_id).await? else {
+            return Ok(Vec::new());
+        };
+
+        let target = format!("telegram:{}", link.chat_id);
+        let Some((title, body)) = throttle_target(&target, request).await else {
+            tracing::warn!(
+                "Skipping Telegram notification for user ID {} due to rate limiting.",
+                request.user_id
+            );
+            return Ok(Vec::new());
+        };
+        let text = format!("{title}\n\n{body}");
+
+        let retry_strategy = ExponentialBackoff::from_millis(TELEGRAM_RETRY_INITIAL_DELAY_MS)
+            .max_delay(Duration::from_secs(5))
+            .take(TELEGRAM_RETRY_ATTEMPTS);
+
+        let send_result = RetryIf::spawn(
+            retry_strategy,
+            || self.bot.send_message(link.chat_id, &text),
+            is_transient_telegram_error,
+        )
+        .await;
+
+        let result = match send_result {
+            Ok(()) => {
+                if let Err(e) = record_delivery(&target, request).await {
+                    tracing::warn!(
+                        "Failed to update rate limit for user ID {}: {e}",
+                        request.user_id
+                    );
+                }
+
+                tracing::info!(
+                    "Telegram notification sent successfully for user ID {}",
+                    request.user_id
+                );
+                Ok(())
+            }
+            Err(e) => {
+                let message = e.to_string();
+                let kind = self.classify_error(&message);
+
+                if kind == DeliveryErrorKind::Permanent {
+                    tracing::warn!(
+                        "Telegram chat of user ID {} is unreachable, unlinking it: {}",
+                        request.user_id,
+                        message
+                    );
+                    if let Err(e) = TelegramLink::unlink_by_chat_id(link.chat_id).await {
+                        tracing::error!(
+                            "Failed to unlink Telegram chat of user ID {}: {e}",
+                            request.user_id
+                        );
+                    }
+                }
+
+                Err(DeliveryError { kind, message })
+            }
+        };
+
+        Ok(vec![TargetOutcome { target, result }])
+    }
+}
+
+fn is_transient_telegram_error(error: &TelegramError) -> bool {
+    match error.status {
+        None => true,
+        Some(status) => status == 429 || status >= 500,
+    }
+}
+
+/// 403 means the user blocked the bot or deleted the account; a 400
+/// "chat not found" means the chat is gone.
+fn classify_telegram_error(error: &str) -> DeliveryErrorKind {
+    if error.contains("responded 403") || error.contains("chat not found") {
+        DeliveryErrorKind::Permanent
+    } else {
+        DeliveryErrorKind::Transient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_blocked_bots_and_missing_chats_as_permanent() {
+        for error in [
+            "Telegram responded 403: Forbidden: bot was blocked by the user",
+            "Telegram responded 400: Bad Request: chat not found",
+        ] {
+            assert_eq!(
+                classify_telegram_error(error),
+                DeliveryErrorKind::Permanent,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_other_telegram_errors_as_transient() {
+        for error in [
+            "Telegram responded 429: Too Many Requests: retry after 5",
+            "Telegram responded 502: Bad Gateway",
+        ] {
+            assert_eq!(
+                classify_telegram_error(error),
+                DeliveryErrorKind::Transient,
+                "{error}"
+            );
+        }
+    }
+}
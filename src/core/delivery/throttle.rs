@@ -0,0 +1,123 @@
+//! Per-target rate limiting shared by the channels that deliver to devices.
+//!
+//! A target receives at most one notification every `RATE_LIMIT_DURATION`
+//! seconds. Notifications dropped in between are counted and the next one that
+//! goes through is replaced by a summary of how many were missed. Mandatory
+//! security notifications bypass both and leave the window untouched.
+
+use crate::core::cache::redis_service::RedisService;
+use crate::core::delivery::DeliveryRequest;
+use crate::errors::Error;
+use crate::loading_templates::render_unsent_summary;
+
+const NOTIFICATION_KEY_PREFIX: &str = "raidenx:notification";
+const RATE_LIMIT_DURATION: usize = 2; // 2 second
+const UNSENT_COUNT_DURATION: usize = 60 * 60 * 24; // 24 hours
+
+/// Returns the title and body to send to `target` now, or `None` when the
+/// target is rate limited and the notification was counted as unsent.
+pub async fn throttle_target(target: &str, request: &DeliveryRequest) -> Option<(String, String)> {
+    let (title, body) = (&request.title, &request.body);
+    if request.is_mandatory() {
+        return Some((title.clone(), body.clone()));
+    }
+
+    let unsent_count = get_unsent_notification_count(target).await.unwrap_or(0);
+
+    if !can_send_notification(target).await {
+        if let Err(e) = increment_unsent_count(target).await {
+            tracing::warn!("Failed to update unsent notification count: {e}");
+        }
+        return None;
+    }
+
+    if unsent_count > 1 {
+        let summary =
+            render_unsent_summary(request.notif_type, request.language, unsent_count).await;
+        Some((summary.title, summary.body))
+    } else {
+        Some((title.clone(), body.clone()))
+    }
+}
+
+/// Starts a new rate limit window for `target` after a successful delivery.
+pub async fn record_delivery(target: &str, request: &DeliveryRequest) -> Result<(), Error> {
+    if request.is_mandatory() {
+        return Ok(());
+    }
+
+    update_last_sent(target).await?;
+    reset_unsent_count(target).await
+}
+
+async fn can_send_notification(target: &str) -> bool {
+    let redis_service = RedisService::new().await;
+    let last_sent = redis_service
+        .get_cache::<Option<i64>>(
+            format!("{}:{}:last_sent", NOTIFICATION_KEY_PREFIX, target).as_str(),
+        )
+        .await
+        .unwrap_or_default();
+
+    match last_sent {
+        Some(timestamp) => {
+            let current_time = chrono::Utc::now().timestamp_millis();
+            let elapsed_time = current_time - timestamp;
+            elapsed_time >= (RATE_LIMIT_DURATION as i64 * 1000)
+        }
+        None => true,
+    }
+}
+
+async fn update_last_sent(target: &str) -> Result<(), Error> {
+    let redis_service = RedisService::new().await;
+
+    let key = format!("{}:{}:last_sent", NOTIFICATION_KEY_PREFIX, target);
+    let current_time = chrono::Utc::now().timestamp_millis();
+
+    redis_service
+        .set_ex_cache(key.as_str(), &current_time, RATE_LIMIT_DURATION)
+        .await
+        .map_err(|e| {
+            Error::internal_err(&format!("Failed to update unsent notification count: {e}"))
+        })?;
+
+    Ok(())
+}
+
+async fn increment_unsent_count(target: &str) -> Result<(), Error> {
+    let current_count = get_unsent_notification_count(target).await.unwrap_or(0);
+    let new_count = current_count + 1;
+
+    let redis_service = RedisService::new().await;
+    let key = format!("{}:{}:unsent_count", NOTIFICATION_KEY_PREFIX, target);
+
+    redis_service
+        .set_ex_cache(&key, &new_count, UNSENT_COUNT_DURATION)
+        .await
+        .map_err(|e| Error::internal_err(&format!("Failed to increment unsent count: {e}")))?;
+
+    Ok(())
+}
+
+async fn reset_unsent_count(target: &str) -> Result<(), Error> {
+    let redis_service = RedisService::new().await;
+    let key = format!("{}:{}:unsent_count", NOTIFICATION_KEY_PREFIX, target);
+
+    redis_service
+        .set_ex_cache(&key, &0i64, UNSENT_COUNT_DURATION)
+        .await
+        .map_err(|e| Error::internal_err(&format!("Failed to reset unsent count: {e}")))?;
+
+    Ok(())
+}
+
+async fn get_unsent_notification_count(target: &str) -> Option<i64> {
+    let redis_service = RedisService::new().await;
+    let key = format!("{}:{}:unsent_count", NOTIFICATION_KEY_PREFIX, target);
+
+    redis_service
+        .get_cache::<Option<i64>>(key.as_str())
+        .await
+        .unwrap_or_default()
+}
@@ -0,0 +1,633 @@
+//! Web Push channel for browser subscriptions.
+//!
+//! Payloads are encrypted with the `aes128gcm` content coding of RFC 8188 as
+//! profiled for Web Push by RFC 8291, and requests are authenticated with a
+//! VAPID token (RFC 8292).
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes128Gcm, Nonce};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use futures::stream::{self, StreamExt};
+use hkdf::Hkdf;
+use p256::ecdh::diffie_hellman;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use serde::Serialize;
+use sha2::Sha256;
+use tokio_retry::{RetryIf, strategy::ExponentialBackoff};
+
+use crate::config::APP_CONFIG;
+use crate::core::delivery::public_host::PublicDnsResolver;
+use crate::core::delivery::throttle::{record_delivery, throttle_target};
+use crate::core::delivery::{
+    ChannelCapabilities, DeliveryChannel, DeliveryError, DeliveryErrorKind, DeliveryPriority,
+    DeliveryRequest, TargetOutcome,
+};
+use crate::errors::Error;
+use crate::models::web_push_subscriptions::WebPushSubscription;
+
+const WEB_PUSH_RETRY_ATTEMPTS: usize = 3;
+const WEB_PUSH_RETRY_INITIAL_DELAY_MS: u64 = 100;
+const WEB_PUSH_SEND_CONCURRENCY: usize = 8;
+const WEB_PUSH_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Push services reject VAPID tokens valid for more than 24 hours.
+const VAPID_TOKEN_TTL_SECS: i64 = 12 * 60 * 60;
+
+/// A single record holds the whole message, so its size bounds the payload.
+const RECORD_SIZE: u32 = 4096;
+const AES_GCM_TAG_LEN: usize = 16;
+const MAX_PLAINTEXT_LEN: usize = RECORD_SIZE as usize - AES_GCM_TAG_LEN - 1;
+const AUTH_SECRET_LEN: usize = 16;
+
+/// Topics replace pending messages and are limited to 32 base64url characters.
+const MAX_TOPIC_LEN: usize = 32;
+
+pub struct VapidKey {
+    signing_key: SigningKey,
+    public_key: String,
+}
+
+impl VapidKey {
+    pub fn from_config() -> Result<Self, Error> {
+        let private_key = APP_CONFIG
+            .vapid_private_key
+            .as_deref()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| Error::internal_err("VAPID_PRIVATE_KEY is not configured"))?;
+
+        let signing_key = decode_base64url(private_key)
+            .ok()
+            .and_then(|bytes| SigningKey::from_slice(&bytes).ok())
+            .ok_or_else(|| Error::internal_err("Invalid VAPID_PRIVATE_KEY"))?;
+        let public_key = URL_SAFE_NO_PAD.encode(
+            signing_key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+
+        Ok(Self {
+            signing_key,
+            public_key,
+        })
+    }
+
+    /// The application server key browsers pass to `pushManager.subscribe`.
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    fn authorization(&self, endpoint: &str, subject: &str) -> Result<String, String> {
+        let audience = reqwest::Url::parse(endpoint)
+            .map_err(|e| format!("Invalid endpoint: {e}"))?
+            .origin()
+            .ascii_serialization();
+
+        let header = URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": chrono::Utc::now().timestamp() + VAPID_TOKEN_TTL_SECS,
+            "sub": subject,
+        });
+        let signing_input = format!(
+            "{header}.{}",
+            URL_SAFE_NO_PAD.encode(claims.to_string().as_bytes())
+        );
+
+        let signature: Signature = self.signing_key.sign(signing_input.as_bytes());
+
+        Ok(format!(
+            "vapid t={signing_input}.{}, k={}",
+            URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+            self.public_key
+        ))
+    }
+}
+
+/// Checks that a browser subscription carries usable keys.
+pub fn validate_subscription_keys(p256dh: &str, auth: &str) -> Result<(), String> {
+    let public_key = decode_base64url(p256dh)?;
+    PublicKey::from_sec1_bytes(&public_key).map_err(|_| "Invalid p256dh key".to_string())?;
+
+    if decode_base64url(auth)?.len() != AUTH_SECRET_LEN {
+        return Err("Invalid auth secret".to_string());
+    }
+
+    Ok(())
+}
+
+/// Accepts only `https` endpoints of the push services in
+/// `web_push_allowed_hosts`, so a subscription cannot make the publisher post
+/// to arbitrary hosts.
+pub fn validate_endpoint(endpoint: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(endpoint).map_err(|e| format!("Invalid endpoint: {e}"))?;
+    if url.scheme() != "https" {
+        return Err("Push endpoint must use https".to_string());
+    }
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| "Push endpoint has no host".to_string())?
+        .to_ascii_lowercase();
+    if !is_allowed_host(&host, &APP_CONFIG.web_push_allowed_hosts) {
+        return Err(format!("{host} is not a known push service"));
+    }
+
+    Ok(())
+}
+
+/// Whether `host` is one of `allowed_hosts` or a subdomain of one.
+fn is_allowed_host(host: &str, allowed_hosts: &[String]) -> bool {
+    allowed_hosts.iter().any(|allowed| {
+        let allowed = allowed.trim().to_ascii_lowercase();
+        !allowed.is_empty()
+            && (host == allowed
+                || host
+                    .strip_suffix(allowed.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    })
+}
+
+/// Encrypts `plaintext` for one subscription as a single `aes128gcm` record.
+pub fn encrypt_payload(p256dh: &str, auth: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    if plaintext.len() > MAX_PLAINTEXT_LEN {
+        return Err(format!(
+            "Payload of {} bytes exceeds {MAX_PLAINTEXT_LEN} bytes",
+            plaintext.len()
+        ));
+    }
+
+    let ua_key = PublicKey::from_sec1_bytes(&decode_base64url(p256dh)?)
+        .map_err(|_| "Invalid p256dh key".to_string())?;
+    let auth_secret = decode_base64url(auth)?;
+
+    let as_secret = SecretKey::random(&mut OsRng);
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+
+    encrypt_record(&ua_key, &auth_secret, &as_secret, &salt, plaintext)
+}
+
+/// RFC 8291 encryption with the application server key and salt given, which
+/// are random for every message outside of tests.
+fn encrypt_record(
+    ua_key: &PublicKey,
+    auth_secret: &[u8],
+    as_secret: &SecretKey,
+    salt: &[u8; 16],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, String> {
+    let ua_public = ua_key.to_encoded_point(false);
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let shared_secret = diffie_hellman(as_secret.to_nonzero_scalar(), ua_key.as_affine());
+
+    // IKM = HKDF(auth_secret, ecdh_secret, "WebPush: info" || 0x00 || ua_public || as_public)
+    let mut key_info = Vec::with_capacity(14 + ua_public.len() + as_public.len());
+    key_info.extend_from_slice(b"WebPush: info\0");
+    key_info.extend_from_slice(ua_public.as_bytes());
+    key_info.extend_from_slice(as_public.as_bytes());
+
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(
+        Some(auth_secret),
+        shared_secret.raw_secret_bytes().as_slice(),
+    )
+    .expand(&key_info, &mut ikm)
+    .map_err(|e| format!("Failed to derive IKM: {e}"))?;
+
+    let hkdf = Hkdf::<Sha256>::new(Some(salt), &ikm);
+    let mut cek = [0u8; 16];
+    hkdf.expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .map_err(|e| format!("Failed to derive content key: {e}"))?;
+    let mut nonce = [0u8; 12];
+    hkdf.expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .map_err(|e| format!("Failed to derive nonce: {e}"))?;
+
+    // 0x02 marks the last (and only) record; no padding is added.
+    let mut record = Vec::with_capacity(plaintext.len() + 1);
+    record.extend_from_slice(plaintext);
+    record.push(0x02);
+
+    let ciphertext = Aes128Gcm::new_from_slice(&cek)
+        .map_err(|e| format!("Invalid content key: {e}"))?
+        .encrypt(Nonce::from_slice(&nonce), record.as_slice())
+        .map_err(|e| format!("Failed to encrypt payload: {e}"))?;
+
+    // Header: salt (16) || record size (4) || key id length (1) || key id (as_public)
+    let mut body = Vec::with_capacity(21 + as_public.len() + ciphertext.len());
+    body.extend_from_slice(salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(as_public.as_bytes());
+    body.extend_from_slice(&ciphertext);
+
+    Ok(body)
+}
+
+fn decode_base64url(value: &str) -> Result<Vec<u8>, String> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| "Invalid base64url value".to_string())
+}
+
+#[derive(Serialize)]
+struct WebPushPayload<'a> {
+    title: &'a str,
+    body: &'a str,
+    r#type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<&'a str>,
+    /// Same keys as the data of mobile pushes, e.g. `deepLink`.
+    data: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+struct WebPushError {
+    status: Option<u16>,
+    message: String,
+}
+
+impl Display for WebPushError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub struct WebPushChannel {
+    client: reqwest::Client,
+    vapid: VapidKey,
+    subject: String,
+}
+
+impl WebPushChannel {
+    pub fn new() -> Result<Self, Error> {
+        let subject = APP_CONFIG
+            .vapid_subject
+            .clone()
+            .filter(|subject| !subject.is_empty())
+            .ok_or_else(|| Error::internal_err("VAPID_SUBJECT is not configured"))?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(WEB_PUSH_REQUEST_TIMEOUT_SECS))
+            // Push services answer directly; a redirect could point the
+            // request at an internal address.
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicDnsResolver))
+            .build()
+            .map_err(|e| Error::internal_err(&format!("Failed to create web push client: {e}")))?;
+
+        Ok(Self {
+            client,
+            vapid: VapidKey::from_config()?,
+            subject,
+        })
+    }
+
+    async fn post_message(
+        &self,
+        endpoint: &str,
+        body: &[u8],
+        request: &DeliveryRequest,
+    ) -> Result<(), WebPushError> {
+        let authorization =
+            self.vapid
+                .authorization(endpoint, &self.subject)
+                .map_err(|message| WebPushError {
+                    status: None,
+                    message,
+                })?;
+
+        let mut http_request = self
+            .client
+            .post(endpoint)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header(reqwest::header::CONTENT_ENCODING, "aes128gcm")
+            .header("TTL", APP_CONFIG.web_push_ttl_secs)
+            .header(
+                "Urgency",
+                match request.priority {
+                    DeliveryPriority::High => "high",
+                    DeliveryPriority::Normal => "normal",
+                },
+            )
+            .body(body.to_vec());
+        if let Some(topic) = request
+            .collapse_key
+            .as_deref()
+            .filter(|topic| is_valid_topic(topic))
+        {
+            http_request = http_request.header("Topic", topic);
+        }
+
+        let response = http_request.send().await.map_err(|e| WebPushError {
+            status: None,
+            message: format!("Push service request failed: {e}"),
+        })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        Err(WebPushError {
+            status: Some(status.as_u16()),
+            message: format!("Push service responded {status}"),
+        })
+    }
+
+    async fn send_to_subscription(
+        &self,
+        request: &DeliveryRequest,
+        subscription: WebPushSubscription,
+        target: String,
+        title: String,
+        body: String,
+    ) -> TargetOutcome {
+        // Subscriptions stored before the allow-list was enforced.
+        if let Err(message) = validate_endpoint(&subscription.endpoint) {
+            tracing::warn!(
+                "Deactivating web push subscription of user ID {}: {message}",
+                request.user_id
+            );
+            if let Err(e) =
+                WebPushSubscription::deactivate_by_endpoint(&subscription.endpoint).await
+            {
+                tracing::error!(
+                    "Failed to deactivate web push subscription of user ID {}: {e}",
+                    request.user_id
+                );
+            }
+
+            return TargetOutcome {
+                target,
+                result: Err(DeliveryError {
+                    kind: DeliveryErrorKind::Permanent,
+                    message,
+                }),
+            };
+        }
+
+        let payload = WebPushPayload {
+            title: &title,
+            body: &body,
+            r#type: request.notif_type.to_string(),
+            // Browsers replace a shown notification with the same tag.
+            tag: request.source_event_id.as_deref(),
+            data: request.data_payload(),
+        };
+
+        let encrypted = serde_json::to_vec(&payload)
+            .map_err(|e| e.to_string())
+            .and_then(|plaintext| {
+                encrypt_payload(&subscription.p256dh, &subscription.auth, &plaintext)
+            });
+        let encrypted = match encrypted {
+            Ok(encrypted) => encrypted,
+            Err(message) => {
+                return TargetOutcome {
+                    target,
+                    result: Err(DeliveryError {
+                        kind: DeliveryErrorKind::Transient,
+                        message: format!("Failed to encrypt web push payload: {message}"),
+                    }),
+                };
+            }
+        };
+
+        let retry_strategy = ExponentialBackoff::from_millis(WEB_PUSH_RETRY_INITIAL_DELAY_MS)
+            .max_delay(Duration::from_secs(5))
+            .take(WEB_PUSH_RETRY_ATTEMPTS);
+
+        let send_result = RetryIf::spawn(
+            retry_strategy,
+            || self.post_message(&subscription.endpoint, &encrypted, request),
+            is_retryable_web_push_error,
+        )
+        .await;
+
+        let result = match send_result {
+            Ok(()) => {
+                if let Err(e) = record_delivery(&target, request).await {
+                    tracing::warn!(
+                        "Failed to update rate limit for user ID {}: {e}",
+                        request.user_id
+                    );
+                }
+
+                tracing::info!(
+                    "Web push notification sent successfully for user ID {}",
+                    request.user_id
+                );
+                Ok(())
+            }
+            Err(e) => {
+                let kind = self.classify_error(&e.message);
+
+                if kind == DeliveryErrorKind::Permanent {
+                    tracing::warn!(
+                        "Web push subscription of user ID {} expired, deactivating it: {}",
+                        request.user_id,
+                        e.message
+                    );
+                    if let Err(e) =
+                        WebPushSubscription::deactivate_by_endpoint(&subscription.endpoint).await
+                    {
+                        tracing::error!(
+                            "Failed to deactivate web push subscription of user ID {}: {e}",
+                            request.user_id
+                        );
+                    }
+                }
+
+                Err(DeliveryError {
+                    kind,
+                    message: e.message,
+                })
+            }
+        };
+
+        TargetOutcome { target, result }
+    }
+}
+
+#[async_trait]
+impl DeliveryChannel for WebPushChannel {
+    fn name(&self) -> &'static str {
+        "webpush"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            title: true,
+            data_payload: true,
+            throttled: true,
+            every_event: false,
+        }
+    }
+
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind {
+        classify_web_push_error(error)
+    }
+
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error> {
+        let subscriptions = WebPushSubscription::find_active_by_user_id(&request.user_id).await?;
+        if subscriptions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut send_jobs = Vec::new();
+
+        for subscription in subscriptions {
+            let target = format!(
+                "webpush:{}",
+                subscription.id.map(|id| id.to_hex()).unwrap_or_default()
+            );
+            match throttle_target(&target, request).await {
+                Some((title, body)) => send_jobs.push((subscription, target, title, body)),
+                None => tracing::warn!(
+                    "Skipping web push notification for user ID {} due to rate limiting.",
+                    request.user_id
+                ),
+            }
+        }
+
+        let outcomes = stream::iter(send_jobs.into_iter().map(
+            |(subscription, target, title, body)| {
+                self.send_to_subscription(request, subscription, target, title, body)
+            },
+        ))
+        .buffer_unordered(WEB_PUSH_SEND_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+        Ok(outcomes)
+    }
+}
+
+fn is_valid_topic(topic: &str) -> bool {
+    topic.len() <= MAX_TOPIC_LEN
+        && topic
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn is_retryable_web_push_error(error: &WebPushError) -> bool {
+    match error.status {
+        None => true,
+        Some(status) => status == 429 || status >= 500,
+    }
+}
+
+/// 404 and 410 mean the browser dropped the subscription.
+fn classify_web_push_error(error: &str) -> DeliveryErrorKind {
+    if error.contains("responded 404") || error.contains("responded 410") {
+        DeliveryErrorKind::Permanent
+    } else {
+        DeliveryErrorKind::Transient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Example from RFC 8291, section 5.
+    #[test]
+    fn encrypts_rfc_8291_example() {
+        let ua_key = PublicKey::from_sec1_bytes(
+            &decode_base64url(
+                "BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let auth_secret = decode_base64url("BTBZMqHH6r4Tts7J_aSIgg").unwrap();
+        let as_secret = SecretKey::from_slice(
+            &decode_base64url("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw").unwrap(),
+        )
+        .unwrap();
+        let salt: [u8; 16] = decode_base64url("DGv6ra1nlYgDCS1FRnbzlw")
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        let body = encrypt_record(
+            &ua_key,
+            &auth_secret,
+            &as_secret,
+            &salt,
+            b"When I grow up, I want to be a watermelon",
+        )
+        .unwrap();
+
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(body),
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN"
+        );
+    }
+
+    #[test]
+    fn rejects_payloads_larger_than_one_record() {
+        let ua_key = SecretKey::random(&mut OsRng).public_key();
+        let p256dh = URL_SAFE_NO_PAD.encode(ua_key.to_encoded_point(false).as_bytes());
+        let auth = URL_SAFE_NO_PAD.encode([0u8; AUTH_SECRET_LEN]);
+
+        assert!(encrypt_payload(&p256dh, &auth, &[0u8; MAX_PLAINTEXT_LEN]).is_ok());
+        assert!(encrypt_payload(&p256dh, &auth, &[0u8; MAX_PLAINTEXT_LEN + 1]).is_err());
+    }
+
+    #[test]
+    fn classifies_dropped_subscriptions_as_permanent() {
+        for error in [
+            "Push service responded 404 Not Found",
+            "Push service responded 410 Gone",
+        ] {
+            assert_eq!(
+                classify_web_push_error(error),
+                DeliveryErrorKind::Permanent,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_other_push_service_errors_as_transient() {
+        for error in [
+            "Push service responded 413 Payload Too Large",
+            "Push service responded 429 Too Many Requests",
+            "Push service request failed: timed out",
+        ] {
+            assert_eq!(
+                classify_web_push_error(error),
+                DeliveryErrorKind::Transient,
+                "{error}"
+            );
+        }
+    }
+
+    #[test]
+    fn allows_push_services_and_their_subdomains() {
+        let allowed = vec![
+            "fcm.googleapis.com".to_string(),
+            "notify.windows.com".to_string(),
+        ];
+
+        assert!(is_allowed_host("fcm.googleapis.com", &allowed));
+        assert!(is_allowed_host("wns2-par02p.notify.windows.com", &allowed));
+        assert!(!is_allowed_host("evilfcm.googleapis.com.example", &allowed));
+        assert!(!is_allowed_host("attackernotify.windows.com", &allowed));
+        assert!(!is_allowed_host("10.0.0.5", &allowed));
+    }
+}
@@ -0,0 +1,389 @@
+//! Webhook channel posting notification events to user-configured endpoints.
+//!
+//! The publisher only queues a `WebhookJob` per subscription; the webhook
+//! worker makes the attempts, so a slow endpoint never holds up the consumer.
+//!
+//! Every request carries `X-Webhook-Id`, `X-Webhook-Timestamp` (unix seconds)
+//! and `X-Webhook-Signature: sha256=<hex>`, an HMAC-SHA256 over
+//! `"{timestamp}.{body}"` keyed with the subscription secret. Receivers should
+//! reject stale timestamps and deduplicate on the event id, which is the id of
+//! the source event and so stays the same across retries and replays.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use wither::bson::DateTime;
+
+use crate::config::APP_CONFIG;
+use crate::core::delivery::public_host::{PublicDnsResolver, is_public_ip};
+use crate::core::delivery::{
+    ChannelCapabilities, DeliveryChannel, DeliveryErrorKind, DeliveryRequest, TargetOutcome,
+};
+use crate::errors::Error;
+use crate::models::webhook_deliveries::WebhookDelivery;
+use crate::models::webhook_jobs::WebhookJob;
+use crate::models::webhook_subscriptions::WebhookSubscription;
+use crate::utils::models::ModelExt;
+use crate::utils::structs::NotifMetadata;
+
+/// Retries after 30s, 1m, 2m, ... up to an hour between attempts.
+const WEBHOOK_RETRY_BASE_DELAY_SECS: i64 = 30;
+const WEBHOOK_RETRY_MAX_DELAY_SECS: i64 = 60 * 60;
+const WEBHOOK_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Status an endpoint returns to say it is gone for good.
+const WEBHOOK_GONE_STATUS: u16 = 410;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub struct WebhookChannel {
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WebhookEvent<'a> {
+    id: &'a str,
+    r#type: String,
+    user_id: &'a str,
+    created_at: i64,
+    data: serde_json::Value,
+}
+
+#[derive(Debug)]
+struct WebhookAttemptError {
+    status: Option<u16>,
+    message: String,
+}
+
+impl WebhookChannel {
+    pub fn new() -> Result<Self, Error> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(Duration::from_secs(WEBHOOK_REQUEST_TIMEOUT_SECS))
+            // Redirects could point a verified endpoint at an internal address.
+            .redirect(reqwest::redirect::Policy::none());
+        if !APP_CONFIG.webhook_allow_private_hosts {
+            builder = builder.dns_resolver(Arc::new(PublicDnsResolver));
+        }
+        let client = builder
+            .build()
+            .map_err(|e| Error::internal_err(&format!("Failed to create webhook client: {e}")))?;
+
+        Ok(Self { client })
+    }
+
+    /// Makes the attempt `job` was claimed for and records its outcome on the
+    /// job and on its subscription.
+    pub async fn deliver(&self, job: &WebhookJob) -> Result<(), Error> {
+        let subscription = match WebhookSubscription::find_by_id(&job.subscription_id).await? {
+            Some(subscription) if subscription.is_active() => subscription,
+            _ => return job.mark_failed("Webhook subscription is disabled").await,
+        };
+
+        let Err(e) = self.post_event(&subscription, job).await else {
+            if let Err(e) = WebhookSubscription::record_success(job.subscription_id).await {
+                tracing::warn!(
+                    "Failed to record delivery of webhook {}: {e}",
+                    job.subscription_id
+                );
+            }
+            return job.mark_delivered().await;
+        };
+
+        if is_retryable_webhook_error(&e) && job.attempts < APP_CONFIG.webhook_max_attempts {
+            return job.schedule_retry(retry_at(job.attempts), &e.message).await;
+        }
+
+        let updated = match self.classify_error(&e.message) {
+            DeliveryErrorKind::Permanent => {
+                WebhookSubscription::disable(job.subscription_id, &e.message).await
+            }
+            DeliveryErrorKind::Transient => {
+                WebhookSubscription::record_failure(
+                    job.subscription_id,
+                    APP_CONFIG.webhook_max_consecutive_failures,
+                )
+                .await
+            }
+        };
+        match updated {
+            Ok(Some(subscription)) if !subscription.is_active() => tracing::warn!(
+                "Disabled webhook {} of user ID {}: {}",
+                job.subscription_id,
+                subscription.user_id,
+                subscription.disabled_reason.unwrap_or_default()
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::error!(
+                "Failed to record failed delivery of webhook {}: {e}",
+                job.subscription_id
+            ),
+        }
+
+        job.mark_failed(&e.message).await
+    }
+
+    async fn post_event(
+        &self,
+        subscription: &WebhookSubscription,
+        job: &WebhookJob,
+    ) -> Result<(), WebhookAttemptError> {
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = sign_payload(&subscription.secret, timestamp, &job.body);
+
+        let started_at = Instant::now();
+        let response = match check_public_url(&subscription.url) {
+            Ok(()) => self
+                .client
+                .post(&subscription.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header("X-Webhook-Id", &job.event_id)
+                .header("X-Webhook-Timestamp", timestamp.to_string())
+                .header("X-Webhook-Signature", format!("sha256={signature}"))
+                .body(job.body.clone())
+                .send()
+                .await
+                .map_err(|e| WebhookAttemptError {
+                    status: None,
+                    message: format!("Request failed: {e}"),
+                }),
+            Err(message) => Err(WebhookAttemptError {
+                status: None,
+                message,
+            }),
+        };
+        let duration_ms = started_at.elapsed().as_millis() as i64;
+
+        let status_code = response
+            .as_ref()
+            .ok()
+            .map(|response| i32::from(response.status().as_u16()));
+        let result = match response {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => Err(WebhookAttemptError {
+                status: Some(response.status().as_u16()),
+                message: format!("Endpoint responded {}", response.status()),
+            }),
+            Err(e) => Err(e),
+        };
+
+        let now = DateTime::now();
+        let delivery = WebhookDelivery {
+            id: None,
+            subscription_id: job.subscription_id,
+            user_id: subscription.user_id.clone(),
+            event_id: job.event_id.clone(),
+            event_type: job.event_type.clone(),
+            attempt: job.attempts,
+            success: result.is_ok(),
+            status_code,
+            error: result.as_ref().err().map(|e| e.message.clone()),
+            duration_ms,
+            created_at: now,
+            updated_at: now,
+        };
+        if let Err(e) = WebhookDelivery::create(delivery).await {
+            tracing::warn!(
+                "Failed to log delivery of webhook {}: {e}",
+                job.subscription_id
+            );
+        }
+
+        result
+    }
+}
+
+#[async_trait]
+impl DeliveryChannel for WebhookChannel {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn capabilities(&self) -> ChannelCapabilities {
+        ChannelCapabilities {
+            title: false,
+            data_payload: true,
+            throttled: false,
+            every_event: true,
+        }
+    }
+
+    fn classify_error(&self, error: &str) -> DeliveryErrorKind {
+        classify_webhook_error(error)
+    }
+
+    /// Queues the event for every matching subscription; a replayed request
+    /// queues nothing new.
+    async fn send(&self, request: &DeliveryRequest) -> Result<Vec<TargetOutcome>, Error> {
+        let Some(metadata) = &request.metadata else {
+            return Ok(Vec::new());
+        };
+        let Some(event_id) = &request.source_event_id else {
+            tracing::warn!(
+                "Skipped webhooks of user ID {}: the notification has no event id",
+                request.user_id
+            );
+            return Ok(Vec::new());
+        };
+
+        let event_type = request.notif_type.to_string();
+        let subscriptions =
+            WebhookSubscription::find_active_by_user_id_and_type(&request.user_id, &event_type)
+                .await?;
+        if subscriptions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let event = WebhookEvent {
+            id: event_id,
+            r#type: event_type.clone(),
+            user_id: &request.user_id,
+            created_at: request
+                .occurred_at
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis()),
+            data: event_data(metadata)
+                .map_err(|e| Error::internal_err(&format!("Failed to build webhook event: {e}")))?,
+        };
+        let body = serde_json::to_string(&event)
+            .map_err(|e| Error::internal_err(&format!("Failed to serialize webhook event: {e}")))?;
+
+        let mut outcomes = Vec::new();
+        for subscription in subscriptions {
+            let Some(subscription_id) = subscription.id else {
+                continue;
+            };
+
+            WebhookJob::enqueue(
+                subscription_id,
+                &request.user_id,
+                event_id,
+                &event_type,
+                &body,
+            )
+            .await?;
+            outcomes.push(TargetOutcome {
+                target: subscription.url,
+                result: Ok(()),
+            });
+        }
+
+        Ok(outcomes)
+    }
+}
+
+/// Hex-encoded HMAC-SHA256 of `"{timestamp}.{body}"` keyed with `secret`.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Rejects URLs whose host is a non-public IP literal. Names are checked by
+/// the client's resolver when connecting.
+fn check_public_url(url: &str) -> Result<(), String> {
+    if APP_CONFIG.webhook_allow_private_hosts {
+        return Ok(());
+    }
+
+    let url = reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL: {e}"))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| "Webhook URL has no host".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    match host.parse::<IpAddr>() {
+        Ok(ip) if !is_public_ip(ip) => Err(format!("{ip} is not a public address")),
+        _ => Ok(()),
+    }
+}
+
+/// When the next attempt is due after `attempts` failed ones.
+fn retry_at(attempts: i32) -> DateTime {
+    let exponent = attempts.clamp(1, 16) as u32 - 1;
+    let delay_secs = (WEBHOOK_RETRY_BASE_DELAY_SECS << exponent).min(WEBHOOK_RETRY_MAX_DELAY_SECS);
+
+    DateTime::from_millis(DateTime::now().timestamp_millis() + delay_secs * 1000)
+}
+
+fn event_data(metadata: &NotifMetadata) -> serde_json::Result<serde_json::Value> {
+    match metadata {
+        NotifMetadata::Order(data) => serde_json::to_value(data),
+        NotifMetadata::Transaction(data) => serde_json::to_value(data),
+        NotifMetadata::Account(data) => serde_json::to_value(data),
+        NotifMetadata::PriceAlert(data) => serde_json::to_value(data),
+        NotifMetadata::Announcement(data) => serde_json::to_value(data),
+        NotifMetadata::Campaign(data) => serde_json::to_value(data),
+    }
+}
+
+/// Network errors, timeouts, throttling and server errors are worth another
+/// attempt; any other response means the endpoint rejected the event.
+fn is_retryable_webhook_error(error: &WebhookAttemptError) -> bool {
+    match error.status {
+        None => true,
+        Some(status) => status == 408 || status == 429 || status >= 500,
+    }
+}
+
+fn classify_webhook_error(error: &str) -> DeliveryErrorKind {
+    if error.contains(&format!("responded {WEBHOOK_GONE_STATUS}")) {
+        DeliveryErrorKind::Permanent
+    } else {
+        DeliveryErrorKind::Transient
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_payload_is_hmac_sha256_of_timestamp_and_body() {
+        // echo -n '1700000000.{"id":"evt_1"}' | openssl dgst -sha256 -hmac whsec_test
+        assert_eq!(
+            sign_payload("whsec_test", 1_700_000_000, r#"{"id":"evt_1"}"#),
+            "c89214b5b5da833daed6f0b8c5bb6bd58cea9022bd80ccc78230f3942d632925"
+        );
+    }
+
+    #[test]
+    fn sign_payload_depends_on_timestamp_and_secret() {
+        let signature = sign_payload("secret", 1, "{}");
+
+        assert_ne!(signature, sign_payload("secret", 2, "{}"));
+        assert_ne!(signature, sign_payload("other", 1, "{}"));
+        assert_eq!(signature.len(), 64);
+    }
+
+    #[test]
+    fn classifies_gone_endpoints_as_permanent() {
+        assert_eq!(
+            classify_webhook_error("Endpoint responded 410 Gone"),
+            DeliveryErrorKind::Permanent
+        );
+    }
+
+    #[test]
+    fn classifies_other_endpoint_errors_as_transient() {
+        for error in [
+            "Endpoint responded 404 Not Found",
+            "Endpoint responded 500 Internal Server Error",
+            "Request failed: connection refused",
+        ] {
+            assert_eq!(
+                classify_webhook_error(error),
+                DeliveryErrorKind::Transient,
+                "{error}"
+            );
+        }
+    }
+}
@@ -1,5 +1,9 @@
+pub mod admin_auth;
 pub mod cache;
+pub mod delivery;
 pub mod jwt_auth;
 pub mod kafka_service;
 pub mod middleware;
-mod web_socket;
+pub mod price_alerts;
+pub mod realtime;
+pub mod web_socket;
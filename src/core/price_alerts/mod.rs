@@ -0,0 +1 @@
+pub mod price_alert_index;
@@ -0,0 +1,375 @@
+//! Active price alerts indexed by symbol and threshold.
+//!
+//! Each symbol keeps one sorted map of thresholds for rising prices and one
+//! for falling prices, so a tick only looks at the thresholds between the
+//! previous and the new price instead of at every alert of the symbol.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound::{Excluded, Included};
+use std::sync::{LazyLock, Mutex};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use wither::bson::oid::ObjectId;
+
+use crate::core::cache::redis_emitter::{
+    SupervisedSubscription, get_redis_emitter, spawn_supervised_subscription,
+};
+use crate::core::cache::redis_service::RedisService;
+use crate::errors::Error;
+use crate::models::price_alerts::{CrossDirection, PriceAlert};
+use crate::utils::models::ModelExt;
+
+pub const PRICE_ALERTS_UPDATED_CHANNEL: &str = "vdax:notification:price_alerts_updated";
+
+const LAST_PRICE_KEY_PREFIX: &str = "raidenx:price_alert:last_price";
+/// Long enough to bridge a consumer restart, short enough that a symbol which
+/// stopped trading does not compare against a stale price.
+const LAST_PRICE_TTL: usize = 60 * 60;
+
+static PRICE_ALERT_INDEX: LazyLock<RwLock<PriceAlertIndex>> =
+    LazyLock::new(|| RwLock::new(PriceAlertIndex::default()));
+
+/// Alerts refreshed while a full load reads the database, set only during one.
+/// The load may have read them before the change, so they are refreshed again
+/// once it replaced the index.
+static REFRESHED_DURING_LOAD: Mutex<Option<HashSet<ObjectId>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceAlertsUpdated {
+    pub alert_id: String,
+}
+
+#[derive(Default)]
+struct SymbolThresholds {
+    rising: BTreeMap<Decimal, Vec<ObjectId>>,
+    falling: BTreeMap<Decimal, Vec<ObjectId>>,
+}
+
+impl SymbolThresholds {
+    fn side(&mut self, direction: CrossDirection) -> &mut BTreeMap<Decimal, Vec<ObjectId>> {
+        match direction {
+            CrossDirection::Rising => &mut self.rising,
+            CrossDirection::Falling => &mut self.falling,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.rising.is_empty() && self.falling.is_empty()
+    }
+}
+
+/// An alert whose threshold the price moved through.
+#[derive(Debug, Clone)]
+pub struct CrossedAlert {
+    pub alert: PriceAlert,
+    pub threshold: Decimal,
+    pub direction: CrossDirection,
+}
+
+#[derive(Default)]
+pub struct PriceAlertIndex {
+    alerts: HashMap<ObjectId, PriceAlert>,
+    symbols: HashMap<String, SymbolThresholds>,
+}
+
+impl PriceAlertIndex {
+    pub fn len(&self) -> usize {
+        self.alerts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alerts.is_empty()
+    }
+
+    /// Adds the alert, replacing an earlier version of it.
+    pub fn insert(&mut self, alert: PriceAlert) {
+        let Some(id) = alert.id else {
+            return;
+        };
+        self.remove(&id);
+
+        let thresholds = self.symbols.entry(alert.symbol.clone()).or_default();
+        for (direction, price) in alert.thresholds() {
+            thresholds
+                .side(direction)
+                .entry(price)
+                .or_default()
+                .push(id);
+        }
+        self.alerts.insert(id, alert);
+    }
+
+    pub fn remove(&mut self, id: &ObjectId) -> Option<PriceAlert> {
+        let alert = self.alerts.remove(id)?;
+
+        if let Some(thresholds) = self.symbols.get_mut(&alert.symbol) {
+            for (direction, price) in alert.thresholds() {
+                let side = thresholds.side(direction);
+                if let Some(ids) = side.get_mut(&price) {
+                    ids.retain(|other| other != id);
+                    if ids.is_empty() {
+                        side.remove(&price);
+                    }
+                }
+            }
+            if thresholds.is_empty() {
+                self.symbols.remove(&alert.symbol);
+            }
+        }
+
+        Some(alert)
+    }
+
+    /// Alerts of `symbol` whose threshold lies between `previous` and
+    /// `current`. A price landing exactly on a threshold crosses it.
+    pub fn crossed(&self, symbol: &str, previous: Decimal, current: Decimal) -> Vec<CrossedAlert> {
+        let Some(thresholds) = self.symbols.get(symbol) else {
+            return Vec::new();
+        };
+
+        let (direction, crossed) = if current > previous {
+            (
+                CrossDirection::Rising,
+                thresholds
+                    .rising
+                    .range((Excluded(previous), Included(current)))
+                    .collect::<Vec<_>>(),
+            )
+        } else if current < previous {
+            (
+                CrossDirection::Falling,
+                thresholds
+                    .falling
+                    .range((Included(current), Excluded(previous)))
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            return Vec::new();
+        };
+
+        crossed
+            .into_iter()
+            .flat_map(|(threshold, ids)| {
+                ids.iter().filter_map(move |id| {
+                    self.alerts.get(id).map(|alert| CrossedAlert {
+                        alert: alert.clone(),
+                        threshold: *threshold,
+                        direction,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn price_alert_index() -> &'static RwLock<PriceAlertIndex> {
+    &PRICE_ALERT_INDEX
+}
+
+pub async fn load_price_alerts() -> anyhow::Result<()> {
+    *refreshed_during_load() = Some(HashSet::new());
+
+    let alerts = match PriceAlert::find_active().await {
+        Ok(alerts) => alerts,
+        Err(e) => {
+            refreshed_during_load().take();
+            return Err(anyhow::anyhow!("Failed to fetch price alerts: {}", e));
+        }
+    };
+
+    let mut index = PriceAlertIndex::default();
+    for alert in alerts {
+        index.insert(alert);
+    }
+
+    tracing::info!("Loaded {} active price alerts", index.len());
+    let refreshed = {
+        let mut current = PRICE_ALERT_INDEX.write().await;
+        *current = index;
+        refreshed_during_load().take().unwrap_or_default()
+    };
+
+    for id in refreshed {
+        refresh_price_alert(id).await?;
+    }
+
+    Ok(())
+}
+
+fn refreshed_during_load() -> std::sync::MutexGuard<'static, Option<HashSet<ObjectId>>> {
+    REFRESHED_DURING_LOAD
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Replaces the alert in the index with its current version in the database.
+pub async fn refresh_price_alert(id: ObjectId) -> anyhow::Result<()> {
+    let alert = PriceAlert::find_by_id(&id)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch price alert {id}: {}", e))?;
+
+    let mut index = PRICE_ALERT_INDEX.write().await;
+    match alert {
+        Some(alert) if alert.is_active && !alert.is_expired() => index.insert(alert),
+        _ => {
+            index.remove(&id);
+        }
+    }
+    if let Some(refreshed) = refreshed_during_load().as_mut() {
+        refreshed.insert(id);
+    }
+
+    Ok(())
+}
+
+/// Applies alert changes made through the API to the index.
+pub fn spawn_price_alert_sync() -> SupervisedSubscription {
+    spawn_supervised_subscription(
+        PRICE_ALERTS_UPDATED_CHANNEL,
+        |update: PriceAlertsUpdated| {
+            Box::pin(async move {
+                let id = ObjectId::parse_str(&update.alert_id).map_err(|e| {
+                    anyhow::anyhow!("Invalid price alert id {}: {e}", update.alert_id)
+                })?;
+                refresh_price_alert(id).await
+            })
+        },
+        load_price_alerts,
+    )
+}
+
+pub fn publish_price_alerts_updated(alert_id: String) -> Result<(), Error> {
+    get_redis_emitter()
+        .publish(
+            PRICE_ALERTS_UPDATED_CHANNEL,
+            &PriceAlertsUpdated { alert_id },
+        )
+        .map_err(|e| Error::internal_err(&format!("Failed to publish price alert update: {e}")))
+}
+
+fn last_price_key(symbol: &str) -> String {
+    format!("{LAST_PRICE_KEY_PREFIX}:{symbol}")
+}
+
+/// Last price the alert consumer saw for `symbol`, if it is recent.
+pub async fn get_last_price(symbol: &str) -> Option<Decimal> {
+    match RedisService::new()
+        .await
+        .get_cache_opt::<Decimal>(&last_price_key(symbol))
+        .await
+    {
+        Ok(price) => price,
+        Err(e) => {
+            tracing::warn!("Failed to get last price of {symbol}: {e}");
+            None
+        }
+    }
+}
+
+pub async fn store_last_prices(prices: &HashMap<String, Decimal>) -> Result<(), Error> {
+    let entries = prices
+        .iter()
+        .map(|(symbol, price)| (last_price_key(symbol), *price))
+        .collect::<Vec<_>>();
+
+    RedisService::new()
+        .await
+        .set_ex_cache_batch(&entries, LAST_PRICE_TTL)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use wither::bson::DateTime;
+
+    use super::*;
+
+    fn price(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn alert(condition: &str, target_price: &str) -> PriceAlert {
+        PriceAlert {
+            id: Some(ObjectId::new()),
+            user_id: "user".to_string(),
+            symbol: "SUIUSDT".to_string(),
+            condition: condition.to_string(),
+            target_price: Some(price(target_price)),
+            percent: None,
+            reference_price: None,
+            recurring: false,
+            is_active: true,
+            expires_at: None,
+            trigger_count: 0,
+            last_triggered_at: None,
+            last_triggered_price: None,
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
+        }
+    }
+
+    fn crossed_count(index: &PriceAlertIndex, previous: &str, current: &str) -> usize {
+        index
+            .crossed("SUIUSDT", price(previous), price(current))
+            .len()
+    }
+
+    #[test]
+    fn rising_price_crosses_above_thresholds_it_reaches() {
+        let mut index = PriceAlertIndex::default();
+        index.insert(alert("ABOVE", "2"));
+
+        assert_eq!(crossed_count(&index, "1.9", "2"), 1);
+        assert_eq!(crossed_count(&index, "1.9", "2.1"), 1);
+        assert_eq!(crossed_count(&index, "1.9", "1.99"), 0);
+        // Already at the threshold before the tick.
+        assert_eq!(crossed_count(&index, "2", "2.1"), 0);
+        // Falling prices never trigger an above alert.
+        assert_eq!(crossed_count(&index, "2.1", "1.9"), 0);
+    }
+
+    #[test]
+    fn falling_price_crosses_below_thresholds_it_reaches() {
+        let mut index = PriceAlertIndex::default();
+        index.insert(alert("BELOW", "2"));
+
+        assert_eq!(crossed_count(&index, "2.1", "2"), 1);
+        assert_eq!(crossed_count(&index, "2.1", "1.9"), 1);
+        assert_eq!(crossed_count(&index, "2", "1.9"), 0);
+        assert_eq!(crossed_count(&index, "1.9", "2.1"), 0);
+        assert_eq!(crossed_count(&index, "2", "2"), 0);
+    }
+
+    #[test]
+    fn percent_change_alerts_cross_both_ways() {
+        let mut percent_alert = alert("PERCENT_CHANGE", "0");
+        percent_alert.target_price = None;
+        percent_alert.percent = Some(price("10"));
+        percent_alert.reference_price = Some(price("100"));
+
+        let mut index = PriceAlertIndex::default();
+        index.insert(percent_alert);
+
+        assert_eq!(crossed_count(&index, "105", "110"), 1);
+        assert_eq!(crossed_count(&index, "95", "90"), 1);
+        assert_eq!(crossed_count(&index, "95", "105"), 0);
+    }
+
+    #[test]
+    fn removed_alerts_are_not_crossed() {
+        let alert = alert("ABOVE", "2");
+        let id = alert.id.unwrap();
+        let mut index = PriceAlertIndex::default();
+        index.insert(alert);
+        index.remove(&id);
+
+        assert!(index.is_empty());
+        assert_eq!(crossed_count(&index, "1", "3"), 0);
+    }
+}
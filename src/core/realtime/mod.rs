@@ -0,0 +1,2 @@
+pub mod notification_hub;
+pub mod presence;
@@ -0,0 +1,180 @@
+//! In-process fan-out of newly persisted notifications to streaming clients.
+//!
+//! The persister publishes every stored `UserNotification` on
+//! `NEW_NOTIFICATION_CHANNEL`; each API instance subscribes once and hands the
+//! notification to the open streams of that user only.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::config::APP_CONFIG;
+use crate::core::cache::redis_emitter::{get_redis_emitter, spawn_supervised_subscription};
+use crate::core::web_socket::emit_event::emit_user_notify;
+use crate::errors::Error;
+use crate::models::user_notifications::UserNotification;
+
+pub const NEW_NOTIFICATION_CHANNEL: &str = "vdax:notification:new_notification";
+
+/// Notifications a slow stream may fall behind by before it starts skipping.
+const STREAM_BUFFER_SIZE: usize = 64;
+
+static NOTIFICATION_HUB: OnceCell<NotificationHub> = OnceCell::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RealtimeNotification {
+    pub id: String,
+    pub user_id: String,
+    pub r#type: String,
+    pub title: String,
+    pub message: String,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+    pub is_read: bool,
+}
+
+impl RealtimeNotification {
+    pub fn from_notification(notification: &UserNotification) -> Option<Self> {
+        Some(Self {
+            id: notification.id?.to_hex(),
+            user_id: notification.user_id.clone(),
+            r#type: notification.r#type.clone(),
+            title: notification.title.clone(),
+            message: notification.message.clone(),
+            created_at: notification.created_at.timestamp_millis(),
+            is_read: notification.is_read,
+        })
+    }
+}
+
+pub fn publish_new_notification(notification: &UserNotification) -> anyhow::Result<()> {
+    let Some(realtime) = RealtimeNotification::from_notification(notification) else {
+        return Err(anyhow::anyhow!("Notification has not been persisted"));
+    };
+
+    get_redis_emitter().publish(NEW_NOTIFICATION_CHANNEL, &realtime)
+}
+
+/// Pushes a stored notification to the user's socket.io room and to the
+/// notification streams. Returns `false` when either failed; the notification
+/// stays persisted either way.
+pub async fn emit_new_notification(notification: &UserNotification) -> bool {
+    let mut emitted = true;
+
+    if let Err(e) = publish_new_notification(notification) {
+        tracing::error!(
+            "Failed to publish notification for streams of user_id={}: {e}",
+            notification.user_id
+        );
+        emitted = false;
+    }
+
+    if !APP_CONFIG.realtime_emit_enabled {
+        return emitted;
+    }
+
+    match emit_user_notify(notification).await {
+        Ok(()) => emitted,
+        Err(e) => {
+            tracing::error!(
+                "Failed to emit NewNotification for user_id={}: {e}",
+                notification.user_id
+            );
+            false
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct NotificationHub {
+    streams: Mutex<HashMap<String, broadcast::Sender<Arc<RealtimeNotification>>>>,
+}
+
+/// A stream's handle on the hub; leaving the hub happens on drop.
+pub struct HubSubscription {
+    pub receiver: broadcast::Receiver<Arc<RealtimeNotification>>,
+    guard: SubscriptionGuard,
+}
+
+impl HubSubscription {
+    pub fn user_id(&self) -> &str {
+        &self.guard.user_id
+    }
+}
+
+struct SubscriptionGuard {
+    user_id: String,
+}
+
+impl Drop for SubscriptionGuard {
+    fn drop(&mut self) {
+        let hub = notification_hub();
+        let mut streams = hub.streams.lock().unwrap_or_else(|e| e.into_inner());
+        // The receiver is dropped before the guard, so an idle sender means this
+        // was the user's last stream.
+        if streams
+            .get(&self.user_id)
+            .is_some_and(|sender| sender.receiver_count() == 0)
+        {
+            streams.remove(&self.user_id);
+        }
+    }
+}
+
+pub fn notification_hub() -> &'static NotificationHub {
+    NOTIFICATION_HUB.get_or_init(NotificationHub::default)
+}
+
+impl NotificationHub {
+    /// Opens a stream for `user_id`, refusing once the user has
+    /// `sse_max_connections_per_user` streams open on this instance.
+    pub fn subscribe(&self, user_id: &str) -> Result<HubSubscription, Error> {
+        let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        let open_streams = streams
+            .get(user_id)
+            .map_or(0, |sender| sender.receiver_count());
+        if open_streams >= APP_CONFIG.sse_max_connections_per_user {
+            return Err(Error::too_many_requests(
+                "Too many notification streams open for this user",
+            ));
+        }
+
+        let sender = streams
+            .entry(user_id.to_string())
+            .or_insert_with(|| broadcast::channel(STREAM_BUFFER_SIZE).0);
+
+        Ok(HubSubscription {
+            receiver: sender.subscribe(),
+            guard: SubscriptionGuard {
+                user_id: user_id.to_string(),
+            },
+        })
+    }
+
+    fn deliver(&self, notification: RealtimeNotification) {
+        let streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(sender) = streams.get(&notification.user_id) {
+            // Fails only when every stream closed meanwhile.
+            let _ = sender.send(Arc::new(notification));
+        }
+    }
+}
+
+/// Subscribes this instance to `NEW_NOTIFICATION_CHANNEL`. Notifications
+/// published while disconnected are recovered by clients through `Last-Event-ID`.
+pub fn start_notification_hub() {
+    spawn_supervised_subscription(
+        NEW_NOTIFICATION_CHANNEL,
+        |notification: RealtimeNotification| {
+            Box::pin(async move {
+                notification_hub().deliver(notification);
+                Ok(())
+            })
+        },
+        || async { Ok(()) },
+    );
+}
@@ -0,0 +1,103 @@
+//! Which API nodes hold open WebSocket connections of a user.
+//!
+//! Each user has a Redis hash keyed `{node_id}:{connection_id}`. Every value
+//! carries its own deadline, which open connections push back periodically.
+//! Redis can only expire the hash as a whole, and another node's refresh
+//! keeps it alive, so fields past their deadline are pruned when read.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::APP_CONFIG;
+use crate::core::cache::redis_service::RedisService;
+use crate::errors::Error;
+
+pub const PRESENCE_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+const PRESENCE_TTL_SECS: i64 = 90;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PresenceEntry {
+    connected_at: i64,
+    expires_at: i64,
+}
+
+static NODE_ID: LazyLock<String> = LazyLock::new(|| {
+    APP_CONFIG
+        .node_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+});
+
+pub fn node_id() -> &'static str {
+    &NODE_ID
+}
+
+fn presence_key(user_id: &str) -> String {
+    format!("raidenx:notification:presence:{user_id}")
+}
+
+fn presence_field(connection_id: &str) -> String {
+    format!("{}:{connection_id}", node_id())
+}
+
+/// Records the connection, or keeps it alive when called again.
+pub async fn mark_online(
+    user_id: &str,
+    connection_id: &str,
+    connected_at_ms: i64,
+) -> Result<(), Error> {
+    let entry = PresenceEntry {
+        connected_at: connected_at_ms,
+        expires_at: chrono::Utc::now().timestamp_millis() + PRESENCE_TTL_SECS * 1000,
+    };
+
+    RedisService::new()
+        .await
+        .hset_ex(
+            &presence_key(user_id),
+            &presence_field(connection_id),
+            &serde_json::to_string(&entry)?,
+            PRESENCE_TTL_SECS,
+        )
+        .await?;
+
+    // Also drops what nodes that died left behind, as nothing else may read it.
+    online_connections(user_id).await?;
+
+    Ok(())
+}
+
+pub async fn mark_offline(user_id: &str, connection_id: &str) -> Result<(), Error> {
+    RedisService::new()
+        .await
+        .hdel(&presence_key(user_id), &[presence_field(connection_id)])
+        .await?;
+
+    Ok(())
+}
+
+/// Start times of the user's open connections, keyed `{node_id}:{connection_id}`.
+/// Removes the entries that expired.
+pub async fn online_connections(user_id: &str) -> Result<HashMap<String, i64>, Error> {
+    let redis = RedisService::new().await;
+    let key = presence_key(user_id);
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let mut online = HashMap::new();
+    let mut expired = Vec::new();
+    for (field, value) in redis.hgetall(&key).await? {
+        match serde_json::from_str::<PresenceEntry>(&value) {
+            Ok(entry) if entry.expires_at > now => {
+                online.insert(field, entry.connected_at);
+            }
+            _ => expired.push(field),
+        }
+    }
+    redis.hdel(&key, &expired).await?;
+
+    Ok(online)
+}
@@ -1,14 +1,12 @@
 use crate::core::cache::redis_emitter::get_redis_emitter;
 use crate::models::user_notifications::UserNotification;
 use bson::DateTime;
-use bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 
-#[allow(dead_code)]
 #[derive(Serialize, Deserialize)]
 pub struct PubUserNotification {
     #[serde(rename = "i")]
-    pub notification_id: Option<ObjectId>,
+    pub notification_id: Option<String>,
     #[serde(rename = "u")]
     pub user_id: String,
     #[serde(rename = "m")]
@@ -21,12 +19,11 @@ pub struct PubUserNotification {
     pub updated_at: DateTime,
 }
 
-#[allow(dead_code)]
-pub async fn emit_user_notify(user_notification: UserNotification) -> anyhow::Result<()> {
+pub async fn emit_user_notify(user_notification: &UserNotification) -> anyhow::Result<()> {
     let redis_emitter = get_redis_emitter();
 
     let pub_notify = PubUserNotification {
-        notification_id: user_notification.id,
+        notification_id: user_notification.id.map(|id| id.to_hex()),
         user_id: user_notification.user_id.clone(),
         r#type: user_notification.r#type.clone(),
         message: user_notification.message.clone(),
@@ -36,7 +33,9 @@ pub async fn emit_user_notify(user_notification: UserNotification) -> anyhow::Re
     let pub_data = serde_json::to_string(&pub_notify)?;
 
     let notification_room = format!("notification:user:{}", user_notification.user_id);
-    let _ = redis_emitter.emit_room(&notification_room, "NewNotification", &pub_data);
+    redis_emitter
+        .emit_room(&notification_room, "NewNotification", &pub_data)
+        .map_err(|e| anyhow::anyhow!(e))?;
 
     tracing::info!(
         "Successfully emitted user notification via WebSocket for user {}",
@@ -1 +1 @@
-mod emit_event;
+pub mod emit_event;
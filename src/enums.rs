@@ -67,6 +67,9 @@ pub enum KafkaTopic {
     UserNotificationPersister,
     #[strum(serialize = "raidenx.user.notify.publisher")]
     UserNotificationPublisher,
+    /// Price ticks produced by the market data service.
+    #[strum(serialize = "raidenx.market.price")]
+    MarketPrice,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
@@ -84,3 +87,259 @@ impl Display for UserFcmTokenStatus {
         }
     }
 }
+
+impl FromStr for UserFcmTokenStatus {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<UserFcmTokenStatus, Self::Err> {
+        match input {
+            "ACTIVE" => Ok(UserFcmTokenStatus::Active),
+            "INACTIVE" => Ok(UserFcmTokenStatus::Inactive),
+            _ => Err(format!("Invalid FCM token status: {input}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DevicePlatform {
+    Ios,
+    Android,
+    Web,
+}
+
+impl Display for DevicePlatform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DevicePlatform::Ios => write!(f, "ios"),
+            DevicePlatform::Android => write!(f, "android"),
+            DevicePlatform::Web => write!(f, "web"),
+        }
+    }
+}
+
+/// Service that issued a device token and is the only one able to deliver to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenProvider {
+    #[default]
+    Fcm,
+    Apns,
+}
+
+impl Display for TokenProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenProvider::Fcm => write!(f, "fcm"),
+            TokenProvider::Apns => write!(f, "apns"),
+        }
+    }
+}
+
+impl FromStr for TokenProvider {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<TokenProvider, Self::Err> {
+        match input {
+            "fcm" => Ok(TokenProvider::Fcm),
+            "apns" => Ok(TokenProvider::Apns),
+            _ => Err(format!("Invalid token provider: {input}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WebhookStatus {
+    Active,
+    Disabled,
+}
+
+impl Display for WebhookStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookStatus::Active => write!(f, "ACTIVE"),
+            WebhookStatus::Disabled => write!(f, "DISABLED"),
+        }
+    }
+}
+
+impl FromStr for WebhookStatus {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<WebhookStatus, Self::Err> {
+        match input {
+            "ACTIVE" => Ok(WebhookStatus::Active),
+            "DISABLED" => Ok(WebhookStatus::Disabled),
+            _ => Err(format!("Invalid webhook status: {input}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WebhookJobStatus {
+    /// Waiting for its first or next attempt.
+    Pending,
+    Delivered,
+    /// Rejected by the endpoint or out of attempts.
+    Failed,
+}
+
+impl Display for WebhookJobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookJobStatus::Pending => write!(f, "PENDING"),
+            WebhookJobStatus::Delivered => write!(f, "DELIVERED"),
+            WebhookJobStatus::Failed => write!(f, "FAILED"),
+        }
+    }
+}
+
+impl FromStr for WebhookJobStatus {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<WebhookJobStatus, Self::Err> {
+        match input {
+            "PENDING" => Ok(WebhookJobStatus::Pending),
+            "DELIVERED" => Ok(WebhookJobStatus::Delivered),
+            "FAILED" => Ok(WebhookJobStatus::Failed),
+            _ => Err(format!("Invalid webhook job status: {input}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AnnouncementStatus {
+    /// Waiting for its scheduled time.
+    Scheduled,
+    /// Being fanned out to its audience.
+    Sending,
+    Completed,
+    Cancelled,
+    Failed,
+}
+
+impl Display for AnnouncementStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnnouncementStatus::Scheduled => write!(f, "SCHEDULED"),
+            AnnouncementStatus::Sending => write!(f, "SENDING"),
+            AnnouncementStatus::Completed => write!(f, "COMPLETED"),
+            AnnouncementStatus::Cancelled => write!(f, "CANCELLED"),
+            AnnouncementStatus::Failed => write!(f, "FAILED"),
+        }
+    }
+}
+
+impl FromStr for AnnouncementStatus {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<AnnouncementStatus, Self::Err> {
+        match input {
+            "SCHEDULED" => Ok(AnnouncementStatus::Scheduled),
+            "SENDING" => Ok(AnnouncementStatus::Sending),
+            "COMPLETED" => Ok(AnnouncementStatus::Completed),
+            "CANCELLED" => Ok(AnnouncementStatus::Cancelled),
+            "FAILED" => Ok(AnnouncementStatus::Failed),
+            _ => Err(format!("Invalid announcement status: {input}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CampaignStatus {
+    /// Waiting for its start time.
+    Scheduled,
+    /// Being fanned out by the scheduler holding its lease.
+    Running,
+    Completed,
+    Cancelled,
+    /// Its end time passed before it reached its whole segment.
+    Expired,
+    Failed,
+}
+
+impl Display for CampaignStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CampaignStatus::Scheduled => write!(f, "SCHEDULED"),
+            CampaignStatus::Running => write!(f, "RUNNING"),
+            CampaignStatus::Completed => write!(f, "COMPLETED"),
+            CampaignStatus::Cancelled => write!(f, "CANCELLED"),
+            CampaignStatus::Expired => write!(f, "EXPIRED"),
+            CampaignStatus::Failed => write!(f, "FAILED"),
+        }
+    }
+}
+
+impl FromStr for CampaignStatus {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<CampaignStatus, Self::Err> {
+        match input {
+            "SCHEDULED" => Ok(CampaignStatus::Scheduled),
+            "RUNNING" => Ok(CampaignStatus::Running),
+            "COMPLETED" => Ok(CampaignStatus::Completed),
+            "CANCELLED" => Ok(CampaignStatus::Cancelled),
+            "EXPIRED" => Ok(CampaignStatus::Expired),
+            "FAILED" => Ok(CampaignStatus::Failed),
+            _ => Err(format!("Invalid campaign status: {input}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PriceAlertCondition {
+    /// The price rises to or above the target price.
+    Above,
+    /// The price falls to or below the target price.
+    Below,
+    /// The price moves by the given percentage from the reference price.
+    PercentChange,
+}
+
+impl Display for PriceAlertCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceAlertCondition::Above => write!(f, "ABOVE"),
+            PriceAlertCondition::Below => write!(f, "BELOW"),
+            PriceAlertCondition::PercentChange => write!(f, "PERCENT_CHANGE"),
+        }
+    }
+}
+
+impl FromStr for PriceAlertCondition {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<PriceAlertCondition, Self::Err> {
+        match input {
+            "ABOVE" => Ok(PriceAlertCondition::Above),
+            "BELOW" => Ok(PriceAlertCondition::Below),
+            "PERCENT_CHANGE" => Ok(PriceAlertCondition::PercentChange),
+            _ => Err(format!("Invalid price alert condition: {input}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SmsDeliveryStatus {
+    Sent,
+    Failed,
+    /// Not sent because the user reached the daily SMS limit.
+    Capped,
+}
+
+impl Display for SmsDeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SmsDeliveryStatus::Sent => write!(f, "SENT"),
+            SmsDeliveryStatus::Failed => write!(f, "FAILED"),
+            SmsDeliveryStatus::Capped => write!(f, "CAPPED"),
+        }
+    }
+}
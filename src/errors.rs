@@ -45,6 +45,9 @@ pub enum Error {
     #[error("{0}")]
     Forbidden(#[from] Forbidden),
 
+    #[error("{0}")]
+    TooManyRequests(#[from] TooManyRequests),
+
     #[error("{0}")]
     RunSyncTask(#[from] JoinError),
 
@@ -78,6 +81,7 @@ impl Error {
             Error::NotFound(_) => (StatusCode::NOT_FOUND, 40003),
             Error::Unauthorized(_) => (StatusCode::UNAUTHORIZED, 40003),
             Error::Forbidden(_) => (StatusCode::FORBIDDEN, 40003),
+            Error::TooManyRequests(_) => (StatusCode::TOO_MANY_REQUESTS, 40008),
             Error::Authenticate(AuthenticateError::WrongCredentials) => {
                 (StatusCode::UNAUTHORIZED, 40004)
             }
@@ -134,6 +138,12 @@ impl Error {
             message: message.to_string(),
         })
     }
+
+    pub fn too_many_requests(message: &str) -> Self {
+        Error::TooManyRequests(TooManyRequests {
+            message: message.to_string(),
+        })
+    }
 }
 
 impl IntoResponse for Error {
@@ -202,6 +212,12 @@ pub struct Forbidden {
     pub message: String,
 }
 
+#[derive(thiserror::Error, Debug)]
+#[error("Too many requests: {message}")]
+pub struct TooManyRequests {
+    pub message: String,
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error("Slow synchronization")]
 pub struct SlowSynchronization {
@@ -9,7 +9,9 @@ mod database;
 pub mod enums;
 pub mod errors;
 pub mod loading_fcm_token;
+pub mod loading_languages;
 pub mod loading_preferences;
+pub mod loading_templates;
 pub mod middleware;
 pub mod models;
 pub mod routes;
@@ -1,16 +1,37 @@
-use crate::core::cache::redis_emitter::get_redis_emitter;
-use crate::enums::UserFcmTokenStatus;
+use crate::core::cache::redis_emitter::{
+    SupervisedSubscription, get_redis_emitter, spawn_supervised_subscription,
+};
+use crate::enums::{TokenProvider, UserFcmTokenStatus};
 use crate::errors::Error;
 use crate::models::user_fcm_token::UserFcmToken;
 use crate::utils::models::ModelExt;
 use bson::doc;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::LazyLock;
 use tokio::sync::RwLock;
 
-static USER_FCM_TOKENS: LazyLock<RwLock<HashMap<String, Vec<String>>>> =
-    LazyLock::new(|| RwLock::new(HashMap::with_capacity(100_000)));
+static USER_FCM_TOKENS: LazyLock<RwLock<TokenCache>> = LazyLock::new(|| {
+    RwLock::new(TokenCache {
+        tokens: HashMap::with_capacity(100_000),
+        updated_during_preload: None,
+    })
+});
+
+struct TokenCache {
+    tokens: HashMap<String, Vec<DeviceToken>>,
+    /// Users whose tokens changed while a preload was reading the database,
+    /// so the preload does not replace their update with what it read.
+    updated_during_preload: Option<HashSet<String>>,
+}
+
+impl TokenCache {
+    fn mark_updated(&mut self, user_id: &str) {
+        if let Some(updated) = self.updated_during_preload.as_mut() {
+            updated.insert(user_id.to_string());
+        }
+    }
+}
 
 pub const UPDATE_FCM_TOKEN_CHANNEL: &str = "vdax:notification:update_fcm_token";
 
@@ -26,29 +47,67 @@ pub struct UpdateFcmToken {
     pub user_id: String,
     pub token: String,
     pub action: UpdateFcmTokenAction,
+    #[serde(default)]
+    pub platform: Option<String>,
+    #[serde(default)]
+    pub provider: TokenProvider,
+}
+
+/// A registered push token together with the platform it was registered from
+/// and the service that issued it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceToken {
+    pub token: String,
+    pub platform: Option<String>,
+    pub provider: TokenProvider,
+}
+
+impl From<UserFcmToken> for DeviceToken {
+    fn from(token: UserFcmToken) -> Self {
+        Self {
+            provider: token.provider(),
+            token: token.token,
+            platform: token.platform,
+        }
+    }
 }
 
 pub async fn preload_user_fcm_tokens() -> Result<(), Error> {
-    let query = doc! {};
-    let fcm_tokens = UserFcmToken::find(query, None).await?;
+    USER_FCM_TOKENS.write().await.updated_during_preload = Some(HashSet::new());
 
-    let mut map = USER_FCM_TOKENS.write().await;
-    map.clear();
+    let query = doc! {};
+    let fcm_tokens = match UserFcmToken::find(query, None).await {
+        Ok(fcm_tokens) => fcm_tokens,
+        Err(e) => {
+            USER_FCM_TOKENS.write().await.updated_during_preload = None;
+            return Err(e);
+        }
+    };
 
+    let mut map = HashMap::with_capacity(fcm_tokens.len());
     for token in fcm_tokens {
         if token.status == UserFcmTokenStatus::Active.to_string() {
-            map.entry(token.user_id).or_default().push(token.token);
+            map.entry(token.user_id.clone())
+                .or_default()
+                .push(DeviceToken::from(token));
         }
     }
 
+    let mut cache = USER_FCM_TOKENS.write().await;
+    // Users updated meanwhile are loaded from the database again on first use.
+    for user_id in cache.updated_during_preload.take().unwrap_or_default() {
+        map.remove(&user_id);
+    }
+    cache.tokens = map;
+
     Ok(())
 }
 
-pub async fn get_user_fcm_tokens(user_id: String) -> Result<Vec<String>, Error> {
+pub async fn get_user_fcm_tokens(user_id: String) -> Result<Vec<DeviceToken>, Error> {
     {
-        let map = USER_FCM_TOKENS.read().await;
+        let cache = USER_FCM_TOKENS.read().await;
 
-        if let Some(tokens) = map.get(&user_id) {
+        if let Some(tokens) = cache.tokens.get(&user_id) {
             return Ok(tokens.clone());
         }
     }
@@ -63,16 +122,18 @@ pub async fn get_user_fcm_tokens(user_id: String) -> Result<Vec<String>, Error>
 
     match UserFcmToken::find(query, None).await {
         Ok(tokens) => {
-            let mut map = USER_FCM_TOKENS.write().await;
+            let mut cache = USER_FCM_TOKENS.write().await;
             let mut result = Vec::new();
 
             for token in tokens {
                 if token.status == UserFcmTokenStatus::Active.to_string() {
-                    let fcm_token = token.token.clone();
-                    map.entry(user_id.clone())
+                    let device_token = DeviceToken::from(token);
+                    cache
+                        .tokens
+                        .entry(user_id.clone())
                         .or_default()
-                        .push(fcm_token.clone());
-                    result.push(fcm_token);
+                        .push(device_token.clone());
+                    result.push(device_token);
                 }
             }
 
@@ -90,7 +151,12 @@ pub async fn get_user_fcm_tokens(user_id: String) -> Result<Vec<String>, Error>
 pub async fn update_fcm_token_in_memory(action: UpdateFcmToken) -> anyhow::Result<()> {
     match action.action {
         UpdateFcmTokenAction::Add => {
-            add_fcm_token_to_memory(action.user_id, action.token.clone()).await
+            let token = DeviceToken {
+                token: action.token,
+                platform: action.platform,
+                provider: action.provider,
+            };
+            add_fcm_token_to_memory(action.user_id, token).await
         }
         UpdateFcmTokenAction::Remove => {
             remove_fcm_token_from_memory(action.user_id, action.token.clone()).await
@@ -98,9 +164,27 @@ pub async fn update_fcm_token_in_memory(action: UpdateFcmToken) -> anyhow::Resul
     }
 }
 
+/// Applies token updates broadcast on `UPDATE_FCM_TOKEN_CHANNEL` to the
+/// in-memory cache, reloading every token after a reconnect.
+pub fn spawn_fcm_token_sync() -> SupervisedSubscription {
+    spawn_supervised_subscription(
+        UPDATE_FCM_TOKEN_CHANNEL,
+        |update: UpdateFcmToken| Box::pin(update_fcm_token_in_memory(update)),
+        || async {
+            preload_user_fcm_tokens()
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to reload user FCM tokens: {e}"))?;
+            tracing::info!("User FCM tokens reloaded after resubscribing.");
+            Ok(())
+        },
+    )
+}
+
 pub async fn publish_fcm_token_update(
     user_id: String,
     token: String,
+    platform: Option<String>,
+    provider: TokenProvider,
     action: UpdateFcmTokenAction,
 ) -> Result<(), Error> {
     let redis_emitter = get_redis_emitter();
@@ -109,6 +193,8 @@ pub async fn publish_fcm_token_update(
         user_id: user_id.clone(),
         token,
         action,
+        platform,
+        provider,
     };
 
     redis_emitter
@@ -125,27 +211,64 @@ pub async fn publish_fcm_token_update(
     Ok(())
 }
 
+/// Deactivates a token that its `provider` rejected permanently and drops it
+/// from the cache of this and every other running publisher.
+pub async fn prune_fcm_token(
+    user_id: String,
+    token: String,
+    provider: TokenProvider,
+) -> Result<(), Error> {
+    let deactivated = UserFcmToken::deactivate_by_token(&token, provider).await?;
+
+    remove_fcm_token_from_memory(user_id.clone(), token.clone())
+        .await
+        .map_err(|e| {
+            Error::internal_err(&format!("Failed to remove FCM token from memory: {e}"))
+        })?;
+
+    publish_fcm_token_update(
+        user_id.clone(),
+        token,
+        None,
+        provider,
+        UpdateFcmTokenAction::Remove,
+    )
+    .await?;
+
+    tracing::info!(
+        "Pruned dead {} token for user ID {} ({} record(s) deactivated)",
+        provider,
+        user_id,
+        deactivated
+    );
+
+    Ok(())
+}
+
 async fn remove_fcm_token_from_memory(user_id: String, token: String) -> anyhow::Result<()> {
-    let mut map = USER_FCM_TOKENS.write().await;
+    let mut cache = USER_FCM_TOKENS.write().await;
+    cache.mark_updated(&user_id);
 
-    let tokens = map.get_mut(&user_id);
+    let tokens = cache.tokens.get_mut(&user_id);
     if let Some(tokens) = tokens {
-        tokens.retain(|t| t != &token);
+        tokens.retain(|t| t.token != token);
 
         if tokens.is_empty() {
-            map.remove(&user_id);
+            cache.tokens.remove(&user_id);
         }
     }
 
     Ok(())
 }
 
-async fn add_fcm_token_to_memory(user_id: String, token: String) -> anyhow::Result<()> {
-    let mut map = USER_FCM_TOKENS.write().await;
+async fn add_fcm_token_to_memory(user_id: String, token: DeviceToken) -> anyhow::Result<()> {
+    let mut cache = USER_FCM_TOKENS.write().await;
+    cache.mark_updated(&user_id);
 
-    let tokens = map.entry(user_id).or_default();
-    if !tokens.contains(&token) {
-        tokens.push(token.clone());
+    let tokens = cache.tokens.entry(user_id).or_default();
+    match tokens.iter_mut().find(|t| t.token == token.token) {
+        Some(existing) => *existing = token,
+        None => tokens.push(token),
     }
 
     Ok(())
@@ -0,0 +1,59 @@
+use crate::models::accounts::{Account, ELanguage};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Accounts rarely change language, so a stale entry only delays the switch.
+const LANGUAGE_CACHE_TTL: Duration = Duration::from_secs(600);
+
+static USER_LANGUAGES: LazyLock<RwLock<HashMap<String, (ELanguage, Instant)>>> =
+    LazyLock::new(|| RwLock::new(HashMap::with_capacity(100_000)));
+
+/// Account language of each user, looked up with one query for all users not
+/// cached yet. Users without an account, or when the lookup fails, get the
+/// default language.
+pub async fn get_user_languages_batch(user_ids: &[String]) -> HashMap<String, ELanguage> {
+    let mut result = HashMap::with_capacity(user_ids.len());
+    let mut missing_user_ids = Vec::new();
+
+    {
+        let map = USER_LANGUAGES.read().await;
+        for user_id in user_ids {
+            match map.get(user_id) {
+                Some((lang, loaded_at)) if loaded_at.elapsed() < LANGUAGE_CACHE_TTL => {
+                    result.insert(user_id.clone(), *lang);
+                }
+                _ => missing_user_ids.push(user_id.clone()),
+            }
+        }
+    }
+
+    if missing_user_ids.is_empty() {
+        return result;
+    }
+
+    let accounts = match Account::find_by_user_ids(&missing_user_ids).await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            tracing::warn!("Failed to batch fetch account languages: {e}");
+            for user_id in missing_user_ids {
+                result.insert(user_id, ELanguage::default());
+            }
+            return result;
+        }
+    };
+
+    for account in accounts {
+        result.insert(account.user_id, account.lang);
+    }
+
+    let now = Instant::now();
+    let mut map = USER_LANGUAGES.write().await;
+    for user_id in missing_user_ids {
+        let lang = *result.entry(user_id.clone()).or_default();
+        map.insert(user_id, (lang, now));
+    }
+
+    result
+}
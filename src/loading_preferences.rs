@@ -33,6 +33,9 @@ pub async fn load_user_notification_preferences() -> anyhow::Result<()> {
             account: setting.account,
             campaign: setting.campaign,
             transaction: setting.transaction,
+            price_alert: setting.price_alert,
+            language: setting.language,
+            timezone: setting.timezone,
         };
 
         map.insert(setting.user_id.clone(), preferences);
@@ -104,6 +107,9 @@ pub async fn get_user_notification_preferences(
             account: setting.account,
             campaign: setting.campaign,
             transaction: setting.transaction,
+            price_alert: setting.price_alert,
+            language: setting.language,
+            timezone: setting.timezone,
         }
     } else {
         NotificationPreferences {
@@ -111,6 +117,9 @@ pub async fn get_user_notification_preferences(
             account: true,
             campaign: true,
             transaction: true,
+            price_alert: true,
+            language: None,
+            timezone: None,
         }
     };
 
@@ -194,6 +203,9 @@ pub async fn get_user_notification_preferences_batch(
                         account: setting.account,
                         campaign: setting.campaign,
                         transaction: setting.transaction,
+                        price_alert: setting.price_alert,
+                        language: setting.language,
+                        timezone: setting.timezone,
                     };
                     result.insert(setting.user_id.clone(), preferences);
                     fetched_preferences.push((setting.user_id, preferences));
@@ -211,6 +223,9 @@ pub async fn get_user_notification_preferences_batch(
                     account: true,
                     campaign: true,
                     transaction: true,
+                    price_alert: true,
+                    language: None,
+                    timezone: None,
                 };
                 result.insert(user_id.clone(), default_prefs);
                 fetched_preferences.push((user_id, default_prefs));
@@ -246,6 +261,9 @@ pub async fn update_user_notification_preferences(
         account: setting.account,
         campaign: setting.campaign,
         transaction: setting.transaction,
+        price_alert: setting.price_alert,
+        language: setting.language,
+        timezone: setting.timezone,
     };
 
     let redis_key = get_redis_preference_key(&user_id);
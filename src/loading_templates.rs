@@ -0,0 +1,269 @@
+use crate::core::cache::redis_emitter::{
+    SupervisedSubscription, get_redis_emitter, spawn_supervised_subscription,
+};
+use crate::errors::Error;
+use crate::models::accounts::ELanguage;
+use crate::models::notification_templates::NotificationTemplate;
+use crate::utils::models::ModelExt;
+use crate::utils::structs::{NotifMetadata, NotifType};
+use minijinja::{Environment, UndefinedBehavior};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::RwLock;
+use wither::bson::doc;
+
+pub const TEMPLATES_UPDATED_CHANNEL: &str = "vdax:notification:templates_updated";
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Variant of the templates replacing a notification that follows ones a rate
+/// limited device missed; they get `count` in their context.
+pub const UNSENT_SUMMARY_VARIANT: &str = "UNSENT_SUMMARY";
+
+/// (kind, variant, locale) -> (title, body)
+type TemplateMap = HashMap<(String, String, String), (String, String)>;
+
+#[derive(Default)]
+struct TemplateCache {
+    /// Load the templates were read by; an older load never replaces them.
+    generation: u64,
+    templates: TemplateMap,
+}
+
+static NOTIFICATION_TEMPLATES: LazyLock<RwLock<TemplateCache>> =
+    LazyLock::new(|| RwLock::new(TemplateCache::default()));
+
+static TEMPLATE_LOADS: AtomicU64 = AtomicU64::new(0);
+
+static TEMPLATE_ENV: LazyLock<Environment<'static>> = LazyLock::new(|| {
+    let mut env = Environment::new();
+    // A typo in a variable name falls back to the built-in text instead of
+    // sending a message with a hole in it.
+    env.set_undefined_behavior(UndefinedBehavior::Strict);
+    env
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplatesUpdated {
+    pub template_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderedNotification {
+    pub title: String,
+    pub body: String,
+}
+
+/// Replaces the cached templates, unless a load that started later already
+/// did, as two reloads triggered by consecutive changes can finish out of order.
+pub async fn load_notification_templates() -> anyhow::Result<()> {
+    let generation = TEMPLATE_LOADS.fetch_add(1, Ordering::SeqCst) + 1;
+    let templates = NotificationTemplate::find(doc! {}, None)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch notification templates: {}", e))?;
+
+    let templates = templates
+        .into_iter()
+        .map(|template| {
+            (
+                (template.kind, template.variant, template.locale),
+                (template.title, template.body),
+            )
+        })
+        .collect::<TemplateMap>();
+
+    let mut cache = NOTIFICATION_TEMPLATES.write().await;
+    if cache.generation > generation {
+        tracing::info!("Discarded notification templates read before a newer reload");
+        return Ok(());
+    }
+
+    tracing::info!("Loaded {} notification templates", templates.len());
+    *cache = TemplateCache {
+        generation,
+        templates,
+    };
+
+    Ok(())
+}
+
+/// Reloads the templates whenever the admin API changes one.
+pub fn spawn_template_sync() -> SupervisedSubscription {
+    spawn_supervised_subscription(
+        TEMPLATES_UPDATED_CHANNEL,
+        |update: TemplatesUpdated| {
+            Box::pin(async move {
+                tracing::info!(
+                    "Template {} changed, reloading templates",
+                    update.template_id
+                );
+                load_notification_templates().await
+            })
+        },
+        load_notification_templates,
+    )
+}
+
+pub fn publish_templates_updated(template_id: String) -> Result<(), Error> {
+    get_redis_emitter()
+        .publish(TEMPLATES_UPDATED_CHANNEL, &TemplatesUpdated { template_id })
+        .map_err(|e| Error::internal_err(&format!("Failed to publish template update: {e}")))
+}
+
+pub fn render_template(
+    title: &str,
+    body: &str,
+    context: &serde_json::Value,
+) -> Result<RenderedNotification, minijinja::Error> {
+    Ok(RenderedNotification {
+        title: TEMPLATE_ENV.render_str(title, context)?,
+        body: TEMPLATE_ENV.render_str(body, context)?,
+    })
+}
+
+/// Checks that `source` compiles, without rendering it.
+pub fn validate_template(source: &str) -> Result<(), minijinja::Error> {
+    TEMPLATE_ENV.template_from_str(source).map(|_| ())
+}
+
+/// Renders the stored template for the event in `lang`, falling back to the
+/// built-in text in `lang`, and to the default locale template only when
+/// there is no built-in text. `time` is the formatted event time.
+pub async fn render_notification(
+    notif_type: NotifType,
+    metadata: &NotifMetadata,
+    lang: ELanguage,
+    time: &str,
+) -> anyhow::Result<RenderedNotification> {
+    let locale = lang.to_string();
+    if let Some(rendered) = render_stored_template(notif_type, metadata, &locale, time).await {
+        return Ok(rendered);
+    }
+
+    let built_in = metadata
+        .construct_message(lang, time)
+        .map(|body| RenderedNotification {
+            title: metadata
+                .title()
+                .unwrap_or_else(|| notif_type.construct_title(lang)),
+            body,
+        });
+    match built_in {
+        Err(e) if locale != DEFAULT_LOCALE => {
+            match render_stored_template(notif_type, metadata, DEFAULT_LOCALE, time).await {
+                Some(rendered) => {
+                    tracing::warn!(
+                        "No built-in {notif_type} text in {locale}, using the {DEFAULT_LOCALE} template: {e:#}"
+                    );
+                    Ok(rendered)
+                }
+                None => Err(e),
+            }
+        }
+        built_in => built_in,
+    }
+}
+
+/// Renders the summary of `count` missed notifications of `notif_type` from
+/// its `UNSENT_SUMMARY` template in `lang`, falling back to the built-in text.
+pub async fn render_unsent_summary(
+    notif_type: NotifType,
+    lang: ELanguage,
+    count: i64,
+) -> RenderedNotification {
+    let kind = notif_type.to_string();
+    let locale = lang.to_string();
+    let template = NOTIFICATION_TEMPLATES
+        .read()
+        .await
+        .templates
+        .get(&(
+            kind.clone(),
+            UNSENT_SUMMARY_VARIANT.to_string(),
+            locale.clone(),
+        ))
+        .cloned();
+
+    if let Some((title, body)) = template {
+        match render_template(&title, &body, &serde_json::json!({ "count": count })) {
+            Ok(rendered) => return rendered,
+            Err(e) => tracing::warn!(
+                "Failed to render {kind} {UNSENT_SUMMARY_VARIANT} template in {locale}: {e:#}"
+            ),
+        }
+    }
+
+    built_in_unsent_summary(lang, count)
+}
+
+fn built_in_unsent_summary(lang: ELanguage, count: i64) -> RenderedNotification {
+    let (title, body) = match lang {
+        ELanguage::en => (
+            "You have many notifications".to_string(),
+            format!("You have {count} unread notifications. Please check your app."),
+        ),
+        ELanguage::vi => (
+            "Bạn có nhiều thông báo".to_string(),
+            format!("Bạn có {count} thông báo chưa đọc. Vui lòng kiểm tra ứng dụng."),
+        ),
+    };
+
+    RenderedNotification { title, body }
+}
+
+/// Renders the most specific stored template of the event in `locale`, if
+/// there is one and it renders.
+async fn render_stored_template(
+    notif_type: NotifType,
+    metadata: &NotifMetadata,
+    locale: &str,
+    time: &str,
+) -> Option<RenderedNotification> {
+    let kind = notif_type.to_string();
+    let (title, body) = {
+        let cache = NOTIFICATION_TEMPLATES.read().await;
+        metadata
+            .template_variants()
+            .into_iter()
+            .find_map(|variant| {
+                cache
+                    .templates
+                    .get(&(kind.clone(), variant, locale.to_string()))
+                    .cloned()
+            })?
+    };
+
+    let lang = ELanguage::from_str(locale).unwrap_or_default();
+    match render_template(&title, &body, &metadata.template_context(lang, time)) {
+        Ok(rendered) => Some(rendered),
+        Err(e) => {
+            tracing::warn!("Failed to render {kind} template in {locale}: {e:#}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_unsent_summary_is_localized() {
+        let en = built_in_unsent_summary(ELanguage::en, 3);
+        assert_eq!(en.title, "You have many notifications");
+        assert_eq!(
+            en.body,
+            "You have 3 unread notifications. Please check your app."
+        );
+
+        let vi = built_in_unsent_summary(ELanguage::vi, 3);
+        assert_eq!(vi.title, "Bạn có nhiều thông báo");
+        assert_eq!(
+            vi.body,
+            "Bạn có 3 thông báo chưa đọc. Vui lòng kiểm tra ứng dụng."
+        );
+    }
+}
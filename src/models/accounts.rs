@@ -1,12 +1,13 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use strum_macros::{Display, VariantNames};
+use strum_macros::{Display, EnumString, VariantNames};
 use utoipa::ToSchema;
 use validator::Validate;
 use wither::Model as WitherModel;
 use wither::bson::DateTime;
-use wither::bson::{doc, oid::ObjectId};
+use wither::bson::{Document, doc, oid::ObjectId};
 use wither::mongodb::Database;
+use wither::mongodb::options::FindOptions;
 
 use crate::database;
 use crate::errors::Error;
@@ -34,16 +35,35 @@ pub struct Account {
     pub has_order: bool,
     pub last_login_at: DateTime,
     pub referral_code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// E.164 formatted, e.g. `+84901234567`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone_number: Option<String>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
 
 #[derive(
-    Debug, Serialize, Deserialize, Clone, PartialEq, PartialOrd, ToSchema, VariantNames, Display,
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    ToSchema,
+    VariantNames,
+    Display,
+    EnumString,
 )]
 #[strum(serialize_all = "lowercase")]
 #[allow(non_camel_case_types)]
 pub enum ELanguage {
+    #[default]
     en,
     vi,
 }
@@ -52,4 +72,26 @@ impl Account {
     pub async fn get_account_by_user_id(user_id: &str) -> Result<Option<Account>, Error> {
         <Self as ModelExt>::find_one(doc! { "userId": user_id }, None).await
     }
+
+    pub async fn find_by_user_ids(user_ids: &[String]) -> Result<Vec<Account>, Error> {
+        <Self as ModelExt>::find(doc! { "userId": { "$in": user_ids } }, None).await
+    }
+
+    /// Up to `limit` accounts matching `filter` with an id after `after`, in
+    /// id order, to page through large audiences without skipping.
+    pub async fn find_page(
+        mut filter: Document,
+        after: Option<ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<Account>, Error> {
+        if let Some(after) = after {
+            filter.insert("_id", doc! { "$gt": after });
+        }
+        let options = FindOptions::builder()
+            .sort(doc! { "_id": 1 })
+            .limit(limit)
+            .build();
+
+        <Self as ModelExt>::find(filter, options).await
+    }
 }
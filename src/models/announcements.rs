@@ -0,0 +1,215 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{Document, doc, oid::ObjectId};
+use wither::mongodb::Database;
+
+use crate::database;
+use crate::enums::AnnouncementStatus;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+use crate::utils::segment::AudienceSegment;
+
+#[async_trait]
+impl ModelExt for Announcement {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// A message broadcast by an operator to every user of a segment.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct Announcement {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[validate(length(min = 1, max = 256))]
+    pub title: String,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// App path opened by the notification, e.g. `markets/SUIUSDT`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub segment: AudienceSegment,
+    /// `AnnouncementStatus` value, e.g. `SCHEDULED`.
+    pub status: String,
+    pub scheduled_at: DateTime,
+    /// Admin who created the announcement.
+    pub created_by: String,
+    /// Id of the last account fanned out to; the fan-out resumes after it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<ObjectId>,
+    /// Accounts in the segment when the fan-out started.
+    #[serde(default)]
+    pub total_recipients: i64,
+    /// Accounts looked at so far, including the ones skipped.
+    #[serde(default)]
+    pub processed: i64,
+    /// Inbox entries written.
+    #[serde(default)]
+    pub delivered: i64,
+    /// Accounts that opted out of announcements.
+    #[serde(default)]
+    pub skipped: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl Announcement {
+    /// Id of the event notifications of this announcement are created from.
+    pub fn source_event_id(&self) -> String {
+        format!(
+            "announcement:{}",
+            self.id.map(|id| id.to_hex()).unwrap_or_default()
+        )
+    }
+
+    /// Updates an announcement that has not started yet.
+    pub async fn update_scheduled_by_id(id: ObjectId, mut set: Document) -> Result<Self, Error> {
+        set.insert("updatedAt", DateTime::now());
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id, "status": AnnouncementStatus::Scheduled.to_string() },
+            doc! { "$set": set },
+            false,
+        )
+        .await?
+        .ok_or_else(|| Error::bad_request("Only scheduled announcements can be changed"))
+    }
+
+    /// Stops an announcement that has not finished; inbox entries already
+    /// written stay.
+    pub async fn cancel_by_id(id: ObjectId) -> Result<Self, Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! {
+                "_id": id,
+                "status": {
+                    "$in": [
+                        AnnouncementStatus::Scheduled.to_string(),
+                        AnnouncementStatus::Sending.to_string(),
+                    ]
+                },
+            },
+            doc! {
+                "$set": {
+                    "status": AnnouncementStatus::Cancelled.to_string(),
+                    "finishedAt": now,
+                    "updatedAt": now,
+                }
+            },
+            false,
+        )
+        .await?
+        .ok_or_else(|| {
+            Error::bad_request("Only scheduled or sending announcements can be cancelled")
+        })
+    }
+
+    /// Moves one announcement that is due from scheduled to sending, so only
+    /// one worker fans it out.
+    pub async fn claim_due() -> Result<Option<Self>, Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! {
+                "status": AnnouncementStatus::Scheduled.to_string(),
+                "scheduledAt": { "$lte": now },
+            },
+            doc! {
+                "$set": {
+                    "status": AnnouncementStatus::Sending.to_string(),
+                    "startedAt": now,
+                    "updatedAt": now,
+                }
+            },
+            false,
+        )
+        .await
+    }
+
+    /// Announcements left sending by a worker that stopped.
+    pub async fn find_sending() -> Result<Vec<Self>, Error> {
+        <Self as ModelExt>::find(
+            doc! { "status": AnnouncementStatus::Sending.to_string() },
+            None,
+        )
+        .await
+    }
+
+    pub async fn is_sending(id: ObjectId) -> Result<bool, Error> {
+        <Self as ModelExt>::exists(
+            doc! { "_id": id, "status": AnnouncementStatus::Sending.to_string() },
+        )
+        .await
+    }
+
+    pub async fn set_total_recipients(id: ObjectId, total_recipients: i64) -> Result<(), Error> {
+        <Self as ModelExt>::update_one(
+            doc! { "_id": id },
+            doc! { "$set": { "totalRecipients": total_recipients, "updatedAt": DateTime::now() } },
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Saves the progress of one page of the fan-out.
+    pub async fn record_progress(
+        id: ObjectId,
+        cursor: ObjectId,
+        processed: i64,
+        delivered: i64,
+        skipped: i64,
+    ) -> Result<(), Error> {
+        <Self as ModelExt>::update_one(
+            doc! { "_id": id },
+            doc! {
+                "$set": { "cursor": cursor, "updatedAt": DateTime::now() },
+                "$inc": { "processed": processed, "delivered": delivered, "skipped": skipped },
+            },
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Ends the fan-out, unless the announcement was cancelled meanwhile.
+    pub async fn finish(id: ObjectId, error: Option<String>) -> Result<(), Error> {
+        let status = match error {
+            Some(_) => AnnouncementStatus::Failed,
+            None => AnnouncementStatus::Completed,
+        };
+        let now = DateTime::now();
+
+        <Self as ModelExt>::update_one(
+            doc! { "_id": id, "status": AnnouncementStatus::Sending.to_string() },
+            doc! {
+                "$set": {
+                    "status": status.to_string(),
+                    "error": error,
+                    "finishedAt": now,
+                    "updatedAt": now,
+                }
+            },
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,271 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{Document, doc, oid::ObjectId};
+use wither::mongodb::Database;
+
+use crate::database;
+use crate::enums::CampaignStatus;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+use crate::utils::segment::AudienceSegment;
+
+#[async_trait]
+impl ModelExt for Campaign {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// Marketing message sent to a segment between its start and end time.
+///
+/// A scheduler replica owns a running campaign while it holds the lease,
+/// i.e. while `leaseOwner` is its owner id and `leaseExpiresAt` is in the
+/// future. Every write of the fan-out is conditioned on the lease, so a
+/// replica that lost it stops instead of sending the campaign twice.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct Campaign {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+    #[validate(length(min = 1, max = 256))]
+    pub title: String,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// App path opened by the notification, e.g. `promotions/zero-fee`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    #[serde(default)]
+    pub segment: AudienceSegment,
+    /// `CampaignStatus` value, e.g. `SCHEDULED`.
+    pub status: String,
+    pub starts_at: DateTime,
+    /// Users not reached by then are not sent the campaign anymore.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<DateTime>,
+    pub created_by: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lease_owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lease_expires_at: Option<DateTime>,
+    /// Id of the last account published to; the fan-out resumes after it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<ObjectId>,
+    /// Accounts in the segment when the fan-out started.
+    #[serde(default)]
+    pub total_recipients: i64,
+    /// Notifications published; users who opted out are filtered later.
+    #[serde(default)]
+    pub published: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl Campaign {
+    /// Id of the event notifications of this campaign are created from.
+    pub fn source_event_id(&self) -> String {
+        format!(
+            "campaign:{}",
+            self.id.map(|id| id.to_hex()).unwrap_or_default()
+        )
+    }
+
+    pub fn has_ended(&self) -> bool {
+        self.ends_at
+            .is_some_and(|ends_at| ends_at <= DateTime::now())
+    }
+
+    /// Updates a campaign that has not started yet.
+    pub async fn update_scheduled_by_id(id: ObjectId, mut set: Document) -> Result<Self, Error> {
+        set.insert("updatedAt", DateTime::now());
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id, "status": CampaignStatus::Scheduled.to_string() },
+            doc! { "$set": set },
+            false,
+        )
+        .await?
+        .ok_or_else(|| Error::bad_request("Only scheduled campaigns can be changed"))
+    }
+
+    /// Stops a campaign that has not finished; the replica running it notices
+    /// on its next write.
+    pub async fn cancel_by_id(id: ObjectId) -> Result<Self, Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! {
+                "_id": id,
+                "status": {
+                    "$in": [
+                        CampaignStatus::Scheduled.to_string(),
+                        CampaignStatus::Running.to_string(),
+                    ]
+                },
+            },
+            doc! {
+                "$set": {
+                    "status": CampaignStatus::Cancelled.to_string(),
+                    "finishedAt": now,
+                    "updatedAt": now,
+                },
+                "$unset": { "leaseOwner": "", "leaseExpiresAt": "" },
+            },
+            false,
+        )
+        .await?
+        .ok_or_else(|| Error::bad_request("Only scheduled or running campaigns can be cancelled"))
+    }
+
+    /// Takes the lease of one campaign that is due, or whose previous owner
+    /// stopped renewing its lease. Mongo applies the update to a single
+    /// document atomically, so only one replica gets each campaign.
+    pub async fn claim_due(owner: &str, lease_secs: i64) -> Result<Option<Self>, Error> {
+        let now = DateTime::now();
+        let lease_expires_at = lease_deadline(lease_secs);
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! {
+                "startsAt": { "$lte": now },
+                "$or": [
+                    { "status": CampaignStatus::Scheduled.to_string() },
+                    {
+                        "status": CampaignStatus::Running.to_string(),
+                        "leaseExpiresAt": { "$lt": now },
+                    },
+                ],
+            },
+            doc! {
+                "$set": {
+                    "status": CampaignStatus::Running.to_string(),
+                    "leaseOwner": owner,
+                    "leaseExpiresAt": lease_expires_at,
+                    "updatedAt": now,
+                },
+                // Keeps the time of the first claim when a campaign is taken over.
+                "$min": { "startedAt": now },
+            },
+            false,
+        )
+        .await
+    }
+
+    fn lease_filter(id: ObjectId, owner: &str) -> Document {
+        doc! {
+            "_id": id,
+            "status": CampaignStatus::Running.to_string(),
+            "leaseOwner": owner,
+        }
+    }
+
+    /// Sets the audience size and extends the lease. Returns `false` when the
+    /// lease was lost.
+    pub async fn set_total_recipients(
+        id: ObjectId,
+        owner: &str,
+        lease_secs: i64,
+        total_recipients: i64,
+    ) -> Result<bool, Error> {
+        let result = <Self as ModelExt>::update_one(
+            Self::lease_filter(id, owner),
+            doc! {
+                "$set": {
+                    "totalRecipients": total_recipients,
+                    "leaseExpiresAt": lease_deadline(lease_secs),
+                    "updatedAt": DateTime::now(),
+                }
+            },
+            None,
+        )
+        .await?;
+
+        Ok(result.matched_count == 1)
+    }
+
+    /// Extends the lease before a page is published. Returns `false` when the
+    /// lease was lost or the campaign cancelled.
+    pub async fn renew_lease(id: ObjectId, owner: &str, lease_secs: i64) -> Result<bool, Error> {
+        let result = <Self as ModelExt>::update_one(
+            Self::lease_filter(id, owner),
+            doc! {
+                "$set": {
+                    "leaseExpiresAt": lease_deadline(lease_secs),
+                    "updatedAt": DateTime::now(),
+                }
+            },
+            None,
+        )
+        .await?;
+
+        Ok(result.matched_count == 1)
+    }
+
+    /// Saves the progress of one page and extends the lease. Returns `false`
+    /// when the lease was lost or the campaign cancelled.
+    pub async fn record_progress(
+        id: ObjectId,
+        owner: &str,
+        lease_secs: i64,
+        cursor: ObjectId,
+        published: i64,
+    ) -> Result<bool, Error> {
+        let result = <Self as ModelExt>::update_one(
+            Self::lease_filter(id, owner),
+            doc! {
+                "$set": {
+                    "cursor": cursor,
+                    "leaseExpiresAt": lease_deadline(lease_secs),
+                    "updatedAt": DateTime::now(),
+                },
+                "$inc": { "published": published },
+            },
+            None,
+        )
+        .await?;
+
+        Ok(result.matched_count == 1)
+    }
+
+    /// Ends the campaign and releases its lease, if `owner` still holds it.
+    pub async fn finish(
+        id: ObjectId,
+        owner: &str,
+        status: CampaignStatus,
+        error: Option<String>,
+    ) -> Result<(), Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::update_one(
+            Self::lease_filter(id, owner),
+            doc! {
+                "$set": {
+                    "status": status.to_string(),
+                    "error": error,
+                    "finishedAt": now,
+                    "updatedAt": now,
+                },
+                "$unset": { "leaseOwner": "", "leaseExpiresAt": "" },
+            },
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn lease_deadline(lease_secs: i64) -> DateTime {
+    DateTime::from_millis(DateTime::now().timestamp_millis() + lease_secs * 1000)
+}
@@ -0,0 +1,60 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{doc, oid::ObjectId};
+use wither::mongodb::Database;
+
+use crate::database;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for EmailSuppression {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// An address the SMTP relay rejected permanently; no further email is sent to it.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailSuppression {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub email: String,
+    pub user_id: String,
+    pub reason: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl EmailSuppression {
+    pub async fn is_suppressed(email: &str) -> Result<bool, Error> {
+        let suppression =
+            <Self as ModelExt>::find_one(doc! { "email": email.to_lowercase() }, None).await?;
+
+        Ok(suppression.is_some())
+    }
+
+    pub async fn suppress(email: &str, user_id: &str, reason: &str) -> Result<(), Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "email": email.to_lowercase() },
+            doc! {
+                "$set": {
+                    "userId": user_id,
+                    "reason": reason,
+                    "updatedAt": now,
+                },
+                "$setOnInsert": { "createdAt": now },
+            },
+            true,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,4 +1,15 @@
 pub mod accounts;
+pub mod announcements;
+pub mod campaigns;
+pub mod email_suppressions;
+pub mod notification_templates;
+pub mod price_alerts;
+pub mod sms_deliveries;
+pub mod telegram_links;
 pub mod user_fcm_token;
 pub mod user_notification_settings;
 pub mod user_notifications;
+pub mod web_push_subscriptions;
+pub mod webhook_deliveries;
+pub mod webhook_jobs;
+pub mod webhook_subscriptions;
@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{Document, doc, oid::ObjectId};
+use wither::mongodb::Database;
+use wither::mongodb::options::FindOptions;
+
+use crate::database;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for NotificationTemplate {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// Title and body of one kind of notification in one locale, as minijinja
+/// templates rendered with variables taken from the notification metadata.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationTemplate {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    /// Notification type, e.g. `ORDER`.
+    pub kind: String,
+    /// Status or action within the kind, e.g. `FILLED`, or `*` for any.
+    pub variant: String,
+    pub locale: String,
+    #[validate(length(min = 1, max = 256))]
+    pub title: String,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl NotificationTemplate {
+    pub async fn find_all() -> Result<Vec<Self>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! { "kind": 1, "variant": 1, "locale": 1 })
+            .build();
+
+        <Self as ModelExt>::find(doc! {}, Some(options)).await
+    }
+
+    pub async fn find_by_key(
+        kind: &str,
+        variant: &str,
+        locale: &str,
+    ) -> Result<Option<Self>, Error> {
+        <Self as ModelExt>::find_one(
+            doc! { "kind": kind, "variant": variant, "locale": locale },
+            None,
+        )
+        .await
+    }
+
+    pub async fn update_by_id(id: ObjectId, set: Document) -> Result<Self, Error> {
+        let mut set = set;
+        set.insert("updatedAt", DateTime::now());
+
+        <Self as ModelExt>::find_one_and_update(doc! { "_id": id }, doc! { "$set": set }, false)
+            .await?
+            .ok_or_else(|| Error::not_found("Template not found"))
+    }
+
+    pub async fn delete_by_id(id: ObjectId) -> Result<(), Error> {
+        let result = <Self as ModelExt>::delete_one(doc! { "_id": id }).await?;
+        if result.deleted_count == 0 {
+            return Err(Error::not_found("Template not found"));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{Bson, Document, doc, oid::ObjectId};
+use wither::mongodb::Database;
+use wither::mongodb::options::FindOptions;
+
+use crate::database;
+use crate::enums::PriceAlertCondition;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for PriceAlert {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// Which way the price has to move through a threshold to trigger an alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CrossDirection {
+    Rising,
+    Falling,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceAlert {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: String,
+    /// Market symbol as it appears in the price feed, e.g. `SUIUSDT`.
+    pub symbol: String,
+    /// `PriceAlertCondition` value, e.g. `ABOVE`.
+    pub condition: String,
+    /// Price to cross for `ABOVE` and `BELOW` alerts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_price: Option<Decimal>,
+    /// Move in percent for `PERCENT_CHANGE` alerts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<Decimal>,
+    /// Price `PERCENT_CHANGE` alerts measure from; moves to the trigger price
+    /// each time a recurring alert fires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_price: Option<Decimal>,
+    /// Recurring alerts stay active and fire on every crossing.
+    pub recurring: bool,
+    pub is_active: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime>,
+    pub trigger_count: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_triggered_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_triggered_price: Option<Decimal>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl PriceAlert {
+    pub fn condition(&self) -> Option<PriceAlertCondition> {
+        PriceAlertCondition::from_str(&self.condition).ok()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= DateTime::now())
+    }
+
+    /// Prices whose crossing triggers the alert.
+    pub fn thresholds(&self) -> Vec<(CrossDirection, Decimal)> {
+        match self.condition() {
+            Some(PriceAlertCondition::Above) => self
+                .target_price
+                .map(|price| vec![(CrossDirection::Rising, price)])
+                .unwrap_or_default(),
+            Some(PriceAlertCondition::Below) => self
+                .target_price
+                .map(|price| vec![(CrossDirection::Falling, price)])
+                .unwrap_or_default(),
+            Some(PriceAlertCondition::PercentChange) => {
+                match (self.reference_price, self.percent) {
+                    (Some(reference), Some(percent)) => {
+                        let change = reference * percent / Decimal::ONE_HUNDRED;
+                        vec![
+                            (CrossDirection::Rising, reference + change),
+                            (CrossDirection::Falling, reference - change),
+                        ]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub async fn find_by_user_id(user_id: &str) -> Result<Vec<Self>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! { "createdAt": -1 })
+            .build();
+
+        <Self as ModelExt>::find(doc! { "userId": user_id }, Some(options)).await
+    }
+
+    pub async fn find_by_user_id_and_id(user_id: &str, id: ObjectId) -> Result<Self, Error> {
+        <Self as ModelExt>::find_one(doc! { "_id": id, "userId": user_id }, None)
+            .await?
+            .ok_or_else(|| Error::not_found("Price alert not found"))
+    }
+
+    /// Active alerts that have not expired yet.
+    pub async fn find_active() -> Result<Vec<Self>, Error> {
+        let query = doc! {
+            "isActive": true,
+            "$or": [
+                { "expiresAt": null },
+                { "expiresAt": { "$gt": DateTime::now() } },
+            ],
+        };
+
+        <Self as ModelExt>::find(query, None).await
+    }
+
+    pub async fn count_active_by_user_id(user_id: &str) -> Result<u64, Error> {
+        <Self as ModelExt>::count(doc! { "userId": user_id, "isActive": true }).await
+    }
+
+    pub async fn update_by_user_id_and_id(
+        user_id: &str,
+        id: ObjectId,
+        mut set: Document,
+    ) -> Result<Self, Error> {
+        set.insert("updatedAt", DateTime::now());
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id, "userId": user_id },
+            doc! { "$set": set },
+            false,
+        )
+        .await?
+        .ok_or_else(|| Error::not_found("Price alert not found"))
+    }
+
+    pub async fn delete_by_user_id_and_id(user_id: &str, id: ObjectId) -> Result<(), Error> {
+        let result = <Self as ModelExt>::delete_one(doc! { "_id": id, "userId": user_id }).await?;
+
+        if result.deleted_count == 0 {
+            return Err(Error::not_found("Price alert not found"));
+        }
+
+        Ok(())
+    }
+
+    /// Records that the alert fired at `price`. One-shot alerts are
+    /// deactivated; recurring percent-change alerts measure from `price` next.
+    ///
+    /// Only applies to the alert as read, i.e. with the same trigger count, so
+    /// recording the same trigger twice counts it once. Returns `None` then.
+    pub async fn record_trigger(&self, price: Decimal) -> Result<Option<Self>, Error> {
+        let Some(id) = self.id else {
+            return Ok(None);
+        };
+
+        let now = DateTime::now();
+        let mut set = doc! {
+            "lastTriggeredAt": now,
+            "lastTriggeredPrice": price.to_string(),
+            "updatedAt": now,
+        };
+        if !self.recurring {
+            set.insert("isActive", false);
+        } else if self.condition() == Some(PriceAlertCondition::PercentChange) {
+            set.insert("referencePrice", price.to_string());
+        }
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id, "isActive": true, "triggerCount": self.trigger_count },
+            doc! { "$set": set, "$inc": { "triggerCount": 1 } },
+            false,
+        )
+        .await
+    }
+
+    pub async fn deactivate(id: ObjectId) -> Result<(), Error> {
+        <Self as ModelExt>::update_one(
+            doc! { "_id": id },
+            doc! { "$set": { "isActive": false, "updatedAt": DateTime::now() } },
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// BSON value for an optional price, stored as a string like the rest of the
+/// document.
+pub fn decimal_to_bson(value: Option<Decimal>) -> Bson {
+    value
+        .map(|value| Bson::String(value.to_string()))
+        .unwrap_or(Bson::Null)
+}
@@ -0,0 +1,85 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::oid::ObjectId;
+use wither::mongodb::Database;
+
+use crate::database;
+use crate::enums::SmsDeliveryStatus;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for SmsDelivery {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// Outcome of one SMS sent, or held back, for an account event.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct SmsDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: String,
+    /// Only the last digits are kept, e.g. `*******4567`.
+    pub phone_number: String,
+    pub event_type: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_message_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl SmsDelivery {
+    pub async fn record(
+        user_id: &str,
+        phone_number: &str,
+        event_type: &str,
+        status: SmsDeliveryStatus,
+        provider_message_id: Option<String>,
+        error: Option<String>,
+    ) -> Result<Self, Error> {
+        let now = DateTime::now();
+
+        Self::create(Self {
+            id: None,
+            user_id: user_id.to_string(),
+            phone_number: mask_phone_number(phone_number),
+            event_type: event_type.to_string(),
+            status: status.to_string(),
+            provider_message_id,
+            error,
+            created_at: now,
+            updated_at: now,
+        })
+        .await
+    }
+}
+
+/// Keeps the last four characters; counts characters rather than bytes, as
+/// the number comes from user input.
+fn mask_phone_number(phone_number: &str) -> String {
+    let hidden = phone_number.chars().count().saturating_sub(4);
+    let shown = phone_number.chars().skip(hidden).collect::<String>();
+
+    format!("{}{shown}", "*".repeat(hidden))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_all_but_the_last_four_characters() {
+        assert_eq!(mask_phone_number("+84901234567"), "********4567");
+        assert_eq!(mask_phone_number("123"), "123");
+        assert_eq!(mask_phone_number("+８４９０１２３４"), "*****１２３４");
+    }
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{doc, oid::ObjectId};
+use wither::mongodb::Database;
+
+use crate::database;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for TelegramLink {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// The Telegram private chat a user linked through the bot's `/start` command.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct TelegramLink {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: String,
+    pub chat_id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl TelegramLink {
+    pub async fn find_by_user_id(user_id: &str) -> Result<Option<Self>, Error> {
+        <Self as ModelExt>::find_one(doc! { "userId": user_id }, None).await
+    }
+
+    /// Links `chat_id` to `user_id`, replacing any chat the user linked before
+    /// and detaching the chat from any other user.
+    pub async fn link(
+        user_id: &str,
+        chat_id: i64,
+        username: Option<String>,
+    ) -> Result<Self, Error> {
+        <Self as ModelExt>::delete_many(doc! { "chatId": chat_id, "userId": { "$ne": user_id } })
+            .await?;
+
+        let now = DateTime::now();
+        let update = doc! {
+            "$set": {
+                "chatId": chat_id,
+                "username": username,
+                "updatedAt": now,
+            },
+            "$setOnInsert": {
+                "createdAt": now,
+            }
+        };
+
+        <Self as ModelExt>::find_one_and_update(doc! { "userId": user_id }, update, true)
+            .await?
+            .ok_or_else(|| {
+                Error::internal_err(&format!(
+                    "Failed to link Telegram chat for user_id={user_id}"
+                ))
+            })
+    }
+
+    pub async fn unlink_by_user_id(user_id: &str) -> Result<bool, Error> {
+        let result = <Self as ModelExt>::delete_one(doc! { "userId": user_id }).await?;
+
+        Ok(result.deleted_count > 0)
+    }
+
+    pub async fn unlink_by_chat_id(chat_id: i64) -> Result<u64, Error> {
+        let result = <Self as ModelExt>::delete_many(doc! { "chatId": chat_id }).await?;
+
+        Ok(result.deleted_count)
+    }
+}
@@ -1,16 +1,18 @@
 //! main database
 
 use crate::database;
-use crate::enums::UserFcmTokenStatus;
+use crate::enums::{TokenProvider, UserFcmTokenStatus};
 use crate::errors::Error;
 use crate::utils::models::ModelExt;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use validator::Validate;
 use wither::Model as WitherModel;
 use wither::bson::DateTime;
-use wither::bson::{doc, oid::ObjectId};
+use wither::bson::{Bson, doc, oid::ObjectId};
 use wither::mongodb::Database;
+use wither::mongodb::options::FindOptions;
 
 #[async_trait]
 impl ModelExt for UserFcmToken {
@@ -31,14 +33,57 @@ pub struct UserFcmToken {
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen_at: Option<DateTime>,
+    /// `TokenProvider` value; records registered before it existed are FCM tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
 }
 
 impl UserFcmToken {
+    pub async fn find_by_device_id(device_id: &str) -> Result<Option<Self>, Error> {
+        <Self as ModelExt>::find_one(doc! { "deviceId": device_id }, None).await
+    }
+
+    pub fn provider(&self) -> TokenProvider {
+        self.provider
+            .as_deref()
+            .and_then(|provider| TokenProvider::from_str(provider).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == UserFcmTokenStatus::Active.to_string()
+    }
+
+    /// Records registered before `lastSeenAt` existed fall back to `updatedAt`.
+    pub fn last_seen(&self) -> DateTime {
+        self.last_seen_at.unwrap_or(self.updated_at)
+    }
+
+    pub async fn find_by_user_id(user_id: &str) -> Result<Vec<Self>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! { "lastSeenAt": -1, "updatedAt": -1 })
+            .build();
+
+        <Self as ModelExt>::find(doc! { "userId": user_id }, Some(options)).await
+    }
+
+    pub async fn find_active_by_user_id(user_id: &str) -> Result<Vec<Self>, Error> {
+        let query = doc! {
+            "userId": user_id,
+            "status": UserFcmTokenStatus::Active.to_string(),
+        };
+
+        <Self as ModelExt>::find(query, None).await
+    }
+
     pub async fn create_or_update(
         user_id: String,
         device_id: Option<String>,
         token: String,
         platform: Option<String>,
+        provider: TokenProvider,
     ) -> Result<Self, Error> {
         let now = DateTime::now();
 
@@ -52,8 +97,10 @@ impl UserFcmToken {
                 "deviceId": &device_id,
                 "token": &token,
                 "platform": platform.as_ref(),
+                "provider": provider.to_string(),
                 "status": UserFcmTokenStatus::Active.to_string(),
                 "updatedAt": now,
+                "lastSeenAt": now,
             },
             "$setOnInsert": {
                 "createdAt": now,
@@ -100,4 +147,97 @@ impl UserFcmToken {
             Error::not_found(&msg)
         })
     }
+
+    /// Refreshes `lastSeenAt` of an active device; inactive devices must
+    /// register again.
+    pub async fn touch_last_seen(user_id: &str, device_id: &str) -> Result<Self, Error> {
+        let now = DateTime::now();
+
+        let query = doc! {
+            "deviceId": device_id,
+            "userId": user_id,
+            "status": UserFcmTokenStatus::Active.to_string(),
+        };
+
+        let update = doc! {
+            "$set": {
+                "lastSeenAt": now,
+            }
+        };
+
+        let result = <Self as ModelExt>::find_one_and_update(query, update, false).await?;
+
+        result.ok_or_else(|| {
+            let msg = format!(
+                "No active device found for user_id={} and device_id={}",
+                user_id, device_id
+            );
+            Error::not_found(&msg)
+        })
+    }
+
+    /// Deactivates every active token not seen since `cutoff` and returns the
+    /// deactivated records.
+    pub async fn deactivate_idle_since(cutoff: DateTime) -> Result<Vec<Self>, Error> {
+        let query = doc! {
+            "status": UserFcmTokenStatus::Active.to_string(),
+            "$or": [
+                { "lastSeenAt": { "$lt": cutoff } },
+                { "lastSeenAt": { "$exists": false }, "updatedAt": { "$lt": cutoff } },
+            ],
+        };
+
+        let update = doc! {
+            "$set": {
+                "status": UserFcmTokenStatus::Inactive.to_string(),
+                "updatedAt": DateTime::now(),
+            }
+        };
+
+        let candidates = <Self as ModelExt>::find(query.clone(), None).await?;
+        let mut deactivated = Vec::with_capacity(candidates.len());
+
+        // Re-check the idle condition per record so a heartbeat that lands
+        // mid-sweep keeps its device active.
+        for candidate in candidates {
+            let Some(id) = candidate.id else {
+                continue;
+            };
+
+            let mut filter = query.clone();
+            filter.insert("_id", id);
+
+            if let Some(token) =
+                <Self as ModelExt>::find_one_and_update(filter, update.clone(), false).await?
+            {
+                deactivated.push(token);
+            }
+        }
+
+        Ok(deactivated)
+    }
+
+    /// Deactivates `token` on the devices that registered it with `provider`.
+    pub async fn deactivate_by_token(token: &str, provider: TokenProvider) -> Result<u64, Error> {
+        let providers = match provider {
+            TokenProvider::Fcm => vec![Bson::String(provider.to_string()), Bson::Null],
+            TokenProvider::Apns => vec![Bson::String(provider.to_string())],
+        };
+        let query = doc! {
+            "token": token,
+            "status": UserFcmTokenStatus::Active.to_string(),
+            "provider": { "$in": providers },
+        };
+
+        let update = doc! {
+            "$set": {
+                "status": UserFcmTokenStatus::Inactive.to_string(),
+                "updatedAt": DateTime::now(),
+            }
+        };
+
+        let result = <Self as ModelExt>::update_many(query, update, None).await?;
+
+        Ok(result.modified_count)
+    }
 }
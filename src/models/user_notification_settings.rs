@@ -1,6 +1,9 @@
 use crate::database;
+use crate::models::accounts::ELanguage;
 use crate::utils::models::ModelExt;
+use crate::utils::structs::default_price_alert;
 use async_trait::async_trait;
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 use wither::Model as WitherModel;
@@ -25,6 +28,12 @@ pub struct UserNotificationSetting {
     pub announcement: bool,
     pub campaign: bool,
     pub transaction: bool,
+    #[serde(default = "default_price_alert")]
+    pub price_alert: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<ELanguage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<Tz>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
@@ -1,12 +1,17 @@
 use crate::database;
+use crate::errors::Error;
 use crate::utils::models::ModelExt;
+use crate::utils::structs::NotifMetadata;
 use async_trait::async_trait;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use validator::Validate;
 use wither::Model as WitherModel;
 use wither::bson::DateTime;
-use wither::bson::{doc, oid::ObjectId};
+use wither::bson::{Bson, doc, oid::ObjectId};
 use wither::mongodb::Database;
+use wither::mongodb::options::{FindOptions, UpdateOptions};
 
 #[async_trait]
 impl ModelExt for UserNotification {
@@ -27,4 +32,217 @@ pub struct UserNotification {
     pub created_at: DateTime,
     pub updated_at: DateTime,
     pub is_read: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<NotificationMetadata>,
+    /// Identifies the event the notification was created from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_event_id: Option<String>,
+}
+
+/// Details of the event a notification was created from, so clients can show
+/// them and link to the order or transaction.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum NotificationMetadata {
+    Order {
+        order_id: u64,
+        status: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        symbol: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        side: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        order_type: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<String>)]
+        price: Option<Decimal>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<String>)]
+        quantity: Option<Decimal>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<String>)]
+        filled_quantity: Option<Decimal>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<String>)]
+        average_price: Option<Decimal>,
+    },
+    Transaction {
+        transaction_id: u64,
+        asset: String,
+        network_id: String,
+        tx_hash: String,
+        /// `ADD`, `REMOVE`, `BUY` or `SELL`.
+        trading_type: String,
+        amount: String,
+        status: String,
+    },
+    Account {
+        /// `Category:Action`, e.g. `Password:Change`.
+        activity: String,
+        status: String,
+    },
+    PriceAlert {
+        alert_id: String,
+        symbol: String,
+        /// `ABOVE`, `BELOW` or `PERCENT_CHANGE`.
+        condition: String,
+        #[schema(value_type = String)]
+        threshold: Decimal,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<String>)]
+        percent: Option<Decimal>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<String>)]
+        reference_price: Option<Decimal>,
+        #[schema(value_type = String)]
+        price: Decimal,
+    },
+    Announcement {
+        announcement_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        image_url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        link: Option<String>,
+    },
+    Campaign {
+        campaign_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        image_url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        link: Option<String>,
+    },
+}
+
+impl From<&NotifMetadata> for NotificationMetadata {
+    fn from(metadata: &NotifMetadata) -> Self {
+        match metadata {
+            NotifMetadata::Order(order_data) => NotificationMetadata::Order {
+                order_id: order_data.order_id,
+                status: order_data.status.clone(),
+                symbol: order_data.symbol.clone(),
+                side: order_data.side.clone(),
+                order_type: order_data.order_type.clone(),
+                price: order_data.price,
+                quantity: order_data.quantity,
+                filled_quantity: order_data.filled_quantity,
+                average_price: order_data.average_price,
+            },
+            NotifMetadata::Transaction(transaction_data) => NotificationMetadata::Transaction {
+                transaction_id: transaction_data.id,
+                asset: transaction_data.asset.clone(),
+                network_id: transaction_data.network_id.clone(),
+                tx_hash: transaction_data.tx_hash.clone(),
+                trading_type: transaction_data.r#type.to_string(),
+                amount: transaction_data.amount.clone(),
+                status: transaction_data.status.clone(),
+            },
+            NotifMetadata::Account(account_data) => NotificationMetadata::Account {
+                activity: account_data.activity_type.config_key(),
+                status: format!("{:?}", account_data.action_status),
+            },
+            NotifMetadata::PriceAlert(price_alert_data) => NotificationMetadata::PriceAlert {
+                alert_id: price_alert_data.alert_id.clone(),
+                symbol: price_alert_data.symbol.clone(),
+                condition: price_alert_data.condition.to_string(),
+                threshold: price_alert_data.threshold,
+                percent: price_alert_data.percent,
+                reference_price: price_alert_data.reference_price,
+                price: price_alert_data.price,
+            },
+            NotifMetadata::Announcement(announcement_data) => NotificationMetadata::Announcement {
+                announcement_id: announcement_data.announcement_id.clone(),
+                image_url: announcement_data.image_url.clone(),
+                link: announcement_data.link.clone(),
+            },
+            NotifMetadata::Campaign(campaign_data) => NotificationMetadata::Campaign {
+                campaign_id: campaign_data.campaign_id.clone(),
+                image_url: campaign_data.image_url.clone(),
+                link: campaign_data.link.clone(),
+            },
+        }
+    }
+}
+
+impl UserNotification {
+    /// Keeps one notification per user and source event. Older notifications
+    /// without a source event id are not covered.
+    pub async fn create_indexes() -> Result<(), Error> {
+        <Self as ModelExt>::create_unique_index(
+            doc! { "userId": 1, "sourceEventId": 1 },
+            Some(doc! { "sourceEventId": { "$type": "string" } }),
+        )
+        .await
+    }
+
+    /// Stores the notification unless the user already has one created from
+    /// the same source event, e.g. when a batch is consumed again. Returns it
+    /// with its id when it was stored.
+    pub async fn create_once(mut notification: Self) -> Result<Option<Self>, Error> {
+        let Some(source_event_id) = notification.source_event_id.clone() else {
+            return <Self as ModelExt>::create(notification).await.map(Some);
+        };
+
+        notification
+            .validate()
+            .map_err(|e| Error::bad_request(&format!("Validation db error: {e:?}")))?;
+        let document = notification
+            .document_from_instance()
+            .map_err(Error::Wither)?;
+
+        let result = <Self as ModelExt>::update_one(
+            doc! { "userId": notification.user_id.as_str(), "sourceEventId": source_event_id },
+            doc! { "$setOnInsert": document },
+            UpdateOptions::builder().upsert(true).build(),
+        )
+        .await?;
+
+        match result.upserted_id {
+            Some(Bson::ObjectId(id)) => {
+                notification.id = Some(id);
+                Ok(Some(notification))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub async fn count_unread(user_id: &str, notif_type: &str) -> Result<u64, Error> {
+        <Self as ModelExt>::count(doc! { "userId": user_id, "type": notif_type, "isRead": false })
+            .await
+    }
+
+    /// Users among `user_ids` that already have a notification created from
+    /// `source_event_id`.
+    pub async fn find_user_ids_by_source_event_id(
+        source_event_id: &str,
+        user_ids: &[String],
+    ) -> Result<Vec<String>, Error> {
+        let notifications = <Self as ModelExt>::find(
+            doc! { "sourceEventId": source_event_id, "userId": { "$in": user_ids } },
+            None,
+        )
+        .await?;
+
+        Ok(notifications
+            .into_iter()
+            .map(|notification| notification.user_id)
+            .collect())
+    }
+
+    /// Notifications of `user_id` stored after `after`, oldest first.
+    pub async fn find_after(
+        user_id: &str,
+        after: ObjectId,
+        limit: i64,
+    ) -> Result<Vec<Self>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! { "_id": 1 })
+            .limit(limit)
+            .build();
+
+        <Self as ModelExt>::find(doc! { "userId": user_id, "_id": { "$gt": after } }, options).await
+    }
 }
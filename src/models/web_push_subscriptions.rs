@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{doc, oid::ObjectId};
+use wither::mongodb::Database;
+use wither::mongodb::options::FindOptions;
+
+use crate::database;
+use crate::enums::UserFcmTokenStatus;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for WebPushSubscription {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// A browser `PushSubscription`, the Web Push counterpart of `UserFcmToken`.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct WebPushSubscription {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: String,
+    pub endpoint: String,
+    /// Browser public key, base64url encoded.
+    pub p256dh: String,
+    /// Browser auth secret, base64url encoded.
+    pub auth: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    pub status: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl WebPushSubscription {
+    pub async fn find_by_user_id(user_id: &str) -> Result<Vec<Self>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! { "updatedAt": -1 })
+            .build();
+
+        <Self as ModelExt>::find(doc! { "userId": user_id }, Some(options)).await
+    }
+
+    pub async fn find_active_by_user_id(user_id: &str) -> Result<Vec<Self>, Error> {
+        let query = doc! {
+            "userId": user_id,
+            "status": UserFcmTokenStatus::Active.to_string(),
+        };
+
+        <Self as ModelExt>::find(query, None).await
+    }
+
+    /// Subscriptions are keyed by endpoint, so re-subscribing the same browser
+    /// refreshes its keys and owner instead of adding a duplicate.
+    pub async fn create_or_update(
+        user_id: &str,
+        endpoint: &str,
+        p256dh: &str,
+        auth: &str,
+        user_agent: Option<String>,
+    ) -> Result<Self, Error> {
+        let now = DateTime::now();
+
+        let update = doc! {
+            "$set": {
+                "userId": user_id,
+                "p256dh": p256dh,
+                "auth": auth,
+                "userAgent": user_agent,
+                "status": UserFcmTokenStatus::Active.to_string(),
+                "updatedAt": now,
+            },
+            "$setOnInsert": {
+                "createdAt": now,
+            }
+        };
+
+        <Self as ModelExt>::find_one_and_update(doc! { "endpoint": endpoint }, update, true)
+            .await?
+            .ok_or_else(|| {
+                Error::internal_err(&format!(
+                    "Failed to save web push subscription for user_id={user_id}"
+                ))
+            })
+    }
+
+    pub async fn deactivate_by_user_id_and_id(user_id: &str, id: ObjectId) -> Result<Self, Error> {
+        let update = doc! {
+            "$set": {
+                "status": UserFcmTokenStatus::Inactive.to_string(),
+                "updatedAt": DateTime::now(),
+            }
+        };
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id, "userId": user_id },
+            update,
+            false,
+        )
+        .await?
+        .ok_or_else(|| Error::not_found("Web push subscription not found"))
+    }
+
+    pub async fn deactivate_by_endpoint(endpoint: &str) -> Result<u64, Error> {
+        let update = doc! {
+            "$set": {
+                "status": UserFcmTokenStatus::Inactive.to_string(),
+                "updatedAt": DateTime::now(),
+            }
+        };
+
+        let result = <Self as ModelExt>::update_many(
+            doc! {
+                "endpoint": endpoint,
+                "status": UserFcmTokenStatus::Active.to_string(),
+            },
+            update,
+            None,
+        )
+        .await?;
+
+        Ok(result.modified_count)
+    }
+}
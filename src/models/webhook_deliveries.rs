@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::oid::ObjectId;
+use wither::mongodb::Database;
+
+use crate::database;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for WebhookDelivery {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// One attempt to deliver an event to a webhook subscription.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub subscription_id: ObjectId,
+    pub user_id: String,
+    pub event_id: String,
+    pub event_type: String,
+    pub attempt: i32,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
@@ -0,0 +1,171 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{Document, doc, oid::ObjectId};
+use wither::mongodb::Database;
+use wither::mongodb::options::UpdateOptions;
+
+use crate::database;
+use crate::enums::WebhookJobStatus;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for WebhookJob {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+/// An event queued for delivery to one webhook subscription.
+///
+/// The publisher only enqueues jobs; the webhook worker claims due jobs, makes
+/// one attempt per claim and reschedules failed ones. A claim increments
+/// `attempts` and leases the job until `leaseExpiresAt`, and every write that
+/// finishes an attempt is conditioned on that attempt number, so a worker
+/// whose lease ran out cannot overwrite the outcome of the next attempt.
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookJob {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub subscription_id: ObjectId,
+    pub user_id: String,
+    /// Sent as `X-Webhook-Id`; unique per subscription.
+    pub event_id: String,
+    pub event_type: String,
+    /// Serialized event, signed anew on every attempt.
+    pub body: String,
+    /// `WebhookJobStatus` value, e.g. `PENDING`.
+    pub status: String,
+    #[serde(default)]
+    pub attempts: i32,
+    pub next_attempt_at: DateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lease_expires_at: Option<DateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl WebhookJob {
+    /// Makes replays of the same event enqueue it only once.
+    pub async fn create_indexes() -> Result<(), Error> {
+        <Self as ModelExt>::create_unique_index(doc! { "subscriptionId": 1, "eventId": 1 }, None)
+            .await
+    }
+
+    /// Queues an event for immediate delivery, unless it was queued before.
+    pub async fn enqueue(
+        subscription_id: ObjectId,
+        user_id: &str,
+        event_id: &str,
+        event_type: &str,
+        body: &str,
+    ) -> Result<(), Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::update_one(
+            doc! { "subscriptionId": subscription_id, "eventId": event_id },
+            doc! {
+                "$setOnInsert": {
+                    "userId": user_id,
+                    "eventType": event_type,
+                    "body": body,
+                    "status": WebhookJobStatus::Pending.to_string(),
+                    "attempts": 0,
+                    "nextAttemptAt": now,
+                    "createdAt": now,
+                    "updatedAt": now,
+                }
+            },
+            UpdateOptions::builder().upsert(true).build(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Claims a due pending job for one attempt, leasing it for `lease_secs`.
+    pub async fn claim_due(lease_secs: i64) -> Result<Option<Self>, Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! {
+                "status": WebhookJobStatus::Pending.to_string(),
+                "nextAttemptAt": { "$lte": now },
+                "$or": [
+                    { "leaseExpiresAt": null },
+                    { "leaseExpiresAt": { "$lt": now } },
+                ],
+            },
+            doc! {
+                "$set": {
+                    "leaseExpiresAt": DateTime::from_millis(
+                        now.timestamp_millis() + lease_secs * 1000
+                    ),
+                    "updatedAt": now,
+                },
+                "$inc": { "attempts": 1 },
+            },
+            false,
+        )
+        .await
+    }
+
+    fn attempt_filter(&self) -> Result<Document, Error> {
+        let id = self
+            .id
+            .ok_or_else(|| Error::internal_err("Webhook job has no id"))?;
+
+        Ok(doc! { "_id": id, "attempts": self.attempts })
+    }
+
+    pub async fn mark_delivered(&self) -> Result<(), Error> {
+        self.finish_attempt(doc! {
+            "$set": {
+                "status": WebhookJobStatus::Delivered.to_string(),
+                "updatedAt": DateTime::now(),
+            },
+            "$unset": { "leaseExpiresAt": "", "lastError": "" },
+        })
+        .await
+    }
+
+    pub async fn schedule_retry(
+        &self,
+        next_attempt_at: DateTime,
+        error: &str,
+    ) -> Result<(), Error> {
+        self.finish_attempt(doc! {
+            "$set": {
+                "nextAttemptAt": next_attempt_at,
+                "lastError": error,
+                "updatedAt": DateTime::now(),
+            },
+            "$unset": { "leaseExpiresAt": "" },
+        })
+        .await
+    }
+
+    pub async fn mark_failed(&self, error: &str) -> Result<(), Error> {
+        self.finish_attempt(doc! {
+            "$set": {
+                "status": WebhookJobStatus::Failed.to_string(),
+                "lastError": error,
+                "updatedAt": DateTime::now(),
+            },
+            "$unset": { "leaseExpiresAt": "" },
+        })
+        .await
+    }
+
+    async fn finish_attempt(&self, update: Document) -> Result<(), Error> {
+        <Self as ModelExt>::update_one(self.attempt_filter()?, update, None).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use wither::Model as WitherModel;
+use wither::bson::DateTime;
+use wither::bson::{Document, doc, oid::ObjectId};
+use wither::mongodb::Database;
+use wither::mongodb::options::FindOptions;
+
+use crate::database;
+use crate::enums::WebhookStatus;
+use crate::errors::Error;
+use crate::utils::models::ModelExt;
+
+#[async_trait]
+impl ModelExt for WebhookSubscription {
+    async fn get_connection() -> &'static Database {
+        database::connection().await
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, WitherModel, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookSubscription {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub user_id: String,
+    #[validate(url)]
+    pub url: String,
+    /// Shared secret used to sign every delivery.
+    pub secret: String,
+    /// `NotifType` values the endpoint receives, e.g. `ORDER`.
+    pub event_types: Vec<String>,
+    pub status: String,
+    pub consecutive_failures: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disabled_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_delivery_at: Option<DateTime>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl WebhookSubscription {
+    pub fn is_active(&self) -> bool {
+        self.status == WebhookStatus::Active.to_string()
+    }
+
+    pub async fn find_by_user_id(user_id: &str) -> Result<Vec<Self>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! { "createdAt": -1 })
+            .build();
+
+        <Self as ModelExt>::find(doc! { "userId": user_id }, Some(options)).await
+    }
+
+    pub async fn find_by_user_id_and_id(user_id: &str, id: ObjectId) -> Result<Self, Error> {
+        <Self as ModelExt>::find_one(doc! { "_id": id, "userId": user_id }, None)
+            .await?
+            .ok_or_else(|| Error::not_found("Webhook not found"))
+    }
+
+    pub async fn find_active_by_user_id_and_type(
+        user_id: &str,
+        event_type: &str,
+    ) -> Result<Vec<Self>, Error> {
+        let query = doc! {
+            "userId": user_id,
+            "eventTypes": event_type,
+            "status": WebhookStatus::Active.to_string(),
+        };
+
+        <Self as ModelExt>::find(query, None).await
+    }
+
+    pub async fn count_by_user_id(user_id: &str) -> Result<u64, Error> {
+        <Self as ModelExt>::count(doc! { "userId": user_id }).await
+    }
+
+    pub async fn update_by_user_id_and_id(
+        user_id: &str,
+        id: ObjectId,
+        mut set: Document,
+    ) -> Result<Self, Error> {
+        set.insert("updatedAt", DateTime::now());
+
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id, "userId": user_id },
+            doc! { "$set": set },
+            false,
+        )
+        .await?
+        .ok_or_else(|| Error::not_found("Webhook not found"))
+    }
+
+    pub async fn delete_by_user_id_and_id(user_id: &str, id: ObjectId) -> Result<(), Error> {
+        let result = <Self as ModelExt>::delete_one(doc! { "_id": id, "userId": user_id }).await?;
+
+        if result.deleted_count == 0 {
+            return Err(Error::not_found("Webhook not found"));
+        }
+
+        Ok(())
+    }
+
+    pub async fn record_success(id: ObjectId) -> Result<(), Error> {
+        let now = DateTime::now();
+
+        <Self as ModelExt>::update_one(
+            doc! { "_id": id },
+            doc! {
+                "$set": {
+                    "consecutiveFailures": 0,
+                    "lastDeliveryAt": now,
+                    "updatedAt": now,
+                }
+            },
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Counts a failed delivery and disables the subscription once it has failed
+    /// `max_failures` times in a row. Returns the updated subscription.
+    pub async fn record_failure(id: ObjectId, max_failures: i32) -> Result<Option<Self>, Error> {
+        let now = DateTime::now();
+
+        let subscription = <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id },
+            doc! {
+                "$inc": { "consecutiveFailures": 1 },
+                "$set": { "lastDeliveryAt": now, "updatedAt": now },
+            },
+            false,
+        )
+        .await?;
+
+        match subscription {
+            Some(subscription) if subscription.consecutive_failures >= max_failures => {
+                let reason = format!("Disabled after {max_failures} consecutive failed deliveries");
+                Self::disable(id, &reason).await
+            }
+            other => Ok(other),
+        }
+    }
+
+    pub async fn disable(id: ObjectId, reason: &str) -> Result<Option<Self>, Error> {
+        <Self as ModelExt>::find_one_and_update(
+            doc! { "_id": id, "status": WebhookStatus::Active.to_string() },
+            doc! {
+                "$set": {
+                    "status": WebhookStatus::Disabled.to_string(),
+                    "disabledReason": reason,
+                    "updatedAt": DateTime::now(),
+                }
+            },
+            false,
+        )
+        .await
+    }
+}
@@ -0,0 +1,259 @@
+use crate::enums::{AnnouncementStatus, CampaignStatus};
+use crate::models::announcements::Announcement;
+use crate::models::campaigns::Campaign;
+use crate::models::notification_templates::NotificationTemplate;
+use crate::utils::segment::AudienceSegment;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateNotificationTemplateRequestDto {
+    /// Notification type, e.g. `ORDER`.
+    pub kind: String,
+    /// Status or action, e.g. `FILLED`, `COMPLETED:ADD`, `Password:Reset:Success`,
+    /// `*` for every event of the kind, or `UNSENT_SUMMARY` for the summary
+    /// sent after rate limited notifications, which gets `count`.
+    #[validate(length(min = 1, max = 64))]
+    pub variant: String,
+    #[validate(length(min = 2, max = 10))]
+    pub locale: String,
+    #[validate(length(min = 1, max = 256))]
+    pub title: String,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNotificationTemplateRequestDto {
+    #[validate(length(min = 1, max = 256))]
+    pub title: Option<String>,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationTemplateDto {
+    pub id: String,
+    pub kind: String,
+    pub variant: String,
+    pub locale: String,
+    pub title: String,
+    pub body: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<NotificationTemplate> for NotificationTemplateDto {
+    fn from(template: NotificationTemplate) -> Self {
+        Self {
+            id: template.id.map(|id| id.to_hex()).unwrap_or_default(),
+            kind: template.kind,
+            variant: template.variant,
+            locale: template.locale,
+            title: template.title,
+            body: template.body,
+            created_at: template.created_at.to_string(),
+            updated_at: template.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewNotificationTemplateRequestDto {
+    pub title: String,
+    pub body: String,
+    /// Event metadata as carried on Kafka, e.g.
+    /// `{"Order": {"order_id": 1, "status": "FILLED"}}`.
+    #[schema(value_type = Object)]
+    pub metadata: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewNotificationTemplateResponseDto {
+    pub title: String,
+    pub body: String,
+    /// Variables the metadata provides to templates.
+    #[schema(value_type = Object)]
+    pub variables: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAnnouncementRequestDto {
+    #[validate(length(min = 1, max = 256))]
+    pub title: String,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: String,
+    #[validate(url, length(max = 2048))]
+    pub image_url: Option<String>,
+    /// App path the notification opens, e.g. `markets/SUIUSDT`.
+    #[validate(length(min = 1, max = 512))]
+    pub link: Option<String>,
+    /// Users to reach, everyone when omitted.
+    pub segment: Option<AudienceSegment>,
+    /// When to start sending, right away when omitted.
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAnnouncementRequestDto {
+    #[validate(length(min = 1, max = 256))]
+    pub title: Option<String>,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: Option<String>,
+    #[validate(url, length(max = 2048))]
+    pub image_url: Option<String>,
+    #[validate(length(min = 1, max = 512))]
+    pub link: Option<String>,
+    pub segment: Option<AudienceSegment>,
+    pub scheduled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementDto {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    pub image_url: Option<String>,
+    pub link: Option<String>,
+    pub segment: AudienceSegment,
+    pub status: AnnouncementStatus,
+    pub scheduled_at: String,
+    pub created_by: String,
+    pub total_recipients: i64,
+    pub processed: i64,
+    pub delivered: i64,
+    pub skipped: i64,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Announcement> for AnnouncementDto {
+    fn from(announcement: Announcement) -> Self {
+        Self {
+            id: announcement.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: AnnouncementStatus::from_str(&announcement.status)
+                .unwrap_or(AnnouncementStatus::Failed),
+            title: announcement.title,
+            body: announcement.body,
+            image_url: announcement.image_url,
+            link: announcement.link,
+            segment: announcement.segment,
+            scheduled_at: announcement.scheduled_at.to_string(),
+            created_by: announcement.created_by,
+            total_recipients: announcement.total_recipients,
+            processed: announcement.processed,
+            delivered: announcement.delivered,
+            skipped: announcement.skipped,
+            started_at: announcement.started_at.map(|at| at.to_string()),
+            finished_at: announcement.finished_at.map(|at| at.to_string()),
+            error: announcement.error,
+            created_at: announcement.created_at.to_string(),
+            updated_at: announcement.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCampaignRequestDto {
+    /// Internal name, not shown to users.
+    #[validate(length(min = 1, max = 128))]
+    pub name: String,
+    #[validate(length(min = 1, max = 256))]
+    pub title: String,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: String,
+    #[validate(url, length(max = 2048))]
+    pub image_url: Option<String>,
+    /// App path the notification opens, e.g. `promotions/zero-fee`.
+    #[validate(length(min = 1, max = 512))]
+    pub link: Option<String>,
+    /// Users to reach, everyone when omitted.
+    pub segment: Option<AudienceSegment>,
+    pub starts_at: DateTime<Utc>,
+    /// Users not reached by then are not sent the campaign anymore.
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCampaignRequestDto {
+    #[validate(length(min = 1, max = 128))]
+    pub name: Option<String>,
+    #[validate(length(min = 1, max = 256))]
+    pub title: Option<String>,
+    #[validate(length(min = 1, max = 4096))]
+    pub body: Option<String>,
+    #[validate(url, length(max = 2048))]
+    pub image_url: Option<String>,
+    #[validate(length(min = 1, max = 512))]
+    pub link: Option<String>,
+    pub segment: Option<AudienceSegment>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CampaignDto {
+    pub id: String,
+    pub name: String,
+    pub title: String,
+    pub body: String,
+    pub image_url: Option<String>,
+    pub link: Option<String>,
+    pub segment: AudienceSegment,
+    pub status: CampaignStatus,
+    pub starts_at: String,
+    pub ends_at: Option<String>,
+    pub created_by: String,
+    /// Scheduler replica running the campaign.
+    pub lease_owner: Option<String>,
+    pub total_recipients: i64,
+    pub published: i64,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Campaign> for CampaignDto {
+    fn from(campaign: Campaign) -> Self {
+        Self {
+            id: campaign.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: CampaignStatus::from_str(&campaign.status).unwrap_or(CampaignStatus::Failed),
+            name: campaign.name,
+            title: campaign.title,
+            body: campaign.body,
+            image_url: campaign.image_url,
+            link: campaign.link,
+            segment: campaign.segment,
+            starts_at: campaign.starts_at.to_string(),
+            ends_at: campaign.ends_at.map(|at| at.to_string()),
+            created_by: campaign.created_by,
+            lease_owner: campaign.lease_owner,
+            total_recipients: campaign.total_recipients,
+            published: campaign.published,
+            started_at: campaign.started_at.map(|at| at.to_string()),
+            finished_at: campaign.finished_at.map(|at| at.to_string()),
+            error: campaign.error,
+            created_at: campaign.created_at.to_string(),
+            updated_at: campaign.updated_at.to_string(),
+        }
+    }
+}
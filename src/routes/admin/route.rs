@@ -0,0 +1,793 @@
+use std::str::FromStr;
+
+use axum::{
+    Json,
+    extract::{Path, Query},
+};
+use chrono::Utc;
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_axum::routes;
+use validator::Validate;
+use wither::bson::{DateTime, doc, oid::ObjectId};
+use wither::mongodb::options::FindOptions;
+
+use crate::app_state::AppState;
+use crate::core::admin_auth::admin_auth::AdminAuth;
+use crate::enums::{AnnouncementStatus, CampaignStatus};
+use crate::errors::Error;
+use crate::loading_templates::{publish_templates_updated, render_template, validate_template};
+use crate::models::accounts::ELanguage;
+use crate::models::announcements::Announcement;
+use crate::models::campaigns::Campaign;
+use crate::models::notification_templates::NotificationTemplate;
+use crate::routes::admin::dto::{
+    AnnouncementDto, CampaignDto, CreateAnnouncementRequestDto, CreateCampaignRequestDto,
+    CreateNotificationTemplateRequestDto, NotificationTemplateDto,
+    PreviewNotificationTemplateRequestDto, PreviewNotificationTemplateResponseDto,
+    UpdateAnnouncementRequestDto, UpdateCampaignRequestDto, UpdateNotificationTemplateRequestDto,
+};
+use crate::utils::models::ModelExt;
+use crate::utils::pagination::{PaginationQuery, PaginationResponseDto};
+use crate::utils::structs::{NotifMetadata, NotifType, format_event_time};
+
+pub fn create_route() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(get_templates, create_template))
+        .routes(routes!(get_template, update_template, delete_template))
+        .routes(routes!(preview_template))
+        .routes(routes!(get_announcements, create_announcement))
+        .routes(routes!(get_announcement, update_announcement))
+        .routes(routes!(cancel_announcement))
+        .routes(routes!(get_campaigns, create_campaign))
+        .routes(routes!(get_campaign, update_campaign))
+        .routes(routes!(cancel_campaign))
+}
+
+#[utoipa::path(
+    get,
+    path = "/templates",
+    tag = "Admin APIs",
+    responses(
+        (status = 200, description = "All notification templates", body = Vec<NotificationTemplateDto>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn get_templates(_: AdminAuth) -> Result<Json<Vec<NotificationTemplateDto>>, Error> {
+    let templates = NotificationTemplate::find_all().await?;
+
+    Ok(Json(
+        templates
+            .into_iter()
+            .map(NotificationTemplateDto::from)
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/templates",
+    tag = "Admin APIs",
+    request_body(
+        content = CreateNotificationTemplateRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Template created successfully", body = NotificationTemplateDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn create_template(
+    AdminAuth { username }: AdminAuth,
+    Json(request): Json<CreateNotificationTemplateRequestDto>,
+) -> Result<Json<NotificationTemplateDto>, Error> {
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid template: {e}")))?;
+    let kind = NotifType::from_str(&request.kind)
+        .map_err(|_| Error::bad_request(&format!("Unknown notification type {}", request.kind)))?
+        .to_string();
+    check_template_syntax(&request.title, &request.body)?;
+
+    if NotificationTemplate::find_by_key(&kind, &request.variant, &request.locale)
+        .await?
+        .is_some()
+    {
+        return Err(Error::bad_request(
+            "A template for this kind, variant and locale already exists",
+        ));
+    }
+
+    let now = DateTime::now();
+    let template = NotificationTemplate::create(NotificationTemplate {
+        id: None,
+        kind,
+        variant: request.variant,
+        locale: request.locale,
+        title: request.title,
+        body: request.body,
+        created_at: now,
+        updated_at: now,
+    })
+    .await?;
+
+    tracing::info!(
+        "Admin {username} created {} template {} ({})",
+        template.kind,
+        template.variant,
+        template.locale
+    );
+    broadcast_templates_updated(&template);
+
+    Ok(Json(NotificationTemplateDto::from(template)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/templates/{id}",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 200, description = "Notification template", body = NotificationTemplateDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn get_template(
+    _: AdminAuth,
+    Path(id): Path<String>,
+) -> Result<Json<NotificationTemplateDto>, Error> {
+    let oid = parse_template_id(&id)?;
+    let template = NotificationTemplate::find_by_id(&oid)
+        .await?
+        .ok_or_else(|| Error::not_found("Template not found"))?;
+
+    Ok(Json(NotificationTemplateDto::from(template)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/templates/{id}",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Template ID")
+    ),
+    request_body(
+        content = UpdateNotificationTemplateRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Template updated successfully", body = NotificationTemplateDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn update_template(
+    AdminAuth { username }: AdminAuth,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateNotificationTemplateRequestDto>,
+) -> Result<Json<NotificationTemplateDto>, Error> {
+    let oid = parse_template_id(&id)?;
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid template: {e}")))?;
+
+    let mut set = doc! {};
+    if let Some(title) = &request.title {
+        check_template_syntax(title, "")?;
+        set.insert("title", title);
+    }
+    if let Some(body) = &request.body {
+        check_template_syntax("", body)?;
+        set.insert("body", body);
+    }
+
+    let template = NotificationTemplate::update_by_id(oid, set).await?;
+
+    tracing::info!(
+        "Admin {username} updated {} template {} ({})",
+        template.kind,
+        template.variant,
+        template.locale
+    );
+    broadcast_templates_updated(&template);
+
+    Ok(Json(NotificationTemplateDto::from(template)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/templates/{id}",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Template ID")
+    ),
+    responses(
+        (status = 200, description = "Template deleted, the built-in text applies again"),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn delete_template(
+    AdminAuth { username }: AdminAuth,
+    Path(id): Path<String>,
+) -> Result<Json<()>, Error> {
+    let oid = parse_template_id(&id)?;
+    NotificationTemplate::delete_by_id(oid).await?;
+
+    tracing::info!("Admin {username} deleted template {id}");
+    if let Err(e) = publish_templates_updated(id) {
+        tracing::warn!("{e}");
+    }
+
+    Ok(Json(()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/templates/preview",
+    tag = "Admin APIs",
+    request_body(
+        content = PreviewNotificationTemplateRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Template rendered with the given metadata", body = PreviewNotificationTemplateResponseDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn preview_template(
+    _: AdminAuth,
+    Json(request): Json<PreviewNotificationTemplateRequestDto>,
+) -> Result<Json<PreviewNotificationTemplateResponseDto>, Error> {
+    let metadata: NotifMetadata = serde_json::from_value(request.metadata)
+        .map_err(|e| Error::bad_request(&format!("Invalid metadata: {e}")))?;
+    let time = format_event_time(Utc::now().timestamp_millis(), None);
+    let variables = metadata.template_context(ELanguage::default(), &time);
+
+    let rendered = render_template(&request.title, &request.body, &variables)
+        .map_err(|e| Error::bad_request(&format!("Failed to render template: {e:#}")))?;
+
+    Ok(Json(PreviewNotificationTemplateResponseDto {
+        title: rendered.title,
+        body: rendered.body,
+        variables,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/announcements",
+    tag = "Admin APIs",
+    params(
+        PaginationQuery
+    ),
+    responses(
+        (status = 200, description = "Announcements, most recent first", body = PaginationResponseDto<AnnouncementDto>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn get_announcements(
+    _: AdminAuth,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<Json<PaginationResponseDto<AnnouncementDto>>, Error> {
+    let options = FindOptions::builder()
+        .sort(doc! { "createdAt": -1 })
+        .skip(pagination.skip() as u64)
+        .limit(pagination.limit() as i64)
+        .build();
+
+    let (announcements, total) = Announcement::find_and_count(doc! {}, options).await?;
+    let total_pages = (total as f64 / pagination.limit() as f64).ceil() as u32;
+
+    Ok(Json(PaginationResponseDto {
+        docs: announcements
+            .into_iter()
+            .map(AnnouncementDto::from)
+            .collect(),
+        page: pagination.page(),
+        limit: pagination.limit(),
+        total_docs: total as u32,
+        total_pages,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/announcements",
+    tag = "Admin APIs",
+    request_body(
+        content = CreateAnnouncementRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Announcement scheduled", body = AnnouncementDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn create_announcement(
+    AdminAuth { username }: AdminAuth,
+    Json(request): Json<CreateAnnouncementRequestDto>,
+) -> Result<Json<AnnouncementDto>, Error> {
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid announcement: {e}")))?;
+
+    let now = DateTime::now();
+    let announcement = Announcement::create(Announcement {
+        id: None,
+        title: request.title,
+        body: request.body,
+        image_url: request.image_url,
+        link: request.link,
+        segment: request.segment.unwrap_or_default(),
+        status: AnnouncementStatus::Scheduled.to_string(),
+        scheduled_at: request
+            .scheduled_at
+            .map(DateTime::from_chrono)
+            .unwrap_or(now),
+        created_by: username.clone(),
+        cursor: None,
+        total_recipients: 0,
+        processed: 0,
+        delivered: 0,
+        skipped: 0,
+        started_at: None,
+        finished_at: None,
+        error: None,
+        created_at: now,
+        updated_at: now,
+    })
+    .await?;
+
+    tracing::info!(
+        "Admin {username} scheduled announcement {} for {}",
+        announcement.source_event_id(),
+        announcement.scheduled_at
+    );
+
+    Ok(Json(AnnouncementDto::from(announcement)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/announcements/{id}",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Announcement ID")
+    ),
+    responses(
+        (status = 200, description = "Announcement with its fan-out progress", body = AnnouncementDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Announcement not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn get_announcement(
+    _: AdminAuth,
+    Path(id): Path<String>,
+) -> Result<Json<AnnouncementDto>, Error> {
+    let oid = parse_announcement_id(&id)?;
+    let announcement = Announcement::find_by_id(&oid)
+        .await?
+        .ok_or_else(|| Error::not_found("Announcement not found"))?;
+
+    Ok(Json(AnnouncementDto::from(announcement)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/announcements/{id}",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Announcement ID")
+    ),
+    request_body(
+        content = UpdateAnnouncementRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Announcement updated successfully", body = AnnouncementDto),
+        (status = 400, description = "Bad request, or the announcement already started"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn update_announcement(
+    AdminAuth { username }: AdminAuth,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateAnnouncementRequestDto>,
+) -> Result<Json<AnnouncementDto>, Error> {
+    let oid = parse_announcement_id(&id)?;
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid announcement: {e}")))?;
+
+    let mut set = doc! {};
+    if let Some(title) = &request.title {
+        set.insert("title", title);
+    }
+    if let Some(body) = &request.body {
+        set.insert("body", body);
+    }
+    if let Some(image_url) = &request.image_url {
+        set.insert("imageUrl", image_url);
+    }
+    if let Some(link) = &request.link {
+        set.insert("link", link);
+    }
+    if let Some(segment) = &request.segment {
+        let segment = wither::bson::to_bson(segment)
+            .map_err(|e| Error::internal_err(&format!("Failed to serialize segment: {e}")))?;
+        set.insert("segment", segment);
+    }
+    if let Some(scheduled_at) = request.scheduled_at {
+        set.insert("scheduledAt", DateTime::from_chrono(scheduled_at));
+    }
+
+    let announcement = Announcement::update_scheduled_by_id(oid, set).await?;
+
+    tracing::info!("Admin {username} updated announcement {id}");
+
+    Ok(Json(AnnouncementDto::from(announcement)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/announcements/{id}/cancel",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Announcement ID")
+    ),
+    responses(
+        (status = 200, description = "Announcement cancelled; users already reached keep it", body = AnnouncementDto),
+        (status = 400, description = "Bad request, or the announcement already finished"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn cancel_announcement(
+    AdminAuth { username }: AdminAuth,
+    Path(id): Path<String>,
+) -> Result<Json<AnnouncementDto>, Error> {
+    let oid = parse_announcement_id(&id)?;
+    let announcement = Announcement::cancel_by_id(oid).await?;
+
+    tracing::info!("Admin {username} cancelled announcement {id}");
+
+    Ok(Json(AnnouncementDto::from(announcement)))
+}
+
+fn parse_announcement_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::parse_str(id).map_err(|_| Error::bad_request("Invalid announcement ID format"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/campaigns",
+    tag = "Admin APIs",
+    params(
+        PaginationQuery
+    ),
+    responses(
+        (status = 200, description = "Campaigns, most recent first", body = PaginationResponseDto<CampaignDto>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn get_campaigns(
+    _: AdminAuth,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<Json<PaginationResponseDto<CampaignDto>>, Error> {
+    let options = FindOptions::builder()
+        .sort(doc! { "createdAt": -1 })
+        .skip(pagination.skip() as u64)
+        .limit(pagination.limit() as i64)
+        .build();
+
+    let (campaigns, total) = Campaign::find_and_count(doc! {}, options).await?;
+    let total_pages = (total as f64 / pagination.limit() as f64).ceil() as u32;
+
+    Ok(Json(PaginationResponseDto {
+        docs: campaigns.into_iter().map(CampaignDto::from).collect(),
+        page: pagination.page(),
+        limit: pagination.limit(),
+        total_docs: total as u32,
+        total_pages,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/campaigns",
+    tag = "Admin APIs",
+    request_body(
+        content = CreateCampaignRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Campaign scheduled", body = CampaignDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn create_campaign(
+    AdminAuth { username }: AdminAuth,
+    Json(request): Json<CreateCampaignRequestDto>,
+) -> Result<Json<CampaignDto>, Error> {
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid campaign: {e}")))?;
+    check_campaign_window(request.starts_at, request.ends_at)?;
+
+    let now = DateTime::now();
+    let campaign = Campaign::create(Campaign {
+        id: None,
+        name: request.name,
+        title: request.title,
+        body: request.body,
+        image_url: request.image_url,
+        link: request.link,
+        segment: request.segment.unwrap_or_default(),
+        status: CampaignStatus::Scheduled.to_string(),
+        starts_at: DateTime::from_chrono(request.starts_at),
+        ends_at: request.ends_at.map(DateTime::from_chrono),
+        created_by: username.clone(),
+        lease_owner: None,
+        lease_expires_at: None,
+        cursor: None,
+        total_recipients: 0,
+        published: 0,
+        started_at: None,
+        finished_at: None,
+        error: None,
+        created_at: now,
+        updated_at: now,
+    })
+    .await?;
+
+    tracing::info!(
+        "Admin {username} scheduled campaign {} ({}) for {}",
+        campaign.name,
+        campaign.source_event_id(),
+        campaign.starts_at
+    );
+
+    Ok(Json(CampaignDto::from(campaign)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/campaigns/{id}",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Campaign ID")
+    ),
+    responses(
+        (status = 200, description = "Campaign with its fan-out progress", body = CampaignDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Campaign not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn get_campaign(
+    _: AdminAuth,
+    Path(id): Path<String>,
+) -> Result<Json<CampaignDto>, Error> {
+    let oid = parse_campaign_id(&id)?;
+    let campaign = find_campaign(oid).await?;
+
+    Ok(Json(CampaignDto::from(campaign)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/campaigns/{id}",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Campaign ID")
+    ),
+    request_body(
+        content = UpdateCampaignRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Campaign updated successfully", body = CampaignDto),
+        (status = 400, description = "Bad request, or the campaign already started"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Campaign not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn update_campaign(
+    AdminAuth { username }: AdminAuth,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateCampaignRequestDto>,
+) -> Result<Json<CampaignDto>, Error> {
+    let oid = parse_campaign_id(&id)?;
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid campaign: {e}")))?;
+
+    if request.starts_at.is_some() || request.ends_at.is_some() {
+        let existing = find_campaign(oid).await?;
+        check_campaign_window(
+            request
+                .starts_at
+                .unwrap_or_else(|| existing.starts_at.to_chrono()),
+            request
+                .ends_at
+                .or_else(|| existing.ends_at.map(|ends_at| ends_at.to_chrono())),
+        )?;
+    }
+
+    let mut set = doc! {};
+    if let Some(name) = &request.name {
+        set.insert("name", name);
+    }
+    if let Some(title) = &request.title {
+        set.insert("title", title);
+    }
+    if let Some(body) = &request.body {
+        set.insert("body", body);
+    }
+    if let Some(image_url) = &request.image_url {
+        set.insert("imageUrl", image_url);
+    }
+    if let Some(link) = &request.link {
+        set.insert("link", link);
+    }
+    if let Some(segment) = &request.segment {
+        let segment = wither::bson::to_bson(segment)
+            .map_err(|e| Error::internal_err(&format!("Failed to serialize segment: {e}")))?;
+        set.insert("segment", segment);
+    }
+    if let Some(starts_at) = request.starts_at {
+        set.insert("startsAt", DateTime::from_chrono(starts_at));
+    }
+    if let Some(ends_at) = request.ends_at {
+        set.insert("endsAt", DateTime::from_chrono(ends_at));
+    }
+
+    let campaign = Campaign::update_scheduled_by_id(oid, set).await?;
+
+    tracing::info!("Admin {username} updated campaign {id}");
+
+    Ok(Json(CampaignDto::from(campaign)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/campaigns/{id}/cancel",
+    tag = "Admin APIs",
+    params(
+        ("id" = String, Path, description = "Campaign ID")
+    ),
+    responses(
+        (status = 200, description = "Campaign cancelled; users already reached keep it", body = CampaignDto),
+        (status = 400, description = "Bad request, or the campaign already finished"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("basic_auth" = [])
+    )
+)]
+pub async fn cancel_campaign(
+    AdminAuth { username }: AdminAuth,
+    Path(id): Path<String>,
+) -> Result<Json<CampaignDto>, Error> {
+    let oid = parse_campaign_id(&id)?;
+    let campaign = Campaign::cancel_by_id(oid).await?;
+
+    tracing::info!("Admin {username} cancelled campaign {id}");
+
+    Ok(Json(CampaignDto::from(campaign)))
+}
+
+fn parse_campaign_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::parse_str(id).map_err(|_| Error::bad_request("Invalid campaign ID format"))
+}
+
+async fn find_campaign(id: ObjectId) -> Result<Campaign, Error> {
+    Campaign::find_by_id(&id)
+        .await?
+        .ok_or_else(|| Error::not_found("Campaign not found"))
+}
+
+fn check_campaign_window(
+    starts_at: chrono::DateTime<Utc>,
+    ends_at: Option<chrono::DateTime<Utc>>,
+) -> Result<(), Error> {
+    match ends_at {
+        Some(ends_at) if ends_at <= starts_at => {
+            Err(Error::bad_request("Campaign must end after it starts"))
+        }
+        Some(ends_at) if ends_at <= Utc::now() => Err(Error::bad_request(
+            "Campaign end time must be in the future",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn parse_template_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::parse_str(id).map_err(|_| Error::bad_request("Invalid template ID format"))
+}
+
+fn check_template_syntax(title: &str, body: &str) -> Result<(), Error> {
+    validate_template(title)
+        .and_then(|_| validate_template(body))
+        .map_err(|e| Error::bad_request(&format!("Invalid template syntax: {e:#}")))
+}
+
+/// The database is the source of truth, so a failed broadcast is only logged;
+/// consumers pick the change up when they next reload.
+fn broadcast_templates_updated(template: &NotificationTemplate) {
+    let template_id = template.id.map(|id| id.to_hex()).unwrap_or_default();
+    if let Err(e) = publish_templates_updated(template_id) {
+        tracing::warn!("{e}");
+    }
+}
@@ -1,6 +1,21 @@
-use crate::utils::structs::NotificationPreferences;
+use crate::config::APP_CONFIG;
+use crate::enums::{
+    DevicePlatform, PriceAlertCondition, TokenProvider, UserFcmTokenStatus, WebhookStatus,
+};
+use crate::models::price_alerts::PriceAlert;
+use crate::models::telegram_links::TelegramLink;
+use crate::models::user_fcm_token::UserFcmToken;
+use crate::models::user_notifications::NotificationMetadata;
+use crate::models::web_push_subscriptions::WebPushSubscription;
+use crate::models::webhook_deliveries::WebhookDelivery;
+use crate::models::webhook_subscriptions::WebhookSubscription;
+use crate::utils::structs::{NotificationPreferences, locked_preferences};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 use utoipa::ToSchema;
+use validator::Validate;
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -11,6 +26,9 @@ pub struct NotificationDto {
     pub content: String,
     pub is_read: bool,
     pub created_at: String,
+    /// Details of the event, absent on notifications stored before they were kept.
+    pub metadata: Option<NotificationMetadata>,
+    pub source_event_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -25,6 +43,27 @@ pub struct MarkNotificationAsReadResponseDto {
 pub struct NotifPreferenceResponseDto {
     pub user_id: String,
     pub preferences: NotificationPreferences,
+    /// Preferences that cannot be turned off completely, e.g. `account`.
+    pub locked_preferences: Vec<String>,
+    /// Events delivered even when their preference is off, as
+    /// `Category:Action`, e.g. `Password:Reset`.
+    pub mandatory_events: Vec<String>,
+}
+
+impl NotifPreferenceResponseDto {
+    pub fn new(user_id: String, preferences: NotificationPreferences) -> Self {
+        Self {
+            user_id,
+            preferences,
+            locked_preferences: locked_preferences(),
+            mandatory_events: APP_CONFIG
+                .mandatory_account_events
+                .iter()
+                .map(|event| event.trim().to_string())
+                .filter(|event| !event.is_empty())
+                .collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, ToSchema)]
@@ -32,3 +71,320 @@ pub struct NotifPreferenceResponseDto {
 pub struct EditNotifPreferenceRequestDto {
     pub preferences: NotificationPreferences,
 }
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterDeviceRequestDto {
+    #[validate(length(min = 1, max = 255))]
+    pub device_id: String,
+    #[validate(length(min = 1, max = 4096))]
+    pub token: String,
+    pub platform: Option<DevicePlatform>,
+    /// Service that issued `token`; defaults to `fcm`. iOS apps that register
+    /// with APNs directly send `apns`.
+    pub provider: Option<TokenProvider>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDto {
+    pub id: String,
+    pub device_id: String,
+    pub platform: Option<String>,
+    pub provider: TokenProvider,
+    pub status: UserFcmTokenStatus,
+    pub created_at: String,
+    pub updated_at: String,
+    pub last_seen_at: String,
+}
+
+impl From<UserFcmToken> for DeviceDto {
+    fn from(device: UserFcmToken) -> Self {
+        Self {
+            id: device.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: UserFcmTokenStatus::from_str(&device.status)
+                .unwrap_or(UserFcmTokenStatus::Inactive),
+            last_seen_at: device.last_seen().to_string(),
+            provider: device.provider(),
+            device_id: device.device_id,
+            platform: device.platform,
+            created_at: device.created_at.to_string(),
+            updated_at: device.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateWebhookRequestDto {
+    #[validate(url, length(max = 2048))]
+    pub url: String,
+    /// Notification types to receive: `ORDER` and/or `TRANSACTION`.
+    #[validate(length(min = 1))]
+    pub event_types: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateWebhookRequestDto {
+    #[validate(url, length(max = 2048))]
+    pub url: Option<String>,
+    #[validate(length(min = 1))]
+    pub event_types: Option<Vec<String>>,
+    /// Re-enabling a disabled webhook resets its failure count.
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDto {
+    pub id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub status: WebhookStatus,
+    pub consecutive_failures: i32,
+    pub disabled_reason: Option<String>,
+    pub last_delivery_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    /// Signing secret, only returned when the webhook is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl From<WebhookSubscription> for WebhookDto {
+    fn from(webhook: WebhookSubscription) -> Self {
+        Self {
+            id: webhook.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: WebhookStatus::from_str(&webhook.status).unwrap_or(WebhookStatus::Disabled),
+            url: webhook.url,
+            event_types: webhook.event_types,
+            consecutive_failures: webhook.consecutive_failures,
+            disabled_reason: webhook.disabled_reason,
+            last_delivery_at: webhook.last_delivery_at.map(|at| at.to_string()),
+            created_at: webhook.created_at.to_string(),
+            updated_at: webhook.updated_at.to_string(),
+            secret: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDeliveryDto {
+    pub id: String,
+    pub event_id: String,
+    pub event_type: String,
+    pub attempt: i32,
+    pub success: bool,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: String,
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryDto {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: delivery.id.map(|id| id.to_hex()).unwrap_or_default(),
+            event_id: delivery.event_id,
+            event_type: delivery.event_type,
+            attempt: delivery.attempt,
+            success: delivery.success,
+            status_code: delivery.status_code,
+            error: delivery.error,
+            duration_ms: delivery.duration_ms,
+            created_at: delivery.created_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePriceAlertRequestDto {
+    /// Market symbol as it appears in the price feed, e.g. `SUIUSDT`.
+    #[validate(length(min = 1, max = 32))]
+    pub symbol: String,
+    pub condition: PriceAlertCondition,
+    /// Required for `ABOVE` and `BELOW`.
+    #[schema(value_type = Option<String>)]
+    pub target_price: Option<Decimal>,
+    /// Required for `PERCENT_CHANGE`.
+    #[schema(value_type = Option<String>)]
+    pub percent: Option<Decimal>,
+    /// Price a `PERCENT_CHANGE` alert measures from, the last known price
+    /// when omitted.
+    #[schema(value_type = Option<String>)]
+    pub reference_price: Option<Decimal>,
+    #[serde(default)]
+    pub recurring: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePriceAlertRequestDto {
+    #[schema(value_type = Option<String>)]
+    pub target_price: Option<Decimal>,
+    #[schema(value_type = Option<String>)]
+    pub percent: Option<Decimal>,
+    pub recurring: Option<bool>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Re-enables a triggered one-shot alert, or pauses an active one.
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceAlertDto {
+    pub id: String,
+    pub symbol: String,
+    pub condition: PriceAlertCondition,
+    #[schema(value_type = Option<String>)]
+    pub target_price: Option<Decimal>,
+    #[schema(value_type = Option<String>)]
+    pub percent: Option<Decimal>,
+    #[schema(value_type = Option<String>)]
+    pub reference_price: Option<Decimal>,
+    pub recurring: bool,
+    pub is_active: bool,
+    pub expires_at: Option<String>,
+    pub trigger_count: i64,
+    pub last_triggered_at: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub last_triggered_price: Option<Decimal>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<PriceAlert> for PriceAlertDto {
+    fn from(alert: PriceAlert) -> Self {
+        Self {
+            id: alert.id.map(|id| id.to_hex()).unwrap_or_default(),
+            condition: PriceAlertCondition::from_str(&alert.condition)
+                .unwrap_or(PriceAlertCondition::Above),
+            symbol: alert.symbol,
+            target_price: alert.target_price,
+            percent: alert.percent,
+            reference_price: alert.reference_price,
+            recurring: alert.recurring,
+            is_active: alert.is_active,
+            expires_at: alert.expires_at.map(|at| at.to_string()),
+            trigger_count: alert.trigger_count,
+            last_triggered_at: alert.last_triggered_at.map(|at| at.to_string()),
+            last_triggered_price: alert.last_triggered_price,
+            created_at: alert.created_at.to_string(),
+            updated_at: alert.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TelegramLinkCodeDto {
+    /// One-time code to send to the bot as `/start <code>`.
+    pub code: String,
+    /// `t.me` link that opens the bot with the code prefilled.
+    pub deep_link: Option<String>,
+    pub expires_in_secs: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TelegramLinkDto {
+    pub linked: bool,
+    pub username: Option<String>,
+    pub linked_at: Option<String>,
+}
+
+impl From<Option<TelegramLink>> for TelegramLinkDto {
+    fn from(link: Option<TelegramLink>) -> Self {
+        match link {
+            Some(link) => Self {
+                linked: true,
+                username: link.username,
+                linked_at: Some(link.updated_at.to_string()),
+            },
+            None => Self {
+                linked: false,
+                username: None,
+                linked_at: None,
+            },
+        }
+    }
+}
+
+/// The subset of a Telegram `Update` the bot webhook reads.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TelegramUpdateDto {
+    pub message: Option<TelegramMessageDto>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TelegramMessageDto {
+    pub chat: TelegramChatDto,
+    pub from: Option<TelegramUserDto>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TelegramChatDto {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub chat_type: String,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct TelegramUserDto {
+    pub username: Option<String>,
+}
+
+/// A browser `PushSubscription` as serialized by `PushSubscription.toJSON()`.
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct WebPushSubscriptionRequestDto {
+    #[validate(url, length(max = 2048))]
+    pub endpoint: String,
+    #[validate(nested)]
+    pub keys: WebPushKeysDto,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema, Validate)]
+pub struct WebPushKeysDto {
+    #[validate(length(min = 1, max = 256))]
+    pub p256dh: String,
+    #[validate(length(min = 1, max = 64))]
+    pub auth: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebPushSubscriptionDto {
+    pub id: String,
+    pub endpoint: String,
+    pub user_agent: Option<String>,
+    pub status: UserFcmTokenStatus,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<WebPushSubscription> for WebPushSubscriptionDto {
+    fn from(subscription: WebPushSubscription) -> Self {
+        Self {
+            id: subscription.id.map(|id| id.to_hex()).unwrap_or_default(),
+            status: UserFcmTokenStatus::from_str(&subscription.status)
+                .unwrap_or(UserFcmTokenStatus::Inactive),
+            endpoint: subscription.endpoint,
+            user_agent: subscription.user_agent,
+            created_at: subscription.created_at.to_string(),
+            updated_at: subscription.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VapidPublicKeyDto {
+    /// Application server key to pass to `pushManager.subscribe`.
+    pub public_key: String,
+}
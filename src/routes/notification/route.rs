@@ -1,20 +1,52 @@
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
 use axum::{
     Json,
     extract::{Path, Query},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
 };
+use futures::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use tokio::sync::broadcast::error::RecvError;
 use utoipa_axum::router::OpenApiRouter;
 use utoipa_axum::routes;
+use validator::Validate;
 use wither::bson::{DateTime, doc, oid::ObjectId};
 
 use crate::app_state::AppState;
+use crate::config::APP_CONFIG;
+use crate::core::admin_auth::admin_auth::constant_time_eq;
+use crate::core::cache::redis_service::RedisService;
+use crate::core::delivery::public_host::{is_public_ip, resolve_public_host};
+use crate::core::delivery::telegram::telegram_bot;
+use crate::core::delivery::web_push::{VapidKey, validate_endpoint, validate_subscription_keys};
 use crate::core::jwt_auth::jwt_auth::JwtAuth;
+use crate::core::price_alerts::price_alert_index::{get_last_price, publish_price_alerts_updated};
+use crate::core::realtime::notification_hub::{
+    HubSubscription, RealtimeNotification, notification_hub,
+};
+use crate::enums::{PriceAlertCondition, TokenProvider, WebhookStatus};
 use crate::errors::Error;
+use crate::loading_fcm_token::{UpdateFcmTokenAction, publish_fcm_token_update};
 use crate::loading_preferences::update_user_notification_preferences;
+use crate::models::price_alerts::{PriceAlert, decimal_to_bson};
+use crate::models::telegram_links::TelegramLink;
+use crate::models::user_fcm_token::UserFcmToken;
 use crate::models::user_notification_settings::UserNotificationSetting;
 use crate::models::user_notifications::UserNotification;
+use crate::models::web_push_subscriptions::WebPushSubscription;
+use crate::models::webhook_deliveries::WebhookDelivery;
+use crate::models::webhook_subscriptions::WebhookSubscription;
 use crate::routes::notification::dto::{
-    EditNotifPreferenceRequestDto, MarkNotificationAsReadResponseDto, NotifPreferenceResponseDto,
-    NotificationDto,
+    CreatePriceAlertRequestDto, CreateWebhookRequestDto, DeviceDto, EditNotifPreferenceRequestDto,
+    MarkNotificationAsReadResponseDto, NotifPreferenceResponseDto, NotificationDto, PriceAlertDto,
+    RegisterDeviceRequestDto, TelegramLinkCodeDto, TelegramLinkDto, TelegramUpdateDto,
+    UpdatePriceAlertRequestDto, UpdateWebhookRequestDto, VapidPublicKeyDto, WebPushSubscriptionDto,
+    WebPushSubscriptionRequestDto, WebhookDeliveryDto, WebhookDto,
 };
 use crate::utils::models::ModelExt;
 use crate::utils::pagination::{PaginationQuery, PaginationResponseDto};
@@ -31,8 +63,36 @@ pub fn create_route() -> OpenApiRouter<AppState> {
         .routes(routes!(get_account_notification))
         .routes(routes!(update_notification_preference))
         .routes(routes!(get_notification_setting))
+        .routes(routes!(get_devices, register_device))
+        .routes(routes!(unregister_device))
+        .routes(routes!(device_heartbeat))
+        .routes(routes!(get_webhooks, create_webhook))
+        .routes(routes!(get_webhook, update_webhook, delete_webhook))
+        .routes(routes!(get_webhook_deliveries))
+        .routes(routes!(get_price_alerts, create_price_alert))
+        .routes(routes!(
+            get_price_alert,
+            update_price_alert,
+            delete_price_alert
+        ))
+        .routes(routes!(get_telegram_link, unlink_telegram))
+        .routes(routes!(create_telegram_link_code))
+        .routes(routes!(telegram_bot_webhook))
+        .routes(routes!(get_vapid_public_key))
+        .routes(routes!(get_web_push_subscriptions, subscribe_web_push))
+        .routes(routes!(unsubscribe_web_push))
+        .routes(routes!(stream_notifications))
 }
 
+const TELEGRAM_LINK_CODE_KEY_PREFIX: &str = "raidenx:telegram:link_code";
+
+const MAX_WEBHOOKS_PER_USER: u64 = 10;
+
+const MAX_PRICE_ALERTS_PER_USER: u64 = 50;
+
+/// Notification types that can be delivered to webhooks.
+const WEBHOOK_EVENT_TYPES: [NotifType; 2] = [NotifType::Order, NotifType::Transaction];
+
 #[utoipa::path(
     patch,
     path = "/read/{id}",
@@ -224,6 +284,8 @@ pub async fn get_latest_unread_transaction_notification(
                 content: notif.message,
                 is_read: notif.is_read,
                 created_at: notif.created_at.to_string(),
+                metadata: notif.metadata,
+                source_event_id: notif.source_event_id,
             })))
         }
         None => {
@@ -280,6 +342,8 @@ pub async fn get_latest_unread_account_notification(
                 content: notif.message,
                 is_read: notif.is_read,
                 created_at: notif.created_at.to_string(),
+                metadata: notif.metadata,
+                source_event_id: notif.source_event_id,
             })))
         }
         None => {
@@ -344,6 +408,8 @@ pub async fn get_account_notification(
                 content: notif.message.clone(),
                 is_read: notif.is_read,
                 created_at: notif.created_at.to_string(),
+                metadata: notif.metadata.clone(),
+                source_event_id: notif.source_event_id.clone(),
             })
         })
         .collect();
@@ -412,6 +478,8 @@ pub async fn get_transaction_notification(
                 content: notif.message.clone(),
                 is_read: notif.is_read,
                 created_at: notif.created_at.to_string(),
+                metadata: notif.metadata.clone(),
+                source_event_id: notif.source_event_id.clone(),
             })
         })
         .collect();
@@ -466,6 +534,9 @@ pub async fn update_notification_preference(
             "announcement": request.preferences.announcement,
             "campaign": request.preferences.campaign,
             "transaction": request.preferences.transaction,
+            "priceAlert": request.preferences.price_alert,
+            "language": request.preferences.language.map(|language| language.to_string()),
+            "timezone": request.preferences.timezone.map(|timezone| timezone.name()),
             "updatedAt": DateTime::now(),
         },
         "$setOnInsert": {
@@ -483,10 +554,10 @@ pub async fn update_notification_preference(
         .await
         .map_err(|e| Error::internal_err(&e.to_string()))?;
 
-    Ok(Json(NotifPreferenceResponseDto {
-        user_id: claims.user_id,
-        preferences: request.preferences,
-    }))
+    Ok(Json(NotifPreferenceResponseDto::new(
+        claims.user_id,
+        request.preferences,
+    )))
 }
 
 #[utoipa::path(
@@ -515,23 +586,1313 @@ pub async fn get_notification_setting(
         .map_err(|e| Error::internal_err(&format!("Failed to fetch preferences: {}", e)))?;
 
     match setting {
-        Some(setting) => Ok(Json(NotifPreferenceResponseDto {
-            user_id: setting.user_id,
-            preferences: NotificationPreferences {
+        Some(setting) => Ok(Json(NotifPreferenceResponseDto::new(
+            setting.user_id,
+            NotificationPreferences {
                 announcement: setting.announcement,
                 account: setting.account,
                 campaign: setting.campaign,
                 transaction: setting.transaction,
+                price_alert: setting.price_alert,
+                language: setting.language,
+                timezone: setting.timezone,
             },
-        })),
-        None => Ok(Json(NotifPreferenceResponseDto {
-            user_id: claims.user_id,
-            preferences: NotificationPreferences {
+        ))),
+        None => Ok(Json(NotifPreferenceResponseDto::new(
+            claims.user_id,
+            NotificationPreferences {
                 announcement: true,
                 account: true,
                 campaign: true,
                 transaction: true,
+                price_alert: true,
+                language: None,
+                timezone: None,
             },
-        })),
+        ))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/devices",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "Devices registered by the caller", body = Vec<DeviceDto>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_devices(JwtAuth(claims): JwtAuth) -> Result<Json<Vec<DeviceDto>>, Error> {
+    let devices = UserFcmToken::find_by_user_id(&claims.user_id)
+        .await
+        .map_err(|e| Error::internal_err(&format!("Failed to fetch devices: {}", e)))?;
+
+    Ok(Json(devices.into_iter().map(DeviceDto::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/devices",
+    tag = "Notification APIs",
+    request_body(
+        content = RegisterDeviceRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Device registered successfully", body = DeviceDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn register_device(
+    JwtAuth(claims): JwtAuth,
+    Json(request): Json<RegisterDeviceRequestDto>,
+) -> Result<Json<DeviceDto>, Error> {
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid device registration: {e}")))?;
+
+    let previous = UserFcmToken::find_by_device_id(&request.device_id).await?;
+
+    let device = UserFcmToken::create_or_update(
+        claims.user_id.clone(),
+        Some(request.device_id.clone()),
+        request.token,
+        request.platform.map(|platform| platform.to_string()),
+        request.provider.unwrap_or_default(),
+    )
+    .await?;
+
+    // The device may have been re-registered with a new token or signed in by
+    // another user, in which case the old token must stop receiving pushes.
+    if let Some(previous) = previous {
+        let replaced = previous.token != device.token || previous.user_id != device.user_id;
+        if replaced && previous.is_active() {
+            let provider = previous.provider();
+            broadcast_fcm_token_update(
+                previous.user_id,
+                previous.token,
+                None,
+                provider,
+                UpdateFcmTokenAction::Remove,
+            )
+            .await;
+        }
+    }
+
+    broadcast_fcm_token_update(
+        device.user_id.clone(),
+        device.token.clone(),
+        device.platform.clone(),
+        device.provider(),
+        UpdateFcmTokenAction::Add,
+    )
+    .await;
+
+    if let Err(e) = evict_least_recently_seen_devices(&claims.user_id, &device.device_id).await {
+        tracing::warn!(
+            "Failed to enforce device limit for user {}: {e}",
+            claims.user_id
+        );
+    }
+
+    tracing::info!(
+        "User {} registered device {}.",
+        claims.user_id,
+        device.device_id
+    );
+
+    Ok(Json(DeviceDto::from(device)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/devices/{device_id}",
+    tag = "Notification APIs",
+    params(
+        ("device_id" = String, Path, description = "Device ID")
+    ),
+    responses(
+        (status = 200, description = "Device unregistered or signed out remotely", body = DeviceDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Device not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn unregister_device(
+    JwtAuth(claims): JwtAuth,
+    Path(device_id): Path<String>,
+) -> Result<Json<DeviceDto>, Error> {
+    if device_id.is_empty() {
+        return Err(Error::bad_request("Device ID cannot be empty"));
+    }
+
+    let device =
+        UserFcmToken::deactivate_by_user_id_and_device_id(claims.user_id.clone(), &device_id)
+            .await?;
+
+    broadcast_fcm_token_update(
+        device.user_id.clone(),
+        device.token.clone(),
+        None,
+        device.provider(),
+        UpdateFcmTokenAction::Remove,
+    )
+    .await;
+
+    tracing::info!("User {} unregistered device {}.", claims.user_id, device_id);
+
+    Ok(Json(DeviceDto::from(device)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/devices/{device_id}/heartbeat",
+    tag = "Notification APIs",
+    params(
+        ("device_id" = String, Path, description = "Device ID")
+    ),
+    responses(
+        (status = 200, description = "Device last-seen time refreshed", body = DeviceDto),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Device not registered or no longer active"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn device_heartbeat(
+    JwtAuth(claims): JwtAuth,
+    Path(device_id): Path<String>,
+) -> Result<Json<DeviceDto>, Error> {
+    if device_id.is_empty() {
+        return Err(Error::bad_request("Device ID cannot be empty"));
+    }
+
+    let device = UserFcmToken::touch_last_seen(&claims.user_id, &device_id).await?;
+
+    Ok(Json(DeviceDto::from(device)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "Webhooks configured by the caller", body = Vec<WebhookDto>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_webhooks(JwtAuth(claims): JwtAuth) -> Result<Json<Vec<WebhookDto>>, Error> {
+    let webhooks = WebhookSubscription::find_by_user_id(&claims.user_id).await?;
+
+    Ok(Json(webhooks.into_iter().map(WebhookDto::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "Notification APIs",
+    request_body(
+        content = CreateWebhookRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Webhook created; the signing secret is only returned here", body = WebhookDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn create_webhook(
+    JwtAuth(claims): JwtAuth,
+    Json(request): Json<CreateWebhookRequestDto>,
+) -> Result<Json<WebhookDto>, Error> {
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid webhook: {e}")))?;
+    validate_webhook_url(&request.url).await?;
+    let event_types = parse_webhook_event_types(&request.event_types)?;
+
+    if WebhookSubscription::count_by_user_id(&claims.user_id).await? >= MAX_WEBHOOKS_PER_USER {
+        return Err(Error::bad_request(&format!(
+            "A user can configure at most {MAX_WEBHOOKS_PER_USER} webhooks"
+        )));
+    }
+
+    let now = DateTime::now();
+    let secret = generate_webhook_secret();
+    let webhook = WebhookSubscription::create(WebhookSubscription {
+        id: None,
+        user_id: claims.user_id.clone(),
+        url: request.url,
+        secret: secret.clone(),
+        event_types,
+        status: WebhookStatus::Active.to_string(),
+        consecutive_failures: 0,
+        disabled_reason: None,
+        last_delivery_at: None,
+        created_at: now,
+        updated_at: now,
+    })
+    .await?;
+
+    tracing::info!("User {} created webhook {}.", claims.user_id, webhook.url);
+
+    let mut response = WebhookDto::from(webhook);
+    response.secret = Some(secret);
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook", body = WebhookDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_webhook(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookDto>, Error> {
+    let oid = parse_webhook_id(&id)?;
+    let webhook = WebhookSubscription::find_by_user_id_and_id(&claims.user_id, oid).await?;
+
+    Ok(Json(WebhookDto::from(webhook)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/webhooks/{id}",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Webhook ID")
+    ),
+    request_body(
+        content = UpdateWebhookRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Webhook updated successfully", body = WebhookDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_webhook(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateWebhookRequestDto>,
+) -> Result<Json<WebhookDto>, Error> {
+    let oid = parse_webhook_id(&id)?;
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid webhook: {e}")))?;
+
+    let mut set = doc! {};
+    if let Some(url) = &request.url {
+        validate_webhook_url(url).await?;
+        set.insert("url", url);
+    }
+    if let Some(event_types) = &request.event_types {
+        set.insert("eventTypes", parse_webhook_event_types(event_types)?);
+    }
+    match request.enabled {
+        Some(true) => {
+            set.insert("status", WebhookStatus::Active.to_string());
+            set.insert("consecutiveFailures", 0);
+            set.insert("disabledReason", wither::bson::Bson::Null);
+        }
+        Some(false) => {
+            set.insert("status", WebhookStatus::Disabled.to_string());
+            set.insert("disabledReason", "Disabled by user");
+        }
+        None => {}
+    }
+
+    let webhook = WebhookSubscription::update_by_user_id_and_id(&claims.user_id, oid, set).await?;
+
+    Ok(Json(WebhookDto::from(webhook)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted successfully"),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_webhook(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+) -> Result<Json<()>, Error> {
+    let oid = parse_webhook_id(&id)?;
+    WebhookSubscription::delete_by_user_id_and_id(&claims.user_id, oid).await?;
+
+    tracing::info!("User {} deleted webhook {}.", claims.user_id, id);
+
+    Ok(Json(()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Webhook ID"),
+        PaginationQuery
+    ),
+    responses(
+        (status = 200, description = "Delivery attempts, most recent first", body = PaginationResponseDto<WebhookDeliveryDto>),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Webhook not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_webhook_deliveries(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<Json<PaginationResponseDto<WebhookDeliveryDto>>, Error> {
+    let oid = parse_webhook_id(&id)?;
+    WebhookSubscription::find_by_user_id_and_id(&claims.user_id, oid).await?;
+
+    let filter = doc! {
+        "subscriptionId": oid,
+        "userId": &claims.user_id,
+    };
+
+    let options = wither::mongodb::options::FindOptions::builder()
+        .sort(doc! { "createdAt": -1 })
+        .skip(pagination.skip() as u64)
+        .limit(pagination.limit() as i64)
+        .build();
+
+    let deliveries = WebhookDelivery::find(filter.clone(), Some(options))
+        .await
+        .map_err(|e| Error::internal_err(&format!("Failed to fetch webhook deliveries: {}", e)))?;
+
+    let total = WebhookDelivery::count(filter)
+        .await
+        .map_err(|e| Error::internal_err(&format!("Failed to count webhook deliveries: {}", e)))?;
+
+    let total_pages = (total as f64 / pagination.limit() as f64).ceil() as u32;
+
+    Ok(Json(PaginationResponseDto {
+        docs: deliveries
+            .into_iter()
+            .map(WebhookDeliveryDto::from)
+            .collect(),
+        page: pagination.page(),
+        limit: pagination.limit(),
+        total_docs: total as u32,
+        total_pages,
+    }))
+}
+
+fn parse_webhook_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::parse_str(id).map_err(|_| Error::bad_request("Invalid webhook ID format"))
+}
+
+/// Only public HTTPS endpoints are accepted, so a webhook cannot be used to
+/// reach services inside our network. The worker checks the addresses again
+/// when connecting, since DNS records can change after registration.
+async fn validate_webhook_url(url: &str) -> Result<(), Error> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| Error::bad_request("Invalid webhook URL"))?;
+
+    match parsed.scheme() {
+        "https" => {}
+        "http" if APP_CONFIG.webhook_allow_http => {}
+        _ => return Err(Error::bad_request("Webhook URL must use https")),
+    }
+
+    if APP_CONFIG.webhook_allow_private_hosts {
+        return Ok(());
     }
+
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| Error::bad_request("Webhook URL must have a host"))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let is_public = match host.parse::<IpAddr>() {
+        Ok(ip) => is_public_ip(ip),
+        Err(_) if host.eq_ignore_ascii_case("localhost") || host.ends_with(".localhost") => false,
+        Err(_) => resolve_public_host(host).await.is_ok(),
+    };
+    if !is_public {
+        return Err(Error::bad_request(
+            "Webhook URL must point to a public host",
+        ));
+    }
+
+    Ok(())
+}
+
+fn parse_webhook_event_types(event_types: &[String]) -> Result<Vec<String>, Error> {
+    let mut parsed = Vec::new();
+
+    for event_type in event_types {
+        let notif_type = NotifType::from_str(&event_type.to_uppercase())
+            .ok()
+            .filter(|notif_type| WEBHOOK_EVENT_TYPES.contains(notif_type))
+            .ok_or_else(|| {
+                Error::bad_request(&format!("Unsupported webhook event type: {event_type}"))
+            })?;
+
+        let notif_type = notif_type.to_string();
+        if !parsed.contains(&notif_type) {
+            parsed.push(notif_type);
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn generate_webhook_secret() -> String {
+    format!(
+        "whsec_{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/price-alerts",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "Price alerts created by the caller", body = Vec<PriceAlertDto>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_price_alerts(JwtAuth(claims): JwtAuth) -> Result<Json<Vec<PriceAlertDto>>, Error> {
+    let alerts = PriceAlert::find_by_user_id(&claims.user_id).await?;
+
+    Ok(Json(alerts.into_iter().map(PriceAlertDto::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/price-alerts",
+    tag = "Notification APIs",
+    request_body(
+        content = CreatePriceAlertRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Price alert created", body = PriceAlertDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn create_price_alert(
+    JwtAuth(claims): JwtAuth,
+    Json(request): Json<CreatePriceAlertRequestDto>,
+) -> Result<Json<PriceAlertDto>, Error> {
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid price alert: {e}")))?;
+    validate_price_alert(request.condition, request.target_price, request.percent)?;
+    let expires_at = parse_price_alert_expiry(request.expires_at)?;
+    let symbol = request.symbol.trim().to_uppercase();
+
+    if PriceAlert::count_active_by_user_id(&claims.user_id).await? >= MAX_PRICE_ALERTS_PER_USER {
+        return Err(Error::bad_request(&format!(
+            "A user can have at most {MAX_PRICE_ALERTS_PER_USER} active price alerts"
+        )));
+    }
+
+    let reference_price = match request.condition {
+        PriceAlertCondition::PercentChange => {
+            let reference_price = match request.reference_price {
+                Some(reference_price) => reference_price,
+                None => get_last_price(&symbol).await.ok_or_else(|| {
+                    Error::bad_request(&format!(
+                        "No recent price for {symbol}, a reference price is required"
+                    ))
+                })?,
+            };
+            if reference_price <= Decimal::ZERO {
+                return Err(Error::bad_request("Reference price must be positive"));
+            }
+            Some(reference_price)
+        }
+        PriceAlertCondition::Above | PriceAlertCondition::Below => None,
+    };
+
+    let now = DateTime::now();
+    let alert = PriceAlert::create(PriceAlert {
+        id: None,
+        user_id: claims.user_id.clone(),
+        symbol,
+        condition: request.condition.to_string(),
+        target_price: request.target_price,
+        percent: request.percent,
+        reference_price,
+        recurring: request.recurring,
+        is_active: true,
+        expires_at,
+        trigger_count: 0,
+        last_triggered_at: None,
+        last_triggered_price: None,
+        created_at: now,
+        updated_at: now,
+    })
+    .await?;
+
+    notify_price_alert_changed(&alert);
+    tracing::info!(
+        "User {} created {} price alert on {}.",
+        claims.user_id,
+        alert.condition,
+        alert.symbol
+    );
+
+    Ok(Json(PriceAlertDto::from(alert)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/price-alerts/{id}",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Price alert ID")
+    ),
+    responses(
+        (status = 200, description = "Price alert", body = PriceAlertDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Price alert not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_price_alert(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+) -> Result<Json<PriceAlertDto>, Error> {
+    let oid = parse_price_alert_id(&id)?;
+    let alert = PriceAlert::find_by_user_id_and_id(&claims.user_id, oid).await?;
+
+    Ok(Json(PriceAlertDto::from(alert)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/price-alerts/{id}",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Price alert ID")
+    ),
+    request_body(
+        content = UpdatePriceAlertRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Price alert updated successfully", body = PriceAlertDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Price alert not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_price_alert(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+    Json(request): Json<UpdatePriceAlertRequestDto>,
+) -> Result<Json<PriceAlertDto>, Error> {
+    let oid = parse_price_alert_id(&id)?;
+    let existing = PriceAlert::find_by_user_id_and_id(&claims.user_id, oid).await?;
+    let condition = existing
+        .condition()
+        .ok_or_else(|| Error::internal_err("Price alert has an invalid condition"))?;
+    validate_price_alert(
+        condition,
+        request.target_price.or(existing.target_price),
+        request.percent.or(existing.percent),
+    )?;
+
+    let mut set = doc! {};
+    if request.target_price.is_some() {
+        set.insert("targetPrice", decimal_to_bson(request.target_price));
+    }
+    if request.percent.is_some() {
+        set.insert("percent", decimal_to_bson(request.percent));
+    }
+    if let Some(recurring) = request.recurring {
+        set.insert("recurring", recurring);
+    }
+    if request.expires_at.is_some() {
+        set.insert("expiresAt", parse_price_alert_expiry(request.expires_at)?);
+    }
+    match request.enabled {
+        Some(true) if !existing.is_active => {
+            if PriceAlert::count_active_by_user_id(&claims.user_id).await?
+                >= MAX_PRICE_ALERTS_PER_USER
+            {
+                return Err(Error::bad_request(&format!(
+                    "A user can have at most {MAX_PRICE_ALERTS_PER_USER} active price alerts"
+                )));
+            }
+            set.insert("isActive", true);
+        }
+        Some(false) => {
+            set.insert("isActive", false);
+        }
+        _ => {}
+    }
+
+    let alert = PriceAlert::update_by_user_id_and_id(&claims.user_id, oid, set).await?;
+    notify_price_alert_changed(&alert);
+
+    Ok(Json(PriceAlertDto::from(alert)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/price-alerts/{id}",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Price alert ID")
+    ),
+    responses(
+        (status = 200, description = "Price alert deleted successfully"),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Price alert not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_price_alert(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+) -> Result<Json<()>, Error> {
+    let oid = parse_price_alert_id(&id)?;
+    PriceAlert::delete_by_user_id_and_id(&claims.user_id, oid).await?;
+
+    if let Err(e) = publish_price_alerts_updated(id.clone()) {
+        tracing::warn!("{e}");
+    }
+    tracing::info!("User {} deleted price alert {}.", claims.user_id, id);
+
+    Ok(Json(()))
+}
+
+fn parse_price_alert_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::parse_str(id).map_err(|_| Error::bad_request("Invalid price alert ID format"))
+}
+
+/// Checks that the condition has the value it is evaluated with.
+fn validate_price_alert(
+    condition: PriceAlertCondition,
+    target_price: Option<Decimal>,
+    percent: Option<Decimal>,
+) -> Result<(), Error> {
+    match condition {
+        PriceAlertCondition::Above | PriceAlertCondition::Below => match target_price {
+            Some(target_price) if target_price > Decimal::ZERO => Ok(()),
+            Some(_) => Err(Error::bad_request("Target price must be positive")),
+            None => Err(Error::bad_request(&format!(
+                "A {condition} alert requires a target price"
+            ))),
+        },
+        PriceAlertCondition::PercentChange => match percent {
+            Some(percent) if percent > Decimal::ZERO => Ok(()),
+            Some(_) => Err(Error::bad_request("Percent must be positive")),
+            None => Err(Error::bad_request(&format!(
+                "A {condition} alert requires a percent"
+            ))),
+        },
+    }
+}
+
+fn parse_price_alert_expiry(
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Option<DateTime>, Error> {
+    match expires_at {
+        Some(expires_at) if expires_at <= chrono::Utc::now() => {
+            Err(Error::bad_request("Expiry must be in the future"))
+        }
+        expires_at => Ok(expires_at.map(DateTime::from_chrono)),
+    }
+}
+
+/// The alert consumer picks the change up from here; the alert is saved
+/// either way, so a failed publish is only logged.
+fn notify_price_alert_changed(alert: &PriceAlert) {
+    let Some(id) = alert.id else {
+        return;
+    };
+    if let Err(e) = publish_price_alerts_updated(id.to_hex()) {
+        tracing::warn!("{e}");
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/telegram",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "Telegram link of the caller", body = TelegramLinkDto),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_telegram_link(JwtAuth(claims): JwtAuth) -> Result<Json<TelegramLinkDto>, Error> {
+    let link = TelegramLink::find_by_user_id(&claims.user_id).await?;
+
+    Ok(Json(TelegramLinkDto::from(link)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/telegram",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "Telegram chat unlinked successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No Telegram chat linked"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn unlink_telegram(JwtAuth(claims): JwtAuth) -> Result<Json<()>, Error> {
+    if !TelegramLink::unlink_by_user_id(&claims.user_id).await? {
+        return Err(Error::not_found("No Telegram chat linked"));
+    }
+
+    tracing::info!("User {} unlinked their Telegram chat.", claims.user_id);
+
+    Ok(Json(()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/telegram/link-code",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "One-time code to redeem with the bot's /start command", body = TelegramLinkCodeDto),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn create_telegram_link_code(
+    JwtAuth(claims): JwtAuth,
+) -> Result<Json<TelegramLinkCodeDto>, Error> {
+    let code = uuid::Uuid::new_v4().simple().to_string();
+    let ttl = APP_CONFIG.telegram_link_code_ttl_secs;
+
+    RedisService::new()
+        .await
+        .set_ex_cache(&telegram_link_code_key(&code), &claims.user_id, ttl)
+        .await
+        .map_err(|e| Error::internal_err(&format!("Failed to store Telegram link code: {e}")))?;
+
+    let deep_link = APP_CONFIG
+        .telegram_bot_username
+        .as_ref()
+        .map(|username| format!("https://t.me/{username}?start={code}"));
+
+    Ok(Json(TelegramLinkCodeDto {
+        code,
+        deep_link,
+        expires_in_secs: ttl,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/telegram/webhook",
+    tag = "Notification APIs",
+    request_body(
+        content = TelegramUpdateDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Update processed"),
+        (status = 401, description = "Missing or invalid secret token"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn telegram_bot_webhook(
+    headers: HeaderMap,
+    Json(update): Json<TelegramUpdateDto>,
+) -> Result<Json<()>, Error> {
+    let expected = APP_CONFIG
+        .telegram_webhook_secret
+        .as_deref()
+        .filter(|secret| !secret.is_empty())
+        .ok_or_else(|| Error::unauthorized("Telegram webhook is not configured"))?;
+    let provided = headers
+        .get("X-Telegram-Bot-Api-Secret-Token")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if !constant_time_eq(provided.as_bytes(), expected.as_bytes()) {
+        return Err(Error::unauthorized("Invalid Telegram secret token"));
+    }
+
+    let Some(message) = update.message else {
+        return Ok(Json(()));
+    };
+    let chat_id = message.chat.id;
+    let text = message.text.unwrap_or_default();
+
+    // Notifications must not leak into groups the user shares with others.
+    if message.chat.chat_type != "private" {
+        return Ok(Json(()));
+    }
+
+    let reply = if text.trim() == "/stop" {
+        TelegramLink::unlink_by_chat_id(chat_id).await?;
+        "Notifications are turned off. Link your account again from the app to resume."
+    } else {
+        match text
+            .strip_prefix("/start")
+            .map(str::trim)
+            .filter(|code| !code.is_empty())
+        {
+            Some(code) => {
+                let user_id = RedisService::new()
+                    .await
+                    .take_cache::<String>(&telegram_link_code_key(code))
+                    .await
+                    .map_err(|e| {
+                        Error::internal_err(&format!("Failed to read Telegram link code: {e}"))
+                    })?;
+
+                match user_id {
+                    Some(user_id) => {
+                        let username = message.from.and_then(|from| from.username);
+                        TelegramLink::link(&user_id, chat_id, username).await?;
+                        tracing::info!("User {} linked a Telegram chat.", user_id);
+                        "Your account is linked. Notifications will be delivered to this chat."
+                    }
+                    None => {
+                        "This link has expired or was already used. Request a new one from the app."
+                    }
+                }
+            }
+            None => "Open the Telegram link from the app to connect your account.",
+        }
+    };
+
+    match telegram_bot() {
+        Ok(bot) => {
+            if let Err(e) = bot.send_message(chat_id, reply).await {
+                tracing::warn!("Failed to reply to Telegram chat: {e}");
+            }
+        }
+        Err(e) => tracing::warn!("Failed to reply to Telegram chat: {e}"),
+    }
+
+    Ok(Json(()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/web-push/vapid-public-key",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "VAPID application server key", body = VapidPublicKeyDto),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_vapid_public_key(_: JwtAuth) -> Result<Json<VapidPublicKeyDto>, Error> {
+    let vapid = VapidKey::from_config()?;
+
+    Ok(Json(VapidPublicKeyDto {
+        public_key: vapid.public_key().to_string(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/web-push/subscriptions",
+    tag = "Notification APIs",
+    responses(
+        (status = 200, description = "Browser subscriptions of the caller", body = Vec<WebPushSubscriptionDto>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_web_push_subscriptions(
+    JwtAuth(claims): JwtAuth,
+) -> Result<Json<Vec<WebPushSubscriptionDto>>, Error> {
+    let subscriptions = WebPushSubscription::find_by_user_id(&claims.user_id).await?;
+
+    Ok(Json(
+        subscriptions
+            .into_iter()
+            .map(WebPushSubscriptionDto::from)
+            .collect(),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/web-push/subscriptions",
+    tag = "Notification APIs",
+    request_body(
+        content = WebPushSubscriptionRequestDto,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Browser subscription saved", body = WebPushSubscriptionDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn subscribe_web_push(
+    JwtAuth(claims): JwtAuth,
+    headers: HeaderMap,
+    Json(request): Json<WebPushSubscriptionRequestDto>,
+) -> Result<Json<WebPushSubscriptionDto>, Error> {
+    request
+        .validate()
+        .map_err(|e| Error::bad_request(&format!("Invalid push subscription: {e}")))?;
+    validate_endpoint(&request.endpoint)
+        .map_err(|e| Error::bad_request(&format!("Invalid push subscription: {e}")))?;
+    validate_subscription_keys(&request.keys.p256dh, &request.keys.auth)
+        .map_err(|e| Error::bad_request(&format!("Invalid push subscription: {e}")))?;
+
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(512).collect::<String>());
+
+    let subscription = WebPushSubscription::create_or_update(
+        &claims.user_id,
+        &request.endpoint,
+        &request.keys.p256dh,
+        &request.keys.auth,
+        user_agent,
+    )
+    .await?;
+
+    tracing::info!("User {} subscribed a browser to web push.", claims.user_id);
+
+    Ok(Json(WebPushSubscriptionDto::from(subscription)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/web-push/subscriptions/{id}",
+    tag = "Notification APIs",
+    params(
+        ("id" = String, Path, description = "Subscription ID")
+    ),
+    responses(
+        (status = 200, description = "Browser subscription removed", body = WebPushSubscriptionDto),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Subscription not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn unsubscribe_web_push(
+    JwtAuth(claims): JwtAuth,
+    Path(id): Path<String>,
+) -> Result<Json<WebPushSubscriptionDto>, Error> {
+    let oid = ObjectId::parse_str(&id)
+        .map_err(|_| Error::bad_request("Invalid subscription ID format"))?;
+
+    let subscription =
+        WebPushSubscription::deactivate_by_user_id_and_id(&claims.user_id, oid).await?;
+
+    Ok(Json(WebPushSubscriptionDto::from(subscription)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stream",
+    tag = "Notification APIs",
+    params(
+        ("Last-Event-ID" = Option<String>, Header, description = "ID of the last notification received; notifications stored after it are sent first")
+    ),
+    responses(
+        (status = 200, description = "Server-sent `notification` events, each carrying one new notification as JSON", content_type = "text/event-stream", body = String),
+        (status = 400, description = "Bad request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 429, description = "Too many streams open for this user"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn stream_notifications(
+    JwtAuth(claims): JwtAuth,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, Error> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .map(ObjectId::parse_str)
+        .transpose()
+        .map_err(|_| Error::bad_request("Invalid Last-Event-ID"))?;
+
+    // Join the hub before reading the backlog so nothing stored in between is lost.
+    let subscription = notification_hub().subscribe(&claims.user_id)?;
+
+    let (replayed, replay_after) = match last_event_id {
+        Some(after) => replay_page(&claims.user_id, after).await?,
+        None => (Vec::new(), None),
+    };
+    let state = NotificationStream {
+        subscription,
+        pending: replayed.into(),
+        replay_after,
+        last_replayed_id: None,
+    };
+
+    let stream = futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(notification) = state.pending.pop_front() {
+                state.last_replayed_id = Some(notification.id.clone());
+                let event = notification_event(&notification);
+                return Some((event, state));
+            }
+
+            if let Some(after) = state.replay_after.take() {
+                match replay_page(state.subscription.user_id(), after).await {
+                    Ok((page, next)) => {
+                        state.pending.extend(page);
+                        state.replay_after = next;
+                        continue;
+                    }
+                    // The client resumes from the last notification it got.
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to replay notifications of user {}, closing stream: {e}",
+                            state.subscription.user_id()
+                        );
+                        return None;
+                    }
+                }
+            }
+
+            match state.subscription.receiver.recv().await {
+                Ok(notification) => {
+                    // Hex ObjectIds sort like the ids themselves.
+                    if state
+                        .last_replayed_id
+                        .as_ref()
+                        .is_some_and(|last| notification.id <= *last)
+                    {
+                        continue;
+                    }
+                    let event = notification_event(&notification);
+                    return Some((event, state));
+                }
+                // Ending the stream makes the client reconnect with
+                // Last-Event-ID and fetch what was skipped from Mongo.
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "Notification stream of user {} lagged by {skipped}, closing it",
+                        state.subscription.user_id()
+                    );
+                    return None;
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream)
+        .keep_alive(KeepAlive::new().interval(Duration::from_secs(APP_CONFIG.sse_keep_alive_secs))))
+}
+
+/// A notification stream replays the stored backlog a page at a time, then
+/// forwards live notifications that were not part of the replay.
+struct NotificationStream {
+    subscription: HubSubscription,
+    pending: VecDeque<RealtimeNotification>,
+    /// Id after which the next page is read; `None` once caught up.
+    replay_after: Option<ObjectId>,
+    last_replayed_id: Option<String>,
+}
+
+/// Reads up to `sse_replay_limit` notifications stored after `after`, and
+/// where the next page starts when this one is full.
+async fn replay_page(
+    user_id: &str,
+    after: ObjectId,
+) -> Result<(Vec<RealtimeNotification>, Option<ObjectId>), Error> {
+    let page = UserNotification::find_after(user_id, after, APP_CONFIG.sse_replay_limit).await?;
+    let next = if page.len() as i64 >= APP_CONFIG.sse_replay_limit {
+        page.last().and_then(|notification| notification.id)
+    } else {
+        None
+    };
+
+    Ok((
+        page.iter()
+            .filter_map(RealtimeNotification::from_notification)
+            .collect(),
+        next,
+    ))
+}
+
+fn notification_event(notification: &RealtimeNotification) -> Result<Event, axum::Error> {
+    Event::default()
+        .id(notification.id.clone())
+        .event("notification")
+        .json_data(notification)
+}
+
+fn telegram_link_code_key(code: &str) -> String {
+    format!("{}:{}", TELEGRAM_LINK_CODE_KEY_PREFIX, code)
+}
+
+/// The database is the source of truth, so a failed broadcast is only logged;
+/// publishers catch up on their next reload.
+async fn broadcast_fcm_token_update(
+    user_id: String,
+    token: String,
+    platform: Option<String>,
+    provider: TokenProvider,
+    action: UpdateFcmTokenAction,
+) {
+    if let Err(e) =
+        publish_fcm_token_update(user_id.clone(), token, platform, provider, action).await
+    {
+        tracing::warn!(
+            "Failed to broadcast FCM token update for user {}: {e}",
+            user_id
+        );
+    }
+}
+
+/// Signs out the least recently seen devices once the user has more active
+/// devices than `max_active_devices_per_user`, never evicting `current_device_id`.
+async fn evict_least_recently_seen_devices(
+    user_id: &str,
+    current_device_id: &str,
+) -> Result<(), Error> {
+    let max_devices = APP_CONFIG.max_active_devices_per_user;
+    let mut devices = UserFcmToken::find_active_by_user_id(user_id).await?;
+
+    if devices.len() <= max_devices {
+        return Ok(());
+    }
+
+    let excess = devices.len() - max_devices;
+    devices.sort_by_key(|device| device.last_seen().timestamp_millis());
+
+    for device in devices
+        .into_iter()
+        .filter(|device| device.device_id != current_device_id)
+        .take(excess)
+    {
+        UserFcmToken::deactivate_by_user_id_and_device_id(user_id.to_string(), &device.device_id)
+            .await?;
+        broadcast_fcm_token_update(
+            device.user_id.clone(),
+            device.token.clone(),
+            None,
+            device.provider(),
+            UpdateFcmTokenAction::Remove,
+        )
+        .await;
+
+        tracing::info!(
+            "Evicted device {} of user {} (last seen {}) to stay within {} active devices.",
+            device.device_id,
+            user_id,
+            device.last_seen(),
+            max_devices
+        );
+    }
+
+    Ok(())
 }
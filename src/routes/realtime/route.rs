@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use axum::extract::Query;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use utoipa::IntoParams;
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_axum::routes;
+use wither::bson::{DateTime, doc, oid::ObjectId};
+
+use crate::app_state::AppState;
+use crate::core::jwt_auth::jwt_auth::decode_jwt;
+use crate::core::jwt_auth::types::TokenClaims;
+use crate::core::realtime::notification_hub::{
+    HubSubscription, RealtimeNotification, notification_hub,
+};
+use crate::core::realtime::presence::{PRESENCE_REFRESH_INTERVAL, mark_offline, mark_online};
+use crate::errors::Error;
+use crate::models::accounts::Account;
+use crate::models::user_notifications::UserNotification;
+use crate::utils::models::ModelExt;
+use crate::utils::structs::NotifType;
+
+pub fn create_route() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(connect_web_socket))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct WebSocketAuthQuery {
+    /// JWT for clients that cannot set an `Authorization` header.
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum ClientMessage {
+    /// Receive only these notification types; nothing subscribed means all.
+    Subscribe {
+        types: Vec<String>,
+    },
+    Unsubscribe {
+        types: Vec<String>,
+    },
+    MarkAsRead {
+        id: String,
+    },
+    /// Confirms a pushed notification was received.
+    Ack {
+        id: String,
+    },
+    Ping,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+enum ServerMessage {
+    Subscribed { types: Vec<String> },
+    Notification { notification: RealtimeNotification },
+    UnreadCount { notif_type: String, count: u64 },
+    Acked { id: String },
+    Pong,
+    Error { message: String },
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "Notification APIs",
+    params(WebSocketAuthQuery),
+    responses(
+        (status = 101, description = "Switched to the WebSocket notification protocol"),
+        (status = 401, description = "Unauthorized"),
+        (status = 429, description = "Too many streams open for this user")
+    ),
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn connect_web_socket(
+    ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(query): Query<WebSocketAuthQuery>,
+) -> Result<Response, Error> {
+    let claims = authenticate(&headers, query.token.as_deref()).await?;
+    let subscription = notification_hub().subscribe(&claims.user_id)?;
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, claims, subscription)))
+}
+
+/// Same checks as `JwtAuth`, also accepting the token as a query parameter.
+async fn authenticate(
+    headers: &HeaderMap,
+    query_token: Option<&str>,
+) -> Result<TokenClaims, Error> {
+    let header_token = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let token = header_token
+        .or(query_token)
+        .ok_or_else(|| Error::unauthorized("Authorization header missing"))?;
+
+    let claims = decode_jwt(token).map_err(|_| Error::unauthorized("Invalid jwt token"))?;
+
+    Account::get_account_by_user_id(&claims.user_id)
+        .await?
+        .ok_or_else(|| Error::unauthorized("The user belonging to this token no longer exists"))?;
+
+    // OAuth2 scopes do not cover notifications.
+    if claims.oauth2_client_id.is_some() {
+        return Err(Error::forbidden(
+            "You do not have permission to access this resource",
+        ));
+    }
+
+    Ok(claims)
+}
+
+async fn handle_socket(
+    mut socket: WebSocket,
+    claims: TokenClaims,
+    mut subscription: HubSubscription,
+) {
+    let user_id = claims.user_id;
+    let connection_id = uuid::Uuid::new_v4().to_string();
+    let connected_at = DateTime::now().timestamp_millis();
+    let mut subscribed_types = HashSet::new();
+    let mut presence_refresh = tokio::time::interval(PRESENCE_REFRESH_INTERVAL);
+
+    tracing::info!("User {user_id} opened WebSocket connection {connection_id}");
+
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    // Pings are answered by axum; binary frames are not part of the protocol.
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        tracing::debug!("WebSocket connection {connection_id} failed: {e}");
+                        break;
+                    }
+                };
+
+                let replies = match serde_json::from_str::<ClientMessage>(text.as_str()) {
+                    Ok(message) => {
+                        handle_client_message(&user_id, &mut subscribed_types, message).await
+                    }
+                    Err(e) => vec![ServerMessage::Error {
+                        message: format!("Invalid message: {e}"),
+                    }],
+                };
+                if !send_messages(&mut socket, replies).await {
+                    break;
+                }
+            }
+            notification = subscription.receiver.recv() => {
+                let notification = match notification {
+                    Ok(notification) => notification,
+                    Err(RecvError::Lagged(skipped)) => {
+                        let message = ServerMessage::Error {
+                            message: format!("Missed {skipped} notifications, refetch them"),
+                        };
+                        if !send_messages(&mut socket, vec![message]).await {
+                            break;
+                        }
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if !is_subscribed(&subscribed_types, &notification.r#type) {
+                    continue;
+                }
+
+                let mut messages = vec![ServerMessage::Notification {
+                    notification: notification.as_ref().clone(),
+                }];
+                messages.extend(unread_count_message(&user_id, &notification.r#type).await);
+                if !send_messages(&mut socket, messages).await {
+                    break;
+                }
+            }
+            _ = presence_refresh.tick() => {
+                if let Err(e) = mark_online(&user_id, &connection_id, connected_at).await {
+                    tracing::warn!("Failed to refresh presence of user {user_id}: {e}");
+                }
+            }
+        }
+    }
+
+    if let Err(e) = mark_offline(&user_id, &connection_id).await {
+        tracing::warn!("Failed to clear presence of user {user_id}: {e}");
+    }
+
+    tracing::info!("User {user_id} closed WebSocket connection {connection_id}");
+}
+
+async fn handle_client_message(
+    user_id: &str,
+    subscribed_types: &mut HashSet<NotifType>,
+    message: ClientMessage,
+) -> Vec<ServerMessage> {
+    match message {
+        ClientMessage::Subscribe { types } | ClientMessage::Unsubscribe { types }
+            if types
+                .iter()
+                .any(|notif_type| NotifType::from_str(notif_type).is_err()) =>
+        {
+            vec![ServerMessage::Error {
+                message: format!("Unknown notification type in {types:?}"),
+            }]
+        }
+        ClientMessage::Subscribe { types } => {
+            subscribed_types.extend(types.iter().filter_map(|t| NotifType::from_str(t).ok()));
+
+            let mut replies = vec![subscribed_message(subscribed_types)];
+            for notif_type in subscribed_types.iter() {
+                replies.extend(unread_count_message(user_id, &notif_type.to_string()).await);
+            }
+            replies
+        }
+        ClientMessage::Unsubscribe { types } => {
+            for notif_type in types.iter().filter_map(|t| NotifType::from_str(t).ok()) {
+                subscribed_types.remove(&notif_type);
+            }
+
+            vec![subscribed_message(subscribed_types)]
+        }
+        ClientMessage::MarkAsRead { id } => match mark_as_read(user_id, &id).await {
+            Ok(notification) => unread_count_message(user_id, &notification.r#type)
+                .await
+                .into_iter()
+                .collect(),
+            Err(e) => vec![ServerMessage::Error {
+                message: e.to_string(),
+            }],
+        },
+        ClientMessage::Ack { id } => {
+            tracing::debug!("User {user_id} acknowledged notification {id}");
+            vec![ServerMessage::Acked { id }]
+        }
+        ClientMessage::Ping => vec![ServerMessage::Pong],
+    }
+}
+
+async fn mark_as_read(user_id: &str, notif_id: &str) -> Result<UserNotification, Error> {
+    let oid = ObjectId::parse_str(notif_id)
+        .map_err(|_| Error::bad_request("Invalid notification ID format"))?;
+
+    let filter = doc! {
+        "_id": oid,
+        "userId": user_id,
+    };
+    let update = doc! {
+        "$set": {
+            "isRead": true,
+            "updatedAt": DateTime::now(),
+        }
+    };
+
+    UserNotification::find_one_and_update(filter, update, false)
+        .await?
+        .ok_or_else(|| Error::not_found("Notification not found"))
+}
+
+fn is_subscribed(subscribed_types: &HashSet<NotifType>, notif_type: &str) -> bool {
+    subscribed_types.is_empty()
+        || NotifType::from_str(notif_type).is_ok_and(|t| subscribed_types.contains(&t))
+}
+
+fn subscribed_message(subscribed_types: &HashSet<NotifType>) -> ServerMessage {
+    ServerMessage::Subscribed {
+        types: subscribed_types.iter().map(ToString::to_string).collect(),
+    }
+}
+
+async fn unread_count_message(user_id: &str, notif_type: &str) -> Option<ServerMessage> {
+    match UserNotification::count_unread(user_id, notif_type).await {
+        Ok(count) => Some(ServerMessage::UnreadCount {
+            notif_type: notif_type.to_string(),
+            count,
+        }),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to count unread {notif_type} notifications of user {user_id}: {e}"
+            );
+            None
+        }
+    }
+}
+
+/// Returns `false` once the connection is gone.
+async fn send_messages(socket: &mut WebSocket, messages: Vec<ServerMessage>) -> bool {
+    for message in messages {
+        let text = match serde_json::to_string(&message) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::error!("Failed to serialize WebSocket message: {e}");
+                continue;
+            }
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            return false;
+        }
+    }
+
+    true
+}
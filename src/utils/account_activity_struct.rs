@@ -2,6 +2,9 @@ use std::fmt::{Display, Formatter};
 
 use serde::{Deserialize, Serialize};
 
+use crate::config::APP_CONFIG;
+use crate::models::accounts::ELanguage;
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum AccountNotifType {
     Kyc(KycAction),
@@ -45,6 +48,72 @@ impl Display for AccountNotifType {
     }
 }
 
+impl AccountNotifType {
+    /// Changes that weaken account protection and must also reach the user by email.
+    pub fn is_security_event(&self) -> bool {
+        matches!(
+            self,
+            AccountNotifType::Password(PasswordAction::Change | PasswordAction::Reset)
+                | AccountNotifType::Mfa(MfaAction::Disabled)
+                | AccountNotifType::Whitelisting(_)
+        )
+    }
+
+    /// Listed in `mandatory_account_events`, so it ignores preferences and
+    /// rate limits.
+    pub fn is_mandatory(&self) -> bool {
+        let event_type = self.config_key();
+        APP_CONFIG
+            .mandatory_account_events
+            .iter()
+            .any(|mandatory| mandatory.trim() == event_type)
+    }
+
+    /// Short description of the activity in `lang`, e.g. "change password".
+    pub fn describe(&self, lang: ELanguage) -> String {
+        match lang {
+            ELanguage::en => self.to_string(),
+            ELanguage::vi => match self {
+                AccountNotifType::Kyc(action) => match action {
+                    KycAction::Approved => "xác minh KYC",
+                    KycAction::Upgraded => "nâng cấp KYC",
+                },
+                AccountNotifType::Whitelisting(action) => match action {
+                    WhitelistingAction::Enabled => "bật danh sách trắng địa chỉ rút tiền",
+                    WhitelistingAction::Disabled => "tắt danh sách trắng địa chỉ rút tiền",
+                    WhitelistingAction::Added => "thêm địa chỉ rút tiền vào danh sách trắng",
+                    WhitelistingAction::Removed => "xóa địa chỉ rút tiền khỏi danh sách trắng",
+                },
+                AccountNotifType::Account(action) => match action {
+                    AccountAction::Disabled => "vô hiệu hóa tài khoản",
+                    AccountAction::Deleted => "xóa tài khoản",
+                },
+                AccountNotifType::Mfa(action) => match action {
+                    MfaAction::Enabled => "bật xác thực hai yếu tố",
+                    MfaAction::Disabled => "tắt xác thực hai yếu tố",
+                },
+                AccountNotifType::Password(action) => match action {
+                    PasswordAction::Initialized => "thiết lập mật khẩu",
+                    PasswordAction::Change => "đổi mật khẩu",
+                    PasswordAction::Reset => "đặt lại mật khẩu",
+                },
+            }
+            .to_string(),
+        }
+    }
+
+    /// `Category:Action` name used to list event types in configuration.
+    pub fn config_key(&self) -> String {
+        match self {
+            AccountNotifType::Kyc(action) => format!("Kyc:{action:?}"),
+            AccountNotifType::Whitelisting(action) => format!("Whitelisting:{action:?}"),
+            AccountNotifType::Account(action) => format!("Account:{action:?}"),
+            AccountNotifType::Mfa(action) => format!("Mfa:{action:?}"),
+            AccountNotifType::Password(action) => format!("Password:{action:?}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum KycAction {
     Approved,
@@ -92,9 +161,14 @@ pub struct AccountNotifData {
 }
 
 impl AccountNotifData {
-    pub fn construct_message(&self) -> String {
-        let time = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    pub fn construct_message(&self, lang: ELanguage, time: &str) -> String {
+        match lang {
+            ELanguage::en => self.construct_message_en(time),
+            ELanguage::vi => self.construct_message_vi(time),
+        }
+    }
 
+    fn construct_message_en(&self, time: &str) -> String {
         match self.action_status {
             ActionStatus::Failed => format!(
                 "Your request to {} failed on {}. If you do not recognize this activity, please contact us immediately.",
@@ -153,4 +227,67 @@ impl AccountNotifData {
             },
         }
     }
+
+    fn construct_message_vi(&self, time: &str) -> String {
+        const CONTACT_US: &str =
+            "Nếu bạn không thực hiện hoạt động này, vui lòng liên hệ với chúng tôi ngay lập tức.";
+
+        match self.action_status {
+            ActionStatus::Failed => format!(
+                "Yêu cầu {} của bạn đã thất bại lúc {time}. {CONTACT_US}",
+                self.activity_type.describe(ELanguage::vi)
+            ),
+            ActionStatus::Success => match self.activity_type {
+                AccountNotifType::Kyc(action) => match action {
+                    KycAction::Approved => {
+                        format!("Xác minh danh tính của bạn đã được phê duyệt lúc {time}.")
+                    }
+                    KycAction::Upgraded => {
+                        format!("Cấp độ xác minh của bạn đã được nâng cấp lúc {time}.")
+                    }
+                },
+                AccountNotifType::Whitelisting(action) => match action {
+                    WhitelistingAction::Enabled => {
+                        format!("Danh sách trắng địa chỉ rút tiền đã được bật lúc {time}.")
+                    }
+                    WhitelistingAction::Disabled => {
+                        format!("Danh sách trắng địa chỉ rút tiền đã bị tắt lúc {time}.")
+                    }
+                    WhitelistingAction::Added => format!(
+                        "Một địa chỉ rút tiền mới đã được thêm vào danh sách trắng của bạn lúc {time}."
+                    ),
+                    WhitelistingAction::Removed => format!(
+                        "Một địa chỉ rút tiền đã bị xóa khỏi danh sách trắng của bạn lúc {time}."
+                    ),
+                },
+                AccountNotifType::Account(action) => match action {
+                    AccountAction::Disabled => {
+                        format!("Tài khoản của bạn đã bị vô hiệu hóa lúc {time}. {CONTACT_US}")
+                    }
+                    AccountAction::Deleted => format!(
+                        "Tài khoản của bạn đã bị xóa vĩnh viễn lúc {time}. Toàn bộ dữ liệu đã được xóa theo yêu cầu."
+                    ),
+                },
+                AccountNotifType::Mfa(action) => match action {
+                    MfaAction::Enabled => {
+                        format!("Xác thực hai yếu tố đã được bật lúc {time}.")
+                    }
+                    MfaAction::Disabled => {
+                        format!("Xác thực hai yếu tố đã bị tắt lúc {time}. {CONTACT_US}")
+                    }
+                },
+                AccountNotifType::Password(action) => match action {
+                    PasswordAction::Initialized => format!(
+                        "Mật khẩu tài khoản của bạn đã được thiết lập lúc {time}. Tài khoản của bạn đã sẵn sàng để sử dụng."
+                    ),
+                    PasswordAction::Change => {
+                        format!("Mật khẩu của bạn đã được thay đổi lúc {time}. {CONTACT_US}")
+                    }
+                    PasswordAction::Reset => {
+                        format!("Mật khẩu của bạn đã được đặt lại lúc {time}. {CONTACT_US}")
+                    }
+                },
+            },
+        }
+    }
 }
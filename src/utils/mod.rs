@@ -3,6 +3,7 @@ pub mod extractor;
 pub mod models;
 pub mod notification;
 pub mod pagination;
+pub mod segment;
 pub mod sorting;
 pub mod structs;
 pub mod tracing;
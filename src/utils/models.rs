@@ -11,9 +11,11 @@ use wither::bson::doc;
 use wither::bson::from_bson;
 use wither::bson::{self, oid::ObjectId};
 use wither::mongodb::Database;
+use wither::mongodb::IndexModel;
 use wither::mongodb::options::FindOneAndUpdateOptions;
 use wither::mongodb::options::FindOneOptions;
 use wither::mongodb::options::FindOptions;
+use wither::mongodb::options::IndexOptions;
 use wither::mongodb::options::ReturnDocument;
 use wither::mongodb::options::UpdateOptions;
 use wither::mongodb::results::DeleteResult;
@@ -44,6 +46,59 @@ where
         Ok(model)
     }
 
+    /// Validates and inserts all models with one write, returning them with
+    /// their ids set.
+    async fn insert_many(mut models: Vec<Self>) -> Result<Vec<Self>, Error> {
+        if models.is_empty() {
+            return Ok(models);
+        }
+
+        let connection = Self::get_connection().await;
+        let documents = models
+            .iter()
+            .map(|model| {
+                model
+                    .validate()
+                    .map_err(|e| Error::bad_request(&format!("Validation db error: {e:?}")))?;
+                model.document_from_instance().map_err(Error::Wither)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let result = Self::collection(connection)
+            .insert_many(documents)
+            .await
+            .map_err(Error::Mongo)?;
+
+        for (index, id) in result.inserted_ids {
+            if let (Some(model), Bson::ObjectId(id)) = (models.get_mut(index), id) {
+                model.set_id(id);
+            }
+        }
+
+        Ok(models)
+    }
+
+    /// Creates a unique index on `keys`, covering only the documents matching
+    /// `partial_filter` when given; a no-op when it already exists.
+    async fn create_unique_index(
+        keys: Document,
+        partial_filter: Option<Document>,
+    ) -> Result<(), Error> {
+        let connection = Self::get_connection().await;
+        let options = IndexOptions::builder()
+            .unique(true)
+            .partial_filter_expression(partial_filter)
+            .build();
+        let index = IndexModel::builder().keys(keys).options(options).build();
+
+        Self::collection(connection)
+            .create_index(index)
+            .await
+            .map_err(Error::Mongo)?;
+
+        Ok(())
+    }
+
     async fn find_by_id(id: &ObjectId) -> Result<Option<Self>, Error> {
         let connection = Self::get_connection().await;
         <Self as WitherModel>::find_one(connection, doc! { "_id": id }, None)
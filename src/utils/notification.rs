@@ -1,6 +1,9 @@
 use crate::errors::Error;
+use crate::loading_languages::get_user_languages_batch;
 use crate::loading_preferences::get_user_notification_preferences_batch;
-use crate::utils::structs::{NotifMessage, NotifType};
+use crate::loading_templates::render_notification;
+use crate::models::accounts::ELanguage;
+use crate::utils::structs::{NotifMessage, NotifMetadata, NotifType, format_event_time};
 use std::collections::{HashMap, HashSet};
 
 #[derive(Eq, Hash, PartialEq, Debug)]
@@ -11,8 +14,13 @@ pub struct NotifKey {
 }
 
 pub struct NotificationWithTimestamp {
+    pub title: String,
     pub message: String,
     pub timestamp: i64,
+    pub metadata: NotifMetadata,
+    pub source_event_id: String,
+    /// Language the title and message were rendered in.
+    pub lang: ELanguage,
 }
 
 pub async fn group_by_user_id(
@@ -24,6 +32,7 @@ pub async fn group_by_user_id(
         notif_message.iter().map(|n| n.user_id.clone()).collect();
     let user_ids_vec: Vec<String> = unique_user_ids.into_iter().collect();
 
+    let languages_map = get_user_languages_batch(&user_ids_vec).await;
     let preferences_map = get_user_notification_preferences_batch(user_ids_vec)
         .await
         .map_err(|e| Error::internal_err(&format!("Failed to batch load preferences: {}", e)))?;
@@ -46,6 +55,9 @@ pub async fn group_by_user_id(
                 account: true,
                 campaign: true,
                 transaction: true,
+                price_alert: true,
+                language: None,
+                timezone: None,
             }
         });
 
@@ -59,24 +71,38 @@ pub async fn group_by_user_id(
             notif_type
         );
 
-        let message = match notif.metadata.construct_message() {
-            Ok(message) => message,
+        let lang = preference
+            .language
+            .or_else(|| languages_map.get(&user_id).copied())
+            .unwrap_or_default();
+
+        let time = format_event_time(timestamp, preference.timezone);
+
+        let rendered = match render_notification(notif_type, &notif.metadata, lang, &time).await {
+            Ok(rendered) => rendered,
             Err(e) => {
                 tracing::warn!("Skipping notification for user {user_id} with error: {e}");
                 continue;
             }
         };
 
-        if preference.contains(notif_type) {
+        if preference.contains(notif_type) || notif.metadata.is_mandatory() {
             tracing::debug!(
-                "Notification type {:?} is enabled for user {}, adding to grouped notifications",
+                "Notification type {:?} is enabled or mandatory for user {}, adding to grouped notifications",
                 notif_type,
                 user_id
             );
             grouped
                 .entry(key)
                 .or_default()
-                .push(NotificationWithTimestamp { message, timestamp });
+                .push(NotificationWithTimestamp {
+                    title: rendered.title,
+                    message: rendered.body,
+                    timestamp,
+                    source_event_id: notif.source_event_id(),
+                    metadata: notif.metadata,
+                    lang,
+                });
         } else {
             tracing::info!(
                 "Notification type {:?} is DISABLED for user {}, skipping notification",
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use wither::bson::{self, Document, doc};
+
+use crate::models::accounts::ELanguage;
+
+/// Subset of users selected by their `Account`. Every condition that is set
+/// must hold; an empty segment selects every user.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AudienceSegment {
+    /// Account languages to include, all of them when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<ELanguage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_wallet: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_order: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joined_after: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joined_before: Option<DateTime<Utc>>,
+    /// Leaves out users who have not logged in since.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_login_after: Option<DateTime<Utc>>,
+}
+
+impl AudienceSegment {
+    /// Query over the `Account` collection selecting the segment. System
+    /// accounts are never part of an audience.
+    pub fn account_filter(&self) -> Document {
+        let mut filter = doc! { "isSystem": false };
+
+        if !self.languages.is_empty() {
+            let languages = self
+                .languages
+                .iter()
+                .map(|language| language.to_string())
+                .collect::<Vec<_>>();
+            filter.insert("lang", doc! { "$in": languages });
+        }
+        if let Some(has_wallet) = self.has_wallet {
+            filter.insert("hasWallet", has_wallet);
+        }
+        if let Some(has_order) = self.has_order {
+            filter.insert("hasOrder", has_order);
+        }
+
+        let mut joined_at = doc! {};
+        if let Some(after) = self.joined_after {
+            joined_at.insert("$gte", bson::DateTime::from_chrono(after));
+        }
+        if let Some(before) = self.joined_before {
+            joined_at.insert("$lt", bson::DateTime::from_chrono(before));
+        }
+        if !joined_at.is_empty() {
+            filter.insert("joinedAt", joined_at);
+        }
+        if let Some(after) = self.last_login_after {
+            filter.insert(
+                "lastLoginAt",
+                doc! { "$gte": bson::DateTime::from_chrono(after) },
+            );
+        }
+
+        filter
+    }
+}
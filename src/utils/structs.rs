@@ -1,5 +1,11 @@
+use crate::config::APP_CONFIG;
 use crate::constants::TradingType;
+use crate::enums::PriceAlertCondition;
+use crate::models::accounts::ELanguage;
 use crate::utils::account_activity_struct::AccountNotifData;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
@@ -22,6 +28,226 @@ pub struct TransactionNotifData {
 pub struct OrderNotifData {
     pub order_id: u64,
     pub status: String,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// `BUY` or `SELL`.
+    #[serde(default)]
+    pub side: Option<String>,
+    /// e.g. `LIMIT`, `MARKET`, `STOP_LIMIT` or `TAKE_PROFIT`.
+    #[serde(default)]
+    pub order_type: Option<String>,
+    #[serde(default)]
+    pub price: Option<Decimal>,
+    #[serde(default)]
+    pub quantity: Option<Decimal>,
+    #[serde(default)]
+    pub filled_quantity: Option<Decimal>,
+    #[serde(default)]
+    pub average_price: Option<Decimal>,
+}
+
+impl OrderNotifData {
+    fn construct_message(&self, lang: ELanguage) -> anyhow::Result<String> {
+        let order_id = self.order_id;
+        let label = self
+            .describe(lang)
+            .map(|description| format!(" ({description})"))
+            .unwrap_or_default();
+        let fills = self.fill_details(lang);
+
+        let message = match (self.status.as_str(), lang) {
+            ("NEW", ELanguage::en) => format!("Order {order_id}{label} placed successfully."),
+            ("NEW", ELanguage::vi) => format!("Lệnh {order_id}{label} đã được đặt thành công."),
+            ("PARTIALLY_FILLED", ELanguage::en) => {
+                format!("Order {order_id}{label} partially filled.{fills}")
+            }
+            ("PARTIALLY_FILLED", ELanguage::vi) => {
+                format!("Lệnh {order_id}{label} đã được khớp một phần.{fills}")
+            }
+            ("FILLED", ELanguage::en) => format!("Order {order_id}{label} matched.{fills}"),
+            ("FILLED", ELanguage::vi) => format!("Lệnh {order_id}{label} đã được khớp.{fills}"),
+            ("CANCELLED", ELanguage::en) => format!("Order {order_id}{label} cancelled.{fills}"),
+            ("CANCELLED", ELanguage::vi) => format!("Lệnh {order_id}{label} đã bị hủy.{fills}"),
+            ("EXPIRED", ELanguage::en) => format!("Order {order_id}{label} expired.{fills}"),
+            ("EXPIRED", ELanguage::vi) => format!("Lệnh {order_id}{label} đã hết hạn.{fills}"),
+            ("REJECTED", ELanguage::en) => format!("Order {order_id}{label} rejected."),
+            ("REJECTED", ELanguage::vi) => format!("Lệnh {order_id}{label} đã bị từ chối."),
+            ("TRIGGERED", ELanguage::en) => format!("Order {order_id}{label} triggered."),
+            ("TRIGGERED", ELanguage::vi) => format!("Lệnh {order_id}{label} đã được kích hoạt."),
+            ("AMENDED", ELanguage::en) => format!("Order {order_id} amended{label}."),
+            ("AMENDED", ELanguage::vi) => format!("Lệnh {order_id} đã được sửa{label}."),
+            (status, _) => {
+                return Err(anyhow::anyhow!(
+                    "Skipping notification for order {order_id} with unsupported status: {status}"
+                ));
+            }
+        };
+
+        Ok(message)
+    }
+
+    /// Side, type, size and price of the order as far as they are known, e.g.
+    /// "buy limit 1.5 BTCUSDT at 60000".
+    fn describe(&self, lang: ELanguage) -> Option<String> {
+        let side = self.side.as_deref().map(|side| match (side, lang) {
+            ("BUY", ELanguage::vi) => "mua".to_string(),
+            ("SELL", ELanguage::vi) => "bán".to_string(),
+            (side, _) => side.to_lowercase(),
+        });
+        let order_type = self
+            .order_type
+            .as_deref()
+            .map(|order_type| order_type.to_lowercase().replace('_', " "));
+
+        let mut description = [
+            side,
+            order_type,
+            self.quantity.map(format_decimal),
+            self.symbol.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+        if let Some(price) = self.price {
+            let at = match lang {
+                ELanguage::en => "at",
+                ELanguage::vi => "giá",
+            };
+            description = format!("{description} {at} {}", format_decimal(price));
+        }
+
+        let description = description.trim().to_string();
+        (!description.is_empty()).then_some(description)
+    }
+
+    /// Sentence about the filled size, empty when nothing was filled.
+    fn fill_details(&self, lang: ELanguage) -> String {
+        let Some(filled) = self.filled_quantity.filter(|filled| !filled.is_zero()) else {
+            return String::new();
+        };
+
+        let filled = match self.quantity {
+            Some(quantity) => format!("{}/{}", format_decimal(filled), format_decimal(quantity)),
+            None => format_decimal(filled),
+        };
+
+        match (lang, self.average_price) {
+            (ELanguage::en, Some(average_price)) => format!(
+                " Filled {filled} at an average price of {}.",
+                format_decimal(average_price)
+            ),
+            (ELanguage::en, None) => format!(" Filled {filled}."),
+            (ELanguage::vi, Some(average_price)) => format!(
+                " Đã khớp {filled} với giá trung bình {}.",
+                format_decimal(average_price)
+            ),
+            (ELanguage::vi, None) => format!(" Đã khớp {filled}."),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceAlertNotifData {
+    pub alert_id: String,
+    pub symbol: String,
+    pub condition: PriceAlertCondition,
+    /// Threshold the price crossed.
+    pub threshold: Decimal,
+    #[serde(default)]
+    pub percent: Option<Decimal>,
+    #[serde(default)]
+    pub reference_price: Option<Decimal>,
+    /// Price that crossed the threshold.
+    pub price: Decimal,
+}
+
+impl PriceAlertNotifData {
+    fn construct_message(&self, lang: ELanguage) -> String {
+        let symbol = &self.symbol;
+        let threshold = format_decimal(self.threshold);
+        let price = format_decimal(self.price);
+        let rising = self.price >= self.threshold;
+
+        match (self.condition, lang) {
+            (PriceAlertCondition::Above, ELanguage::en) => {
+                format!("{symbol} rose above {threshold} and is now at {price}.")
+            }
+            (PriceAlertCondition::Above, ELanguage::vi) => {
+                format!("{symbol} đã vượt lên trên {threshold}, giá hiện tại là {price}.")
+            }
+            (PriceAlertCondition::Below, ELanguage::en) => {
+                format!("{symbol} fell below {threshold} and is now at {price}.")
+            }
+            (PriceAlertCondition::Below, ELanguage::vi) => {
+                format!("{symbol} đã giảm xuống dưới {threshold}, giá hiện tại là {price}.")
+            }
+            (PriceAlertCondition::PercentChange, lang) => {
+                let percent = self.percent.map(format_decimal).unwrap_or_default();
+                let reference = self
+                    .reference_price
+                    .map(format_decimal)
+                    .unwrap_or_else(|| threshold.clone());
+                match (lang, rising) {
+                    (ELanguage::en, true) => {
+                        format!("{symbol} rose {percent}% from {reference} to {price}.")
+                    }
+                    (ELanguage::en, false) => {
+                        format!("{symbol} fell {percent}% from {reference} to {price}.")
+                    }
+                    (ELanguage::vi, true) => {
+                        format!("{symbol} đã tăng {percent}% từ {reference} lên {price}.")
+                    }
+                    (ELanguage::vi, false) => {
+                        format!("{symbol} đã giảm {percent}% từ {reference} xuống {price}.")
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Text of an announcement, written by an operator rather than rendered.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementNotifData {
+    pub announcement_id: String,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub image_url: Option<String>,
+    /// App path the notification opens, the announcement itself when unset.
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
+/// Content of a marketing campaign.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CampaignNotifData {
+    pub campaign_id: String,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub image_url: Option<String>,
+    /// App path the notification opens, the campaign itself when unset.
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
+fn format_decimal(value: Decimal) -> String {
+    value.normalize().to_string()
+}
+
+/// Last trade price of a market, as published on the price topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceTick {
+    pub symbol: String,
+    pub price: Decimal,
+    /// Milliseconds since the epoch.
+    pub timestamp: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +256,52 @@ pub struct NotifMessage {
     pub notif_type: NotifType,
     pub timestamp: i64,
     pub metadata: NotifMetadata,
+    /// Id the producer gave the event; older producers do not send one.
+    #[serde(default)]
+    pub event_id: Option<String>,
+}
+
+impl NotifMessage {
+    /// The producer's event id, or one derived from the event itself so a
+    /// replayed message maps to the same id.
+    pub fn source_event_id(&self) -> String {
+        if let Some(event_id) = &self.event_id {
+            return event_id.clone();
+        }
+
+        match &self.metadata {
+            // Partial fills repeat the status, the filled size tells them apart.
+            NotifMetadata::Order(order_data) => match order_data.filled_quantity {
+                Some(filled) => format!(
+                    "order:{}:{}:{}",
+                    order_data.order_id,
+                    order_data.status,
+                    filled.normalize()
+                ),
+                None => format!("order:{}:{}", order_data.order_id, order_data.status),
+            },
+            NotifMetadata::Transaction(transaction_data) => format!(
+                "transaction:{}:{}",
+                transaction_data.id, transaction_data.status
+            ),
+            NotifMetadata::Account(account_data) => format!(
+                "account:{}:{}:{}",
+                self.user_id,
+                account_data.activity_type.config_key(),
+                self.timestamp
+            ),
+            NotifMetadata::PriceAlert(price_alert_data) => format!(
+                "price_alert:{}:{}",
+                price_alert_data.alert_id, self.timestamp
+            ),
+            NotifMetadata::Announcement(announcement_data) => {
+                format!("announcement:{}", announcement_data.announcement_id)
+            }
+            NotifMetadata::Campaign(campaign_data) => {
+                format!("campaign:{}", campaign_data.campaign_id)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,30 +309,49 @@ pub enum NotifMetadata {
     Order(OrderNotifData),
     Transaction(TransactionNotifData),
     Account(AccountNotifData),
+    PriceAlert(PriceAlertNotifData),
+    Announcement(AnnouncementNotifData),
+    Campaign(CampaignNotifData),
 }
 
 impl NotifMetadata {
-    pub fn construct_message(&self) -> anyhow::Result<String> {
+    /// Title the event brings itself, used instead of the built-in one.
+    pub fn title(&self) -> Option<String> {
         match self {
-            NotifMetadata::Order(order_data) => {
-                let order_id = order_data.order_id.to_string();
-                let status = order_data.status.clone();
-
-                match status.as_str() {
-                    "NEW" => Ok(format!("Order {order_id} placed successfully.")),
-                    "FILLED" => Ok(format!("Order {order_id} matched.")),
-                    "CANCELLED" => Ok(format!("Order {order_id} cancelled.")),
-                    "REJECTED" => Ok(format!("Order {order_id} rejected.")),
-                    _ => Err(anyhow::anyhow!(
-                        "Skipping notification for order {order_id} with unsupported status: {status}"
-                    )),
-                }
-            }
+            NotifMetadata::Announcement(announcement_data) => Some(announcement_data.title.clone()),
+            NotifMetadata::Campaign(campaign_data) => Some(campaign_data.title.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn image_url(&self) -> Option<String> {
+        match self {
+            NotifMetadata::Announcement(announcement_data) => announcement_data.image_url.clone(),
+            NotifMetadata::Campaign(campaign_data) => campaign_data.image_url.clone(),
+            _ => None,
+        }
+    }
+
+    /// Security events the user cannot opt out of.
+    pub fn is_mandatory(&self) -> bool {
+        match self {
+            NotifMetadata::Account(account_data) => account_data.activity_type.is_mandatory(),
+            _ => false,
+        }
+    }
+
+    /// Built-in text for the event; `time` is the formatted event time.
+    pub fn construct_message(&self, lang: ELanguage, time: &str) -> anyhow::Result<String> {
+        match self {
+            NotifMetadata::Order(order_data) => order_data.construct_message(lang),
             NotifMetadata::Transaction(transaction_data) => {
                 let r#type = transaction_data.r#type;
                 let amount = transaction_data.amount.to_string();
                 let asset = transaction_data.asset.clone();
-                let time = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+                if lang == ELanguage::vi {
+                    return construct_transaction_message_vi(transaction_data, time);
+                }
 
                 match transaction_data.status.as_str() {
                     "COMPLETED" => match r#type {
@@ -91,7 +382,128 @@ impl NotifMetadata {
                     )),
                 }
             }
-            NotifMetadata::Account(account_data) => Ok(account_data.construct_message()),
+            NotifMetadata::Account(account_data) => Ok(account_data.construct_message(lang, time)),
+            NotifMetadata::PriceAlert(price_alert_data) => {
+                Ok(price_alert_data.construct_message(lang))
+            }
+            NotifMetadata::Announcement(announcement_data) => Ok(announcement_data.body.clone()),
+            NotifMetadata::Campaign(campaign_data) => Ok(campaign_data.body.clone()),
+        }
+    }
+
+    /// Path of the app screen showing this event, relative to the deep link base.
+    pub fn deep_link_path(&self) -> String {
+        match self {
+            NotifMetadata::Order(order_data) => format!("orders/{}", order_data.order_id),
+            NotifMetadata::Transaction(transaction_data) if transaction_data.tx_hash.is_empty() => {
+                format!("transactions/{}", transaction_data.id)
+            }
+            NotifMetadata::Transaction(transaction_data) => {
+                format!("transactions/{}", transaction_data.tx_hash)
+            }
+            NotifMetadata::Account(_) => "account/security".to_string(),
+            NotifMetadata::PriceAlert(price_alert_data) => {
+                format!("markets/{}", price_alert_data.symbol)
+            }
+            NotifMetadata::Announcement(announcement_data) => announcement_data
+                .link
+                .clone()
+                .unwrap_or_else(|| format!("announcements/{}", announcement_data.announcement_id)),
+            NotifMetadata::Campaign(campaign_data) => campaign_data
+                .link
+                .clone()
+                .unwrap_or_else(|| format!("campaigns/{}", campaign_data.campaign_id)),
+        }
+    }
+
+    /// Template variants matching this event, most specific first.
+    pub fn template_variants(&self) -> Vec<String> {
+        let mut variants = match self {
+            NotifMetadata::Order(order_data) => vec![order_data.status.clone()],
+            NotifMetadata::Transaction(transaction_data) => vec![
+                format!("{}:{}", transaction_data.status, transaction_data.r#type),
+                transaction_data.status.clone(),
+            ],
+            NotifMetadata::Account(account_data) => vec![
+                format!(
+                    "{}:{:?}",
+                    account_data.activity_type.config_key(),
+                    account_data.action_status
+                ),
+                format!("{:?}", account_data.action_status),
+            ],
+            NotifMetadata::PriceAlert(price_alert_data) => {
+                vec![price_alert_data.condition.to_string()]
+            }
+            NotifMetadata::Announcement(_) | NotifMetadata::Campaign(_) => Vec::new(),
+        };
+        variants.push("*".to_string());
+        variants
+    }
+
+    /// Variables available to templates rendering this event; `activity` is
+    /// described in `lang` and `time` is the formatted event time.
+    pub fn template_context(&self, lang: ELanguage, time: &str) -> serde_json::Value {
+        match self {
+            NotifMetadata::Order(order_data) => serde_json::json!({
+                "order_id": order_data.order_id,
+                "status": order_data.status,
+                "symbol": order_data.symbol,
+                "side": order_data.side,
+                "order_type": order_data.order_type,
+                "price": order_data.price.map(format_decimal),
+                "quantity": order_data.quantity.map(format_decimal),
+                "filled_quantity": order_data.filled_quantity.map(format_decimal),
+                "average_price": order_data.average_price.map(format_decimal),
+                "time": time,
+            }),
+            NotifMetadata::Transaction(transaction_data) => serde_json::json!({
+                "id": transaction_data.id,
+                "asset": transaction_data.asset,
+                "network_id": transaction_data.network_id,
+                "tx_hash": transaction_data.tx_hash,
+                "type": transaction_data.r#type.to_string(),
+                "amount": transaction_data.amount,
+                "status": transaction_data.status,
+                "time": time,
+            }),
+            NotifMetadata::Account(account_data) => {
+                let event = account_data.activity_type.config_key();
+                let (category, action) = event.split_once(':').unwrap_or((event.as_str(), ""));
+
+                serde_json::json!({
+                    "activity": account_data.activity_type.describe(lang),
+                    "category": category,
+                    "action": action,
+                    "status": format!("{:?}", account_data.action_status),
+                    "time": time,
+                })
+            }
+            NotifMetadata::PriceAlert(price_alert_data) => serde_json::json!({
+                "alert_id": price_alert_data.alert_id,
+                "symbol": price_alert_data.symbol,
+                "condition": price_alert_data.condition.to_string(),
+                "threshold": format_decimal(price_alert_data.threshold),
+                "percent": price_alert_data.percent.map(format_decimal),
+                "reference_price": price_alert_data.reference_price.map(format_decimal),
+                "price": format_decimal(price_alert_data.price),
+                "direction": if price_alert_data.price >= price_alert_data.threshold { "UP" } else { "DOWN" },
+                "time": time,
+            }),
+            NotifMetadata::Announcement(announcement_data) => serde_json::json!({
+                "announcement_id": announcement_data.announcement_id,
+                "title": announcement_data.title,
+                "body": announcement_data.body,
+                "image_url": announcement_data.image_url,
+                "time": time,
+            }),
+            NotifMetadata::Campaign(campaign_data) => serde_json::json!({
+                "campaign_id": campaign_data.campaign_id,
+                "title": campaign_data.title,
+                "body": campaign_data.body,
+                "image_url": campaign_data.image_url,
+                "time": time,
+            }),
         }
     }
 }
@@ -103,6 +515,7 @@ pub enum NotifType {
     Account,
     Announcement,
     Campaign,
+    PriceAlert,
 }
 
 impl Display for NotifType {
@@ -113,6 +526,7 @@ impl Display for NotifType {
             NotifType::Account => write!(f, "ACCOUNT"),
             NotifType::Announcement => write!(f, "ANNOUNCEMENT"),
             NotifType::Campaign => write!(f, "CAMPAIGN"),
+            NotifType::PriceAlert => write!(f, "PRICE_ALERT"),
         }
     }
 }
@@ -127,20 +541,75 @@ impl FromStr for NotifType {
             "ACCOUNT" => Ok(NotifType::Account),
             "ANNOUNCEMENT" => Ok(NotifType::Announcement),
             "CAMPAIGN" => Ok(NotifType::Campaign),
+            "PRICE_ALERT" => Ok(NotifType::PriceAlert),
             _ => Err(anyhow::anyhow!("Invalid notification type")),
         }
     }
 }
 
+/// Formats an event time given in milliseconds in `timezone`, or in UTC, with
+/// the zone abbreviation, e.g. `2025-01-31 09:30:00 +07`.
+pub fn format_event_time(timestamp_ms: i64, timezone: Option<Tz>) -> String {
+    let time = DateTime::<Utc>::from_timestamp_millis(timestamp_ms).unwrap_or_else(Utc::now);
+
+    time.with_timezone(&timezone.unwrap_or(Tz::UTC))
+        .format("%Y-%m-%d %H:%M:%S %Z")
+        .to_string()
+}
+
+fn construct_transaction_message_vi(
+    transaction_data: &TransactionNotifData,
+    time: &str,
+) -> anyhow::Result<String> {
+    let amount = &transaction_data.amount;
+    let asset = &transaction_data.asset;
+
+    match transaction_data.status.as_str() {
+        "COMPLETED" => match transaction_data.r#type {
+            TradingType::Add => Ok(format!("Bạn đã nạp thành công {amount} {asset} lúc {time}")),
+            TradingType::Remove | TradingType::Buy | TradingType::Sell => Ok(format!(
+                "Bạn đã rút thành công {amount} {asset} lúc {time}. Nếu bạn không thực hiện giao dịch này, vui lòng liên hệ với chúng tôi ngay lập tức."
+            )),
+        },
+        "FAILED" | "REJECTED" => {
+            let kind = match transaction_data.r#type {
+                TradingType::Add => "nạp",
+                TradingType::Remove => "rút",
+                TradingType::Buy => "mua",
+                TradingType::Sell => "bán",
+            };
+            Ok(format!(
+                "Giao dịch {kind} {amount} {asset} của bạn đã thất bại lúc {time}."
+            ))
+        }
+        _ => Err(anyhow::anyhow!(
+            "Skipping notification for transaction with unsupported status: {}",
+            transaction_data.status
+        )),
+    }
+}
+
 impl NotifType {
-    pub fn construct_title(&self) -> String {
-        match self {
-            NotifType::Transaction => "Transaction Notification".to_string(),
-            NotifType::Order => "Order Notification".to_string(),
-            NotifType::Account => "Account Notification".to_string(),
-            NotifType::Announcement => "Announcement Notification".to_string(),
-            NotifType::Campaign => "Campaign Notification".to_string(),
+    pub fn construct_title(&self, lang: ELanguage) -> String {
+        match lang {
+            ELanguage::en => match self {
+                NotifType::Transaction => "Transaction Notification",
+                NotifType::Order => "Order Notification",
+                NotifType::Account => "Account Notification",
+                NotifType::Announcement => "Announcement Notification",
+                NotifType::Campaign => "Campaign Notification",
+                NotifType::PriceAlert => "Price Alert",
+            },
+            ELanguage::vi => match self {
+                NotifType::Transaction => "Thông báo giao dịch",
+                NotifType::Order => "Thông báo lệnh",
+                NotifType::Account => "Thông báo tài khoản",
+                NotifType::Announcement => "Thông báo",
+                NotifType::Campaign => "Thông báo chiến dịch",
+                NotifType::PriceAlert => "Cảnh báo giá",
+            },
         }
+        .to_string()
     }
 }
 
@@ -150,6 +619,15 @@ pub struct NotificationPreferences {
     pub account: bool,
     pub campaign: bool,
     pub transaction: bool,
+    #[serde(default = "default_price_alert")]
+    pub price_alert: bool,
+    /// Overrides the account language for notifications.
+    #[serde(default)]
+    pub language: Option<ELanguage>,
+    /// IANA timezone, e.g. `Asia/Ho_Chi_Minh`, times are shown in UTC without it.
+    #[serde(default)]
+    #[schema(value_type = Option<String>)]
+    pub timezone: Option<Tz>,
 }
 
 impl NotificationPreferences {
@@ -160,11 +638,64 @@ impl NotificationPreferences {
             NotifType::Account => self.account,
             NotifType::Announcement => self.announcement,
             NotifType::Campaign => self.campaign,
+            NotifType::PriceAlert => self.price_alert,
         }
     }
 }
 
+/// Preference fields that cannot fully turn their notifications off, because
+/// some of their events are mandatory.
+pub fn locked_preferences() -> Vec<String> {
+    if APP_CONFIG
+        .mandatory_account_events
+        .iter()
+        .any(|event| !event.trim().is_empty())
+    {
+        vec!["account".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Users created an alert on purpose, so they get it unless they opt out.
+pub fn default_price_alert() -> bool {
+    true
+}
+
 pub struct OrderNotifBuilder {
     pub user_id: String,
     pub message: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-01-31 02:30:00 UTC
+    const EVENT_TIME_MS: i64 = 1_738_290_600_000;
+
+    #[test]
+    fn formats_event_time_in_utc_without_timezone() {
+        assert_eq!(
+            format_event_time(EVENT_TIME_MS, None),
+            "2025-01-31 02:30:00 UTC"
+        );
+    }
+
+    #[test]
+    fn formats_event_time_in_the_user_timezone() {
+        assert_eq!(
+            format_event_time(EVENT_TIME_MS, Some(chrono_tz::Asia::Ho_Chi_Minh)),
+            "2025-01-31 09:30:00 +07"
+        );
+        assert_eq!(
+            format_event_time(EVENT_TIME_MS, Some(chrono_tz::America::New_York)),
+            "2025-01-30 21:30:00 EST"
+        );
+        // 2025-07-01 12:00:00 UTC, during daylight saving time.
+        assert_eq!(
+            format_event_time(1_751_371_200_000, Some(chrono_tz::America::New_York)),
+            "2025-07-01 08:00:00 EDT"
+        );
+    }
+}